@@ -0,0 +1,82 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::service::app_lock_service::AppLockService;
+use crate::service::auth_service::AuthService;
+use crate::service::settings_service::SettingsService;
+
+// 设置本机应用 PIN，需要已登录；修改已有 PIN 时需要当前 PIN 或有效的解锁令牌
+#[tauri::command]
+pub async fn set_app_pin(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    pin: String,
+    current_pin: Option<String>,
+    unlock_token: Option<String>,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    AppLockService::set_app_pin(&state.db, &state.app_lock, &pin, current_pin.as_deref(), unlock_token.as_deref())
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 校验 PIN 并返回解锁令牌；锁定界面时会话可能尚未恢复，因此不要求登录
+// 失败次数过多时暂时拒绝校验
+#[tauri::command]
+pub async fn verify_app_pin(
+    state: State<'_, Arc<AppState>>,
+    pin: String,
+) -> Result<String, String> {
+    AppLockService::verify_app_pin(&state.db, &state.app_lock, &pin)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 检查界面是否已解锁并刷新空闲计时，前端在显示界面前及用户操作时调用
+#[tauri::command]
+pub async fn check_app_unlock(
+    state: State<'_, Arc<AppState>>,
+    unlock_token: String,
+) -> Result<(), String> {
+    AppLockService::require_unlocked(&state.db, &state.app_lock, &unlock_token)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_app_lock_idle_secs(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<i64, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_app_lock_idle_secs(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 修改空闲锁定时间，已设置 PIN 时需要当前 PIN 或有效的解锁令牌
+#[tauri::command]
+pub async fn set_app_lock_idle_secs(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    secs: i64,
+    current_pin: Option<String>,
+    unlock_token: Option<String>,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    AppLockService::set_idle_secs(&state.db, &state.app_lock, secs, current_pin.as_deref(), unlock_token.as_deref())
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
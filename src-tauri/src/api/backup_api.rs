@@ -0,0 +1,92 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::service::app_lock_service::AppLockService;
+use crate::service::auth_service::AuthService;
+use crate::service::backup_service::{BackupImportResult, BackupService};
+use crate::service::snapshot_service::SnapshotService;
+
+#[tauri::command]
+pub async fn export_encrypted_backup(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    passphrase: String,
+    unlock_token: Option<String>,
+) -> Result<Vec<u8>, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    BackupService::export_encrypted(&state.db, &user.id, &passphrase)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 导入到当前登录用户
+#[tauri::command]
+pub async fn import_encrypted_backup(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    passphrase: String,
+    bytes: Vec<u8>,
+) -> Result<BackupImportResult, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let result = BackupService::import_encrypted(&state.db, &user.id, &passphrase, &bytes)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 唤醒同步循环推送导入的项目
+    if result.items_imported > 0 {
+        state.sync_notify.notify_one();
+    }
+    
+    Ok(result)
+}
+
+// 在批量操作前为当前用户的全部项目和标签创建快照，返回快照 ID
+#[tauri::command]
+pub async fn create_snapshot(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<String, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SnapshotService::create_snapshot(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 用快照替换当前的项目和标签，返回恢复的项目数
+#[tauri::command]
+pub async fn restore_snapshot(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let restored = SnapshotService::restore_snapshot(&state.db, &user.id, &id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 唤醒同步循环推送恢复和删除的项目
+    state.sync_notify.notify_one();
+    
+    Ok(restored)
+}
@@ -0,0 +1,56 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::entity::change::{ChangeFeed, ItemsChangedSince};
+use crate::service::app_lock_service::AppLockService;
+use crate::service::auth_service::AuthService;
+use crate::service::change_service::ChangeService;
+use crate::service::clipboard_service::ClipboardService;
+
+// 单次返回的最大变更数量
+const MAX_CHANGES_PER_PAGE: i64 = 500;
+
+#[tauri::command]
+pub async fn get_changes_since(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    seq: i64,
+    unlock_token: Option<String>,
+) -> Result<ChangeFeed, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ChangeService::get_changes_since(&state.db, &user.id, seq, MAX_CHANGES_PER_PAGE)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 获取某时间之后新增、修改和删除的项目，用于前端重新获得焦点时增量刷新
+#[tauri::command]
+pub async fn get_items_changed_since(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    since_ts: i64,
+    unlock_token: Option<String>,
+) -> Result<ItemsChangedSince, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::get_items_changed_since(&state.db, &user.id, since_ts)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
@@ -1,17 +1,29 @@
-use tauri::{State, AppHandle};
+use tauri::{State, AppHandle, Emitter};
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use crate::AppState;
-use crate::service::clipboard_service::ClipboardService;
+use crate::service::app_lock_service::AppLockService;
+use crate::service::clipboard_service::{ClipboardService, EncryptionResetResult};
 use crate::service::auth_service::AuthService;
-use crate::entity::clipboard_item::{ClipboardItem, ClipboardItemRequest, ClipboardItemUpdateRequest};
-use tauri_plugin_clipboard_manager::ClipboardExt;
+use crate::service::key_cache::KeyCache;
+use crate::service::stats_service::StatsService;
+use crate::service::task_registry::CLIPBOARD_MONITOR_TASK;
+use crate::entity::clipboard_item::{ClipboardItem, ClipboardItemPage, ClipboardItemRequest, ClipboardItemUpdateRequest, SearchPage};
+use crate::entity::item_format::{FormatRequest, ItemFormat};
+use crate::entity::provenance::{ItemProvenance, OriginReport};
+use crate::monitor::{self, ClipboardProvider};
+use crate::util::transform::Transform;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetClipboardItemsRequest {
     pub token: String,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    #[serde(default)]
+    pub reveal_sensitive: bool,
+    // 设置了应用 PIN 时需要有效的解锁令牌
+    #[serde(default)]
+    pub unlock_token: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,16 +31,23 @@ pub struct AddClipboardItemRequest {
     pub token: String,
     pub content: String,
     pub content_type: String,
-    pub encrypt: bool,
+    #[serde(default)]
+    pub encrypt: Option<bool>,
+    #[serde(default)]
+    pub is_sensitive: Option<bool>,
+    #[serde(default)]
+    pub alternate_formats: Vec<FormatRequest>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateClipboardItemRequest {
     pub token: String,
     pub id: String,
-    pub content: String,
-    pub content_type: String,
-    pub encrypt: bool,
+    pub content: Option<String>,
+    pub content_type: Option<String>,
+    pub encrypt: Option<bool>,
+    #[serde(default)]
+    pub is_sensitive: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,40 +56,88 @@ pub struct DeleteClipboardItemRequest {
     pub id: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetItemsBySourceRequest {
+    pub token: String,
+    pub source_app: String,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    // 设置了应用 PIN 时需要有效的解锁令牌
+    #[serde(default)]
+    pub unlock_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetItemsByOriginRequest {
+    pub token: String,
+    pub device_id: String,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    // 设置了应用 PIN 时需要有效的解锁令牌
+    #[serde(default)]
+    pub unlock_token: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchClipboardItemsRequest {
     pub token: String,
     pub query: String,
+    #[serde(default)]
+    pub include_notes: bool,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    // 设置了应用 PIN 时需要有效的解锁令牌
+    #[serde(default)]
+    pub unlock_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagMatchingRequest {
+    pub token: String,
+    pub query: String,
+    pub tag: String,
+    #[serde(default)]
+    pub include_notes: bool,
+    // 匹配数量较多时需要确认
+    #[serde(default)]
+    pub confirm: bool,
 }
 
 #[tauri::command]
 pub async fn get_clipboard_items(
     state: State<'_, Arc<AppState>>,
     request: GetClipboardItemsRequest,
-) -> Result<Vec<ClipboardItem>, String> {
+) -> Result<ClipboardItemPage, String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
-    // 获取剪贴板项目
-    let limit = request.limit.unwrap_or(50);
-    let offset = request.offset.unwrap_or(0);
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, &request.unlock_token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
     
-    ClipboardService::get_items(&state.db, &user.id, limit, offset)
+    // 获取剪贴板项目，limit 超过上限时截断
+    let (limit, offset) = ClipboardService::page_bounds(&state.db, request.limit.unwrap_or(50), request.offset.unwrap_or(0))
         .await
-        .map_err(|e| format!("{:?}", e))
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let items = ClipboardService::get_items(&state.db, &user.id, limit, offset, request.reveal_sensitive)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(ClipboardItemPage { items, limit, offset })
 }
 
 #[tauri::command]
 pub async fn add_clipboard_item(
     state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
     request: AddClipboardItemRequest,
 ) -> Result<ClipboardItem, String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
@@ -79,12 +146,28 @@ pub async fn add_clipboard_item(
         content: request.content,
         content_type: request.content_type,
         encrypt: request.encrypt,
+        source_app: None,
+        is_sensitive: request.is_sensitive,
+        alternate_formats: request.alternate_formats,
     };
     
     // 添加剪贴板项目
-    ClipboardService::add_item(&state.db, &user.id, &item_request)
+    let item = ClipboardService::add_item(&state.db, &user.id, &item_request)
         .await
-        .map_err(|e| format!("{:?}", e))
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 超出软配额时仍保存，只提醒前端
+    let usage = StatsService::get_storage_usage(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    if usage.over_soft_limit {
+        let _ = app_handle.emit("quota_warning", &usage);
+    }
+    
+    // 通知同步循环推送变更
+    state.sync_notify.notify_one();
+    
+    Ok(item)
 }
 
 #[tauri::command]
@@ -93,7 +176,7 @@ pub async fn update_clipboard_item(
     request: UpdateClipboardItemRequest,
 ) -> Result<ClipboardItem, String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
@@ -103,12 +186,69 @@ pub async fn update_clipboard_item(
         content: request.content,
         content_type: request.content_type,
         encrypt: request.encrypt,
+        is_sensitive: request.is_sensitive,
     };
     
     // 更新剪贴板项目
-    ClipboardService::update_item(&state.db, &user.id, &item_request)
+    let item = ClipboardService::update_item(&state.db, &user.id, &item_request)
         .await
-        .map_err(|e| format!("{:?}", e))
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送变更
+    state.sync_notify.notify_one();
+    
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn set_item_note(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+    note: Option<String>,
+) -> Result<ClipboardItem, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let item = ClipboardService::set_item_note(&state.db, &user.id, &id, note.as_deref())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送变更
+    state.sync_notify.notify_one();
+    
+    Ok(item)
+}
+
+// 将文本追加到项目末尾
+#[tauri::command]
+pub async fn append_to_item(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+    text: String,
+    unlock_token: Option<String>,
+) -> Result<ClipboardItem, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let item = ClipboardService::append_to_item(&state.db, &user.id, &id, &text)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送变更
+    state.sync_notify.notify_one();
+    
+    Ok(item)
 }
 
 #[tauri::command]
@@ -117,31 +257,368 @@ pub async fn delete_clipboard_item(
     request: DeleteClipboardItemRequest,
 ) -> Result<(), String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
     // 删除剪贴板项目
     ClipboardService::delete_item(&state.db, &user.id, &request.id)
         .await
-        .map_err(|e| format!("{:?}", e))
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送变更
+    state.sync_notify.notify_one();
+    
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn search_clipboard_items(
     state: State<'_, Arc<AppState>>,
     request: SearchClipboardItemsRequest,
+) -> Result<SearchPage, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, &request.unlock_token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 搜索剪贴板项目，limit 超过上限时截断；next_offset 为 None 表示没有更多结果
+    ClipboardService::search_page(&state.db, &user.id, &request.query, request.include_notes, request.limit.unwrap_or(50), request.offset.unwrap_or(0))
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 为所有匹配搜索的项目添加标签，返回新打上标签的项目数
+#[tauri::command]
+pub async fn tag_matching(
+    state: State<'_, Arc<AppState>>,
+    request: TagMatchingRequest,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::tag_matching(&state.db, &user.id, &request.query, request.include_notes, &request.tag, request.confirm)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_items_by_source(
+    state: State<'_, Arc<AppState>>,
+    request: GetItemsBySourceRequest,
 ) -> Result<Vec<ClipboardItem>, String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, &request.unlock_token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 按来源应用筛选
+    let limit = request.limit.unwrap_or(50);
+    let offset = request.offset.unwrap_or(0);
+    
+    ClipboardService::get_items_by_source(&state.db, &user.id, &request.source_app, limit, offset)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 查看某个设备修改过的项目及各设备的贡献数量
+#[tauri::command]
+pub async fn get_items_by_origin(
+    state: State<'_, Arc<AppState>>,
+    request: GetItemsByOriginRequest,
+) -> Result<OriginReport, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, &request.unlock_token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
-    // 搜索剪贴板项目
     let limit = request.limit.unwrap_or(50);
     let offset = request.offset.unwrap_or(0);
     
-    ClipboardService::search_items(&state.db, &user.id, &request.query, limit, offset)
+    ClipboardService::get_items_by_origin(&state.db, &user.id, &request.device_id, limit, offset)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 查看项目最后由哪个设备修改，来源未知时返回 unknown 而不是错误
+#[tauri::command]
+pub async fn get_item_provenance(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+) -> Result<ItemProvenance, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::get_item_provenance(&state.db, &user.id, &id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 预览项目的明文内容，不修改数据库
+#[tauri::command]
+pub async fn peek_item(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+    unlock_token: Option<String>,
+) -> Result<String, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::peek_item(&state.db, &user.id, &id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 获取项目的全部格式（已解密），按丰富程度从高到低排序，第一项用于还原到剪贴板
+#[tauri::command]
+pub async fn get_item_formats(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+    unlock_token: Option<String>,
+) -> Result<Vec<ItemFormat>, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::get_item_formats(&state.db, &user.id, &id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 查看敏感项目的真实内容（加密项目返回解密后的内容），会写入审计记录
+#[tauri::command]
+pub async fn reveal_item(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+    unlock_token: Option<String>,
+) -> Result<String, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::reveal_item(&state.db, &user.id, &id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 将项目内容转换后写入系统剪贴板，返回写入的文本
+#[tauri::command]
+pub async fn copy_item_transformed(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+    id: String,
+    transform: Transform,
+    unlock_token: Option<String>,
+) -> Result<String, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let content = ClipboardService::transform_item(&state.db, &user.id, &id, transform)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    app_handle.write_text(&content)?;
+    
+    Ok(content)
+}
+
+// 将另一个账号的全部项目转移到当前账号（账号合并），需要提供该账号的密码
+#[tauri::command]
+pub async fn reassign_items(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    from_email: String,
+    from_password: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 验证来源账号的密码，避免转移他人的项目；按来源邮箱限制失败次数，防止借此猜测密码
+    let from_user = AuthService::verify_credentials_limited(&state.db, &state.credential_check_limiter, &from_email, &from_password)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let moved = ClipboardService::reassign_items(&state.db, &from_user.id, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送变更
+    if moved > 0 {
+        state.sync_notify.notify_one();
+    }
+    
+    Ok(moved)
+}
+
+// 将单个项目转移到同一设备上登录的另一个账号，两个会话必须属于同一设备
+#[tauri::command]
+pub async fn move_item_to_account(
+    state: State<'_, Arc<AppState>>,
+    from_token: String,
+    to_token: String,
+    item_id: String,
+) -> Result<(), String> {
+    // 验证两个会话
+    let (from_user, to_user) = AuthService::verify_same_device(&state.db, &from_token, &to_token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::move_item_to_account(&state.db, &from_user.id, &to_user.id, &item_id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送变更
+    state.sync_notify.notify_one();
+    
+    Ok(())
+}
+
+// 重置加密：confirm 为 false 时只返回无法解密的项目数；确认后更换密钥并删除这些项目
+#[tauri::command]
+pub async fn reset_encryption(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    confirm: bool,
+) -> Result<EncryptionResetResult, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let result = ClipboardService::reset_encryption(&state.db, &user.id, confirm)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知同步循环推送重新加密和删除的项目
+    if result.rekeyed {
+        state.sync_notify.notify_one();
+    }
+    
+    Ok(result)
+}
+
+// 丢弃缓存的加密密钥，其他途径修改了数据库中的密钥后调用，之后重新从数据库读取
+#[tauri::command]
+pub async fn invalidate_key_cache(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    KeyCache::invalidate_user(&state.db, &user.id);
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn dedupe_history(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 合并重复项目
+    ClipboardService::dedupe_items(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 清空历史，include_pinned 为 false 时保留置顶项目，返回删除的数量
+#[tauri::command]
+pub async fn clear_history(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+    include_pinned: bool,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let cleared = ClipboardService::clear_history(&state.db, &user.id, include_pinned)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 通知其他窗口刷新列表
+    let _ = app_handle.emit("history_cleared", cleared);
+    
+    // 通知同步循环推送删除记录
+    state.sync_notify.notify_one();
+    
+    Ok(cleared)
+}
+
+// 历史中出现的内容类型及各类型的项目数量
+#[tauri::command]
+pub async fn list_content_types(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Vec<(String, i64)>, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ClipboardService::list_content_types(&state.db, &user.id)
         .await
         .map_err(|e| format!("{:?}", e))
 }
@@ -153,41 +630,18 @@ pub async fn start_clipboard_monitor(
     token: String,
 ) -> Result<(), String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &token)
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
     // 启动剪贴板监控
-    let db = state.db.clone();
-    let user_id = user.id.clone();
-    
-    // 创建一个新线程来监控剪贴板变化
-    tauri::async_runtime::spawn(async move {
-        let mut last_content = String::new();
-        
-        loop {
-            // 使用 tauri_plugin_clipboard_manager 获取剪贴板内容
-            if let Ok(content) = app_handle.clipboard().read_text() {
-                if !content.is_empty() && content != last_content {
-                    // 内容变化，保存到数据库
-                    let item_request = ClipboardItemRequest {
-                        content: content.clone(),
-                        content_type: "text/plain".to_string(),
-                        encrypt: false, // 默认不加密
-                    };
-                    
-                    if let Err(e) = ClipboardService::add_item(&db, &user_id, &item_request).await {
-                        eprintln!("保存剪贴板内容失败: {:?}", e);
-                    }
-                    
-                    last_content = content;
-                }
-            }
-            
-            // 等待一段时间再检查
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        }
+    let event_handle = app_handle.clone();
+    let handle = monitor::spawn_monitor(state.inner().clone(), app_handle, user.id, move |event| {
+        monitor::emit_read_event(&event_handle, event);
     });
     
+    // 注册任务句柄，重复启动时旧的监控任务会被终止
+    state.tasks.register(CLIPBOARD_MONITOR_TASK, handle).await;
+    
     Ok(())
 }
\ No newline at end of file
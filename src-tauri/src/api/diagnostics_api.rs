@@ -0,0 +1,197 @@
+use tauri::{AppHandle, Manager, State};
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::entity::connection_test::{ConnectionTestResult, SmtpConfig};
+use crate::entity::mail::MailQueueStatus;
+use crate::service::auth_service::AuthService;
+use crate::service::connectivity_service::{ConnectivityService, CONNECTION_TEST_TIMEOUT_SECS};
+use crate::service::mail_service::MailService;
+use crate::service::maintenance_service::{CompactionResult, ContentTypeReport, MaintenanceService, OrphanPolicy, OrphanReport};
+use crate::service::session_cache::SessionCacheStats;
+use crate::service::support_service::SupportService;
+use crate::sync;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub session_cache: SessionCacheStats,
+    pub mail_queue: MailQueueStatus,
+    pub unsynced_items: i64,
+    pub last_compaction_at: Option<i64>,
+}
+
+#[tauri::command]
+pub async fn get_diagnostics(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Diagnostics, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let unsynced_items = sync::count_unsynced(&state.db, Some(&user.id))
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let mail_queue = MailService::get_queue_status(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let last_compaction_at = MaintenanceService::last_compaction_at(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(Diagnostics {
+        session_cache: state.session_cache.stats(),
+        mail_queue,
+        unsynced_items,
+        last_compaction_at,
+    })
+}
+
+// 生成诊断包并保存到应用数据目录，返回文件路径；不包含剪贴板内容和任何密钥
+#[tauri::command]
+pub async fn export_support_bundle(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+) -> Result<String, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let manager = state.sync_manager.lock().await.clone();
+    let connected = match manager {
+        Some(manager) => manager.is_connected().await,
+        None => false,
+    };
+    
+    let bundle = SupportService::build_bundle(&state.db, &user.id, connected)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let app_dir = app_handle.path().app_data_dir()
+        .map_err(|e| e.to_string())?;
+    let path = SupportService::write_bundle(&app_dir, &bundle)
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub async fn get_mail_queue_status(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<MailQueueStatus, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    MailService::get_queue_status(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 压缩数据库：暂停监控和同步任务后执行 VACUUM，返回回收的字节数
+#[tauri::command]
+pub async fn compact_database(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<CompactionResult, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 防止并发压缩
+    let _compaction_guard = state.compaction_lock
+        .try_lock()
+        .map_err(|_| "正在压缩数据库，请稍后再试".to_string())?;
+    
+    // 等待后台任务完成当前操作并暂停
+    let _maintenance = state.maintenance_gate.write().await;
+    
+    MaintenanceService::compact(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 检查外键孤儿记录，policy 为 report 时只统计不删除
+#[tauri::command]
+pub async fn repair_orphans(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    policy: OrphanPolicy,
+) -> Result<OrphanReport, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 等待后台任务完成当前操作并暂停
+    let _maintenance = state.maintenance_gate.write().await;
+    
+    let report = MaintenanceService::repair_orphans(&state.db, policy)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 删除的会话可能仍在缓存中
+    if report.deleted > 0 {
+        state.session_cache.clear();
+    }
+    
+    Ok(report)
+}
+
+// 规范化项目中自由填写的 content_type，返回修改的数量
+#[tauri::command]
+pub async fn normalize_content_types(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<ContentTypeReport, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 等待后台任务完成当前操作并暂停
+    let _maintenance = state.maintenance_gate.write().await;
+    
+    MaintenanceService::normalize_content_types(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 测试 SMTP 配置能否连接并认证，不发送邮件也不保存配置
+#[tauri::command]
+pub async fn test_smtp_config(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    config: SmtpConfig,
+) -> Result<ConnectionTestResult, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(ConnectivityService::test_smtp(&config, Duration::from_secs(CONNECTION_TEST_TIMEOUT_SECS)).await)
+}
+
+// 测试同步服务器能否建立 WebSocket 连接，不保存地址
+#[tauri::command]
+pub async fn test_sync_server(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    url: String,
+) -> Result<ConnectionTestResult, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(ConnectivityService::test_sync_server(&url, Duration::from_secs(CONNECTION_TEST_TIMEOUT_SECS)).await)
+}
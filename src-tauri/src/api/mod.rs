@@ -1,2 +1,12 @@
 pub mod user_api;
-pub mod clipboard_api;
\ No newline at end of file
+pub mod clipboard_api;
+pub mod task_api;
+pub mod sync_api;
+pub mod settings_api;
+pub mod diagnostics_api;
+pub mod stats_api;
+pub mod backup_api;
+pub mod change_api;
+pub mod share_api;
+pub mod app_lock_api;
+pub mod profile_api;
\ No newline at end of file
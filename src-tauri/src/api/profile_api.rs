@@ -0,0 +1,65 @@
+use tauri::{AppHandle, State};
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::service::auth_service::AuthService;
+use crate::service::profile_service::ProfileService;
+use crate::shutdown;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileList {
+    pub profiles: Vec<String>,
+    pub current: String,
+    pub pinned: bool, // 由启动参数或环境变量指定时不能切换
+}
+
+// 列出本机已有的配置和当前配置
+#[tauri::command]
+pub async fn list_profiles(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<ProfileList, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let profiles = ProfileService::list_profiles(&state.profile.data_dir)
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(ProfileList {
+        profiles,
+        current: state.profile.name.clone(),
+        pinned: state.profile.pinned,
+    })
+}
+
+// 切换到另一个配置（不存在时创建）：记录选择后按退出流程关闭同步和后台任务，重启应用以连接新的数据库
+#[tauri::command]
+pub async fn switch_profile(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+    name: String,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ProfileService::validate_name(&name)
+        .map_err(|e| format!("{:?}", e))?;
+    if name == state.profile.name {
+        return Ok(());
+    }
+    if state.profile.pinned {
+        return Err("当前配置由启动参数或环境变量指定，无法切换".to_string());
+    }
+    
+    ProfileService::set_active(&state.profile.data_dir, &name)
+        .map_err(|e| format!("{:?}", e))?;
+    
+    shutdown::graceful_shutdown(&state, Duration::from_secs(shutdown::SHUTDOWN_FLUSH_TIMEOUT_SECS)).await;
+    app_handle.restart()
+}
@@ -0,0 +1,417 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::entity::content_type::ContentType;
+use crate::service::auth_service::AuthService;
+use crate::service::settings_service::{EncryptionPolicy, PreviewLengths, QuietHours, RedactionPattern, SanitizeSettings, SettingsService, StorageQuota};
+use crate::service::search_index_service::SearchIndexService;
+use crate::util::crypto::AeadCipher;
+
+#[tauri::command]
+pub async fn get_sanitize_settings(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<SanitizeSettings, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_sanitize_settings(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_sanitize_settings(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    settings: SanitizeSettings,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_sanitize_settings(&state.db, &settings)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_quiet_hours(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<QuietHours, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_quiet_hours(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 监控和同步循环每次检查时都会重新读取，修改后无需重启
+#[tauri::command]
+pub async fn set_quiet_hours(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    schedule: QuietHours,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_quiet_hours(&state.db, &schedule)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_encryption_policy(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<EncryptionPolicy, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_encryption_policy(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_encryption_policy(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    policy: EncryptionPolicy,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_encryption_policy(&state.db, &user.id, &policy)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_storage_quota(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<StorageQuota, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_storage_quota(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_storage_quota(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    quota: StorageQuota,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_storage_quota(&state.db, &user.id, &quota)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_encrypted_search(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<bool, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SearchIndexService::is_enabled(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 开启或关闭加密项目的搜索索引，开启时会为已有项目重建索引
+#[tauri::command]
+pub async fn set_encrypted_search(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    enabled: bool,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SearchIndexService::set_enabled(&state.db, &user.id, enabled)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_cipher(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<AeadCipher, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_cipher(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 只影响之后加密的内容，已有密文不需要重新加密
+#[tauri::command]
+pub async fn set_cipher(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    cipher: AeadCipher,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_cipher(&state.db, cipher)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_max_page_size(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<i64, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_max_page_size(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_max_page_size(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    size: i64,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_max_page_size(&state.db, size)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_capture_cooldown(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<i64, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_capture_cooldown_ms(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 设置剪贴板监控两次保存之间的最短间隔（毫秒），0 表示不限制
+#[tauri::command]
+pub async fn set_capture_cooldown(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    cooldown_ms: i64,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_capture_cooldown_ms(&state.db, cooldown_ms)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_email_check_enabled(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<bool, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_email_check_enabled(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_email_check_enabled(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    enabled: bool,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_email_check_enabled(&state.db, enabled)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_auto_monitor(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<bool, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_auto_monitor(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 只保存偏好，下次登录或恢复会话时生效
+#[tauri::command]
+pub async fn set_auto_monitor(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    enabled: bool,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_auto_monitor(&state.db, enabled)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_capture_types(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Vec<ContentType>, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_capture_types(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 运行中的监控在下一次轮询时生效
+#[tauri::command]
+pub async fn set_capture_types(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    types: Vec<ContentType>,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_capture_types(&state.db, &types)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_preview_lengths(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<PreviewLengths, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_preview_lengths(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_preview_lengths(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    lengths: PreviewLengths,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_preview_lengths(&state.db, &lengths)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_redaction_patterns(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Vec<RedactionPattern>, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::get_redaction_patterns(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_redaction_patterns(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    patterns: Vec<RedactionPattern>,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SettingsService::set_redaction_patterns(&state.db, &patterns)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
@@ -0,0 +1,44 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::entity::share::{ShareLink, SharedContent};
+use crate::service::app_lock_service::AppLockService;
+use crate::service::auth_service::AuthService;
+use crate::service::share_service::ShareService;
+
+// 为项目创建加密分享，ttl 为有效期（秒）
+#[tauri::command]
+pub async fn create_share_link(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    id: String,
+    passphrase: String,
+    ttl: i64,
+    unlock_token: Option<String>,
+) -> Result<ShareLink, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    ShareService::create_share_link(&state.db, &user.id, &id, &passphrase, ttl)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 兑换分享码，只需要分享口令，不要求登录
+#[tauri::command]
+pub async fn redeem_share(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+    passphrase: String,
+) -> Result<SharedContent, String> {
+    ShareService::redeem_share(&state.db, &code, &passphrase)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
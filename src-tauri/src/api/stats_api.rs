@@ -0,0 +1,42 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::service::app_lock_service::AppLockService;
+use crate::service::auth_service::AuthService;
+use crate::service::stats_service::{ClipboardStatistics, StatsService, StorageUsage};
+
+#[tauri::command]
+pub async fn get_statistics(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    unlock_token: Option<String>,
+) -> Result<ClipboardStatistics, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 检查应用锁
+    AppLockService::require_unlocked(&state.db, &state.app_lock, unlock_token.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    StatsService::get_statistics(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_storage_usage(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<StorageUsage, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    StatsService::get_storage_usage(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
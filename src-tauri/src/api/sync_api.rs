@@ -0,0 +1,410 @@
+use tauri::{State, AppHandle, Emitter};
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::entity::tombstone::Tombstone;
+use crate::service::auth_service::AuthService;
+use crate::service::sync_service::SyncService;
+use crate::service::task_registry::SYNC_LOOP_TASK;
+use crate::sync::{DeviceSyncFilter, MergePolicy, ReconnectPolicy, WebSocketManager};
+
+// 等待首次同步完成的最长时间
+const FIRST_SYNC_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetTombstonesRequest {
+    pub token: String,
+    pub since: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[tauri::command]
+pub async fn get_tombstones(
+    state: State<'_, Arc<AppState>>,
+    request: GetTombstonesRequest,
+) -> Result<Vec<Tombstone>, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &request.token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 获取删除记录
+    let since = request.since.unwrap_or(0);
+    let limit = request.limit.unwrap_or(50);
+    let offset = request.offset.unwrap_or(0);
+    
+    SyncService::get_tombstones(&state.db, &user.id, since, limit, offset)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn switch_sync_server(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+    new_url: String,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 防止并发切换
+    let _switch_guard = state.sync_switch_lock
+        .try_lock()
+        .map_err(|_| "正在切换同步服务器，请稍后再试".to_string())?;
+    
+    // 停止旧的同步循环并断开连接
+    let _ = state.tasks.stop(SYNC_LOOP_TASK).await;
+    if let Some(old_manager) = state.sync_manager.lock().await.take() {
+        if let Err(e) = old_manager.disconnect().await {
+            eprintln!("断开旧同步服务器失败: {}", e);
+        }
+    }
+    
+    // 重置本地同步状态
+    SyncService::reset_for_server(&state.db, &new_url)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 连接新服务器
+    let manager = build_manager(&state, &app_handle, &user.id, new_url).await?;
+    manager.connect().await?;
+    
+    let mut sync_results = manager.subscribe_sync_results();
+    *state.sync_manager.lock().await = Some(manager.clone());
+    
+    // 启动新的同步循环
+    spawn_sync_loop(&state, app_handle, manager).await;
+    
+    // 等待首次同步完成或出错
+    match tokio::time::timeout(Duration::from_secs(FIRST_SYNC_TIMEOUT_SECS), sync_results.recv()).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(format!("同步结果通道错误: {}", e)),
+        Err(_) => Err("等待首次同步超时".to_string()),
+    }
+}
+
+// 设置固定的服务器证书指纹，传入 None 时取消固定；下次连接时生效
+#[tauri::command]
+pub async fn set_pinned_cert(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    fingerprint: Option<String>,
+) -> Result<Option<String>, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::set_pinned_cert(&state.db, fingerprint.as_deref())
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 达到重连上限放弃同步后手动重新开始，重连次数和时长重新计算
+#[tauri::command]
+pub async fn retry_sync(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let _switch_guard = state.sync_switch_lock
+        .try_lock()
+        .map_err(|_| "正在切换同步服务器，请稍后再试".to_string())?;
+    
+    let server_url = SyncService::get_server_url(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?
+        .ok_or_else(|| "尚未设置同步服务器".to_string())?;
+    
+    // 停止旧的同步循环，使用最新的设置重新创建连接管理器
+    let _ = state.tasks.stop(SYNC_LOOP_TASK).await;
+    if let Some(old_manager) = state.sync_manager.lock().await.take() {
+        let _ = old_manager.disconnect().await;
+    }
+    
+    let manager = build_manager(&state, &app_handle, &user.id, server_url).await?;
+    *state.sync_manager.lock().await = Some(manager.clone());
+    spawn_sync_loop(&state, app_handle, manager).await;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_reconnect_policy(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<ReconnectPolicy, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::get_reconnect_policy(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 设置重连上限，下次连接或 retry_sync 时生效
+#[tauri::command]
+pub async fn set_reconnect_policy(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    policy: ReconnectPolicy,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::set_reconnect_policy(&state.db, &policy)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_merge_policy(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<MergePolicy, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::get_merge_policy(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn set_merge_policy(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    policy: MergePolicy,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::set_merge_policy(&state.db, policy)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[tauri::command]
+pub async fn get_device_sync_filter(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    device_id: String,
+) -> Result<DeviceSyncFilter, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::get_device_sync_filter(&state.db, &device_id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 设置不向指定设备同步的内容类型，下次推送时生效
+#[tauri::command]
+pub async fn set_device_sync_filter(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    device_id: String,
+    filter: DeviceSyncFilter,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::set_device_sync_filter(&state.db, &device_id, &filter)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 获取本机的设备 ID
+#[tauri::command]
+pub async fn get_device_id(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<String, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::get_device_id(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 重新生成本机的设备 ID，返回新 ID
+#[tauri::command]
+pub async fn regenerate_device_id(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<String, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let device_id = SyncService::regenerate_device_id(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 缓存的会话仍记录旧的设备 ID
+    state.session_cache.clear();
+    
+    Ok(device_id)
+}
+
+// 解绑设备并注销其全部会话，返回注销的会话数；通过 device_unbound 事件通知前端
+#[tauri::command]
+pub async fn unbind_device(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+    device_id: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let revoked = SyncService::unbind_device(&state.db, &user.id, &device_id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    state.session_cache.invalidate_user(&user.id);
+    let _ = app_handle.emit("device_unbound", &device_id);
+    
+    Ok(revoked)
+}
+
+// 立即将一个项目发送给指定的在线设备，目标设备按正常流程合并
+#[tauri::command]
+pub async fn send_item_to_device(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    item_id: String,
+    device_id: String,
+) -> Result<(), String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let message = SyncService::build_direct_send(&state.db, &user.id, &item_id, &device_id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let manager = state.sync_manager.lock().await.clone()
+        .ok_or_else(|| "同步未启动".to_string())?;
+    manager.send_message(message).await
+}
+
+// 合并重复的绑定设备记录，返回移除的记录数
+#[tauri::command]
+pub async fn dedupe_devices(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<usize, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::dedupe_devices(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 将当前用户的所有项目标记为已同步，返回受影响的项目数
+#[tauri::command]
+pub async fn mark_all_synced(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    SyncService::mark_all_synced(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 将当前用户的所有项目标记为待同步并唤醒同步循环重新上传
+#[tauri::command]
+pub async fn mark_all_unsynced(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let count = SyncService::mark_all_unsynced(&state.db, &user.id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    if count > 0 {
+        state.sync_notify.notify_one();
+    }
+    
+    Ok(count)
+}
+
+// 按已保存的证书指纹和重连设置创建连接管理器，只同步当前登录用户的项目
+async fn build_manager(
+    state: &AppState,
+    app_handle: &AppHandle,
+    user_id: &str,
+    server_url: String,
+) -> Result<Arc<WebSocketManager>, String> {
+    let device_id = SyncService::get_device_id(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    let device_name = app_handle.package_info().name.clone();
+    let pinned_cert = SyncService::get_pinned_cert(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    let reconnect_policy = SyncService::get_reconnect_policy(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(Arc::new(
+        WebSocketManager::new(device_id, device_name, server_url)
+            .with_pinned_cert(pinned_cert)
+            .with_reconnect_policy(reconnect_policy)
+            .with_user_id(user_id)
+    ))
+}
+
+// 启动同步循环并注册任务句柄
+async fn spawn_sync_loop(state: &State<'_, Arc<AppState>>, app_handle: AppHandle, manager: Arc<WebSocketManager>) {
+    let app_state = state.inner().clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        if let Err(e) = manager.start_message_loop(app_state, app_handle).await {
+            eprintln!("同步循环退出: {}", e);
+        }
+    });
+    state.tasks.register(SYNC_LOOP_TASK, handle).await;
+}
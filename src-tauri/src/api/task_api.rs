@@ -0,0 +1,34 @@
+use tauri::State;
+use std::sync::Arc;
+use crate::AppState;
+use crate::service::auth_service::AuthService;
+use crate::service::task_registry::BackgroundTaskInfo;
+
+#[tauri::command]
+pub async fn list_background_tasks(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Vec<BackgroundTaskInfo>, String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(state.tasks.list().await)
+}
+
+#[tauri::command]
+pub async fn stop_background_task(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    name: String,
+) -> Result<(), String> {
+    // 验证会话
+    AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    state.tasks.stop(&name)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
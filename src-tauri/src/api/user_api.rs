@@ -1,10 +1,17 @@
-use tauri::{State, AppHandle};
+use tauri::{AppHandle, State};
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 use crate::AppState;
+use crate::monitor;
 use crate::service::auth_service::AuthService;
+use crate::service::sync_service::SyncService;
 use crate::service::user_service::UserService;
-use crate::entity::session::Session;
+use crate::service::mail_service::MailService;
+use crate::service::key_provision_service::KeyProvisionService;
+use crate::service::session_cache::SessionCache;
+use crate::repository::encryption_repository::WrappedKey;
+use crate::entity::session::{Session, SessionInfo};
 use crate::entity::user::UserProfile;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,14 +49,20 @@ pub struct UpdateProfileRequest {
     pub email: String,
 }
 
+// 账户命令的逻辑放在 *_impl(pool, ...) 函数中，命令只负责从 tauri::State 取出连接池和缓存，测试直接调用 *_impl
+
 #[tauri::command]
 pub async fn register_user(
     state: State<'_, Arc<AppState>>,
     request: RegisterRequest,
 ) -> Result<UserProfile, String> {
+    register_user_impl(&state.db, &request).await
+}
+
+pub async fn register_user_impl(pool: &SqlitePool, request: &RegisterRequest) -> Result<UserProfile, String> {
     // 注册用户
     let user = UserService::register(
-        &state.db, 
+        pool, 
         &request.email, 
         &request.password, 
         &request.verification_code
@@ -73,22 +86,142 @@ pub async fn login_user(
     app_handle: AppHandle,
     request: LoginRequest,
 ) -> Result<Session, String> {
-    // 获取设备ID
-    let device_id = app_handle.config().identifier.clone();
+    // 获取本机的设备ID
+    let device_id = SyncService::get_device_id(&state.db)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
     
     // 登录用户
-    AuthService::login(&state.db, &request.email, &request.password, &device_id)
+    let session = login_user_impl(&state.db, &request, &device_id).await?;
+    
+    // 开启了自动监控时启动剪贴板监控，失败不影响登录
+    let event_handle = app_handle.clone();
+    if let Err(e) = monitor::auto_start_monitor(state.inner(), app_handle, &session.user_id, move |event| {
+        monitor::emit_read_event(&event_handle, event);
+    }).await {
+        eprintln!("自动启动剪贴板监控失败: {:?}", e);
+    }
+    
+    Ok(session)
+}
+
+pub async fn login_user_impl(pool: &SqlitePool, request: &LoginRequest, device_id: &str) -> Result<Session, String> {
+    AuthService::login(pool, &request.email, &request.password, device_id)
         .await
         .map_err(|e| format!("{:?}", e))
 }
 
+// 使用已保存的会话恢复登录状态时调用：开启了自动监控且监控未运行时启动，返回是否启动了新任务
+#[tauri::command]
+pub async fn auto_start_monitor(
+    state: State<'_, Arc<AppState>>,
+    app_handle: AppHandle,
+    token: String,
+) -> Result<bool, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let event_handle = app_handle.clone();
+    monitor::auto_start_monitor(state.inner(), app_handle, &user.id, move |event| {
+        monitor::emit_read_event(&event_handle, event);
+    })
+    .await
+    .map_err(|e| format!("{:?}", e))
+}
+
 #[tauri::command]
 pub async fn logout_user(
     state: State<'_, Arc<AppState>>,
     token: String,
 ) -> Result<(), String> {
+    logout_user_impl(&state.db, &state.session_cache, &token).await
+}
+
+pub async fn logout_user_impl(pool: &SqlitePool, session_cache: &SessionCache, token: &str) -> Result<(), String> {
     // 注销用户
-    AuthService::logout(&state.db, &token)
+    AuthService::logout(pool, token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    session_cache.invalidate_token(token);
+    
+    Ok(())
+}
+
+// 列出当前用户的全部有效会话
+#[tauri::command]
+pub async fn list_sessions(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<Vec<SessionInfo>, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    AuthService::list_sessions(&state.db, &user.id, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 注销当前会话以外的全部会话，返回注销的数量
+#[tauri::command]
+pub async fn logout_others(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let revoked = AuthService::logout_others(&state.db, &user.id, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    state.session_cache.invalidate_user(&user.id);
+    
+    Ok(revoked)
+}
+
+// 注销某个设备上的全部会话，返回注销的数量
+#[tauri::command]
+pub async fn revoke_device(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    device_id: String,
+) -> Result<u64, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    let revoked = AuthService::revoke_device(&state.db, &user.id, &device_id)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    state.session_cache.invalidate_user(&user.id);
+    
+    Ok(revoked)
+}
+
+// 为新绑定的设备提供包装后的用户密钥，幂等键相同的重试返回相同结果
+#[tauri::command]
+pub async fn provision_key_to_device(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    device_id: String,
+    idempotency_key: String,
+    pairing_secret: String,
+) -> Result<WrappedKey, String> {
+    // 验证会话
+    let user = AuthService::verify_session_cached(&state.db, &state.session_cache, &token)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    KeyProvisionService::provision_key_to_device(&state.db, &user.id, &device_id, &idempotency_key, &pairing_secret)
         .await
         .map_err(|e| format!("{:?}", e))
 }
@@ -98,13 +231,17 @@ pub async fn get_user_profile(
     state: State<'_, Arc<AppState>>,
     token: String,
 ) -> Result<UserProfile, String> {
+    get_user_profile_impl(&state.db, &state.session_cache, &token).await
+}
+
+pub async fn get_user_profile_impl(pool: &SqlitePool, session_cache: &SessionCache, token: &str) -> Result<UserProfile, String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &token)
+    let user = AuthService::verify_session_cached(pool, session_cache, token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
     // 获取用户资料
-    UserService::get_profile(&state.db, &user.id)
+    UserService::get_profile(pool, &user.id)
         .await
         .map_err(|e| format!("{:?}", e))
 }
@@ -113,30 +250,67 @@ pub async fn get_user_profile(
 pub async fn update_user_profile(
     state: State<'_, Arc<AppState>>,
     request: UpdateProfileRequest,
+) -> Result<UserProfile, String> {
+    update_user_profile_impl(&state.db, &state.session_cache, &request).await
+}
+
+pub async fn update_user_profile_impl(
+    pool: &SqlitePool,
+    session_cache: &SessionCache,
+    request: &UpdateProfileRequest,
 ) -> Result<UserProfile, String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(pool, session_cache, &request.token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
     // 更新用户资料
-    UserService::update_profile(&state.db, &user.id, &request.username, &request.email)
+    let profile = UserService::update_profile(pool, &user.id, &request.username, &request.email)
         .await
-        .map_err(|e| format!("{:?}", e))
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 缓存的会话中保存着旧的用户名和邮箱
+    session_cache.invalidate_user(&user.id);
+    
+    Ok(profile)
 }
 
 #[tauri::command]
 pub async fn change_password(
     state: State<'_, Arc<AppState>>,
     request: ChangePasswordRequest,
+) -> Result<(), String> {
+    change_password_impl(&state.db, &state.session_cache, &request).await
+}
+
+pub async fn change_password_impl(
+    pool: &SqlitePool,
+    session_cache: &SessionCache,
+    request: &ChangePasswordRequest,
 ) -> Result<(), String> {
     // 验证会话
-    let user = AuthService::verify_session(&state.db, &request.token)
+    let user = AuthService::verify_session_cached(pool, session_cache, &request.token)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
     // 修改密码
-    AuthService::change_password(&state.db, &user.id, &request.old_password, &request.new_password)
+    AuthService::change_password(pool, &user.id, &request.old_password, &request.new_password)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    session_cache.invalidate_user(&user.id);
+    
+    Ok(())
+}
+
+// 确认当前账户的密码，不创建新会话；失败次数过多时返回 RateLimited(剩余秒数)
+#[tauri::command]
+pub async fn verify_current_password(
+    state: State<'_, Arc<AppState>>,
+    token: String,
+    password: String,
+) -> Result<bool, String> {
+    AuthService::verify_current_password(&state.db, &state.password_check_limiter, &token, &password)
         .await
         .map_err(|e| format!("{:?}", e))
 }
@@ -146,24 +320,92 @@ pub async fn request_password_reset(
     state: State<'_, Arc<AppState>>,
     email: String,
 ) -> Result<(), String> {
+    request_password_reset_impl(&state.db, &email).await
+}
+
+pub async fn request_password_reset_impl(pool: &SqlitePool, email: &str) -> Result<(), String> {
     // 创建密码重置令牌
-    let token = AuthService::request_password_reset(&state.db, &email)
+    let token = AuthService::request_password_reset(pool, email)
         .await
         .map_err(|e| format!("{:?}", e))?;
     
-    // 在实际应用中，这里应该发送邮件
-    // 但在开发阶段，我们只打印令牌
-    println!("密码重置令牌 ({}): {}", email, token);
+    // 加入邮件队列，由后台任务发送，避免阻塞命令
+    MailService::enqueue(
+        pool,
+        email,
+        "密码重置",
+        &format!("您的密码重置令牌为: {}\n该令牌 24 小时内有效。", token),
+    )
+    .await
+    .map_err(|e| format!("{:?}", e))?;
     
     Ok(())
 }
 
+// 注册前检查邮箱是否可用，有限流；设置中关闭后返回错误
+#[tauri::command]
+pub async fn email_available(
+    state: State<'_, Arc<AppState>>,
+    email: String,
+) -> Result<bool, String> {
+    UserService::email_available(&state.db, &state.email_check_limiter, &email)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
+// 重新发送注册验证码，旧验证码失效；发送过于频繁时返回 RateLimited(剩余秒数)
+#[tauri::command]
+pub async fn resend_verification_code(
+    state: State<'_, Arc<AppState>>,
+    email: String,
+) -> Result<(), String> {
+    let code = UserService::resend_verification_code(&state.db, &email)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 加入邮件队列，由后台任务发送
+    MailService::enqueue(
+        &state.db,
+        &email,
+        "注册验证码",
+        &format!("您的验证码为: {}\n该验证码 10 分钟内有效。", code),
+    )
+    .await
+    .map_err(|e| format!("{:?}", e))?;
+    
+    Ok(())
+}
+
+// 撤销未使用的重置令牌，邮箱不存在时同样返回成功
+#[tauri::command]
+pub async fn cancel_password_reset(
+    state: State<'_, Arc<AppState>>,
+    email: String,
+) -> Result<(), String> {
+    AuthService::cancel_password_reset(&state.db, &email)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
+
 #[tauri::command]
 pub async fn reset_password(
     state: State<'_, Arc<AppState>>,
     request: ResetPasswordRequest,
 ) -> Result<(), String> {
-    AuthService::reset_password(&state.db, &request.email, &request.reset_token, &request.new_password)
+    reset_password_impl(&state.db, &state.session_cache, &request).await
+}
+
+pub async fn reset_password_impl(
+    pool: &SqlitePool,
+    session_cache: &SessionCache,
+    request: &ResetPasswordRequest,
+) -> Result<(), String> {
+    AuthService::reset_password(pool, &request.email, &request.reset_token, &request.new_password)
         .await
-        .map_err(|e| format!("{:?}", e))
+        .map_err(|e| format!("{:?}", e))?;
+    
+    // 重置密码时无法从令牌定位缓存条目，直接清空
+    session_cache.clear();
+    
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use crate::entity::clipboard_item::ClipboardItem;
+
+// 最近项目缓存的默认容量
+pub const RECENT_ITEMS_CACHE_CAPACITY: usize = 200;
+
+// 按 id 缓存最近同步或访问的项目，超过容量时淘汰最久未使用的项目
+// 只用于加速读取，与同步推送队列无关，丢失时从数据库重新读取即可
+#[derive(Debug)]
+pub struct RecentItemsCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, (u64, ClipboardItem)>, // id -> (最近使用序号, 项目)
+    order: BTreeMap<u64, String>,                   // 最近使用序号 -> id，最小的最久未使用
+    next_tick: u64,
+}
+
+impl CacheState {
+    // 标记为最近使用，返回新的序号
+    fn touch(&mut self, id: &str, old_tick: Option<u64>) -> u64 {
+        if let Some(old_tick) = old_tick {
+            self.order.remove(&old_tick);
+        }
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        self.order.insert(tick, id.to_string());
+        tick
+    }
+}
+
+impl RecentItemsCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    // 写入或替换项目并标记为最近使用，超过容量时淘汰最久未使用的项目
+    pub fn add(&self, item: ClipboardItem) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let old_tick = state.entries.get(&item.id).map(|(tick, _)| *tick);
+        let tick = state.touch(&item.id, old_tick);
+        state.entries.insert(item.id.clone(), (tick, item));
+
+        while state.entries.len() > self.capacity {
+            let Some((_, id)) = state.order.pop_first() else {
+                break;
+            };
+            state.entries.remove(&id);
+        }
+    }
+
+    // 读取项目并标记为最近使用
+    pub fn get(&self, id: &str) -> Option<ClipboardItem> {
+        let mut state = self.state.lock().unwrap();
+        let (old_tick, item) = state.entries.get(id).map(|(tick, item)| (*tick, item.clone()))?;
+        let tick = state.touch(id, Some(old_tick));
+        state.entries.insert(id.to_string(), (tick, item.clone()));
+
+        Some(item)
+    }
+
+    // 移除项目（例如项目被删除时），返回被移除的项目
+    pub fn remove(&self, id: &str) -> Option<ClipboardItem> {
+        let mut state = self.state.lock().unwrap();
+        let (tick, item) = state.entries.remove(id)?;
+        state.order.remove(&tick);
+
+        Some(item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl Default for RecentItemsCache {
+    fn default() -> Self {
+        Self::new(RECENT_ITEMS_CACHE_CAPACITY)
+    }
+}
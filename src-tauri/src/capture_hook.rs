@@ -0,0 +1,87 @@
+use futures_util::future::BoxFuture;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use crate::entity::clipboard_item::ClipboardItemRequest;
+use crate::util::text;
+
+// 捕获后、保存前对剪贴板内容的处理：返回修改后的请求，返回 None 时丢弃本次捕获
+//
+// 实现示例：
+// fn process<'a>(&'a self, item: ClipboardItemRequest) -> BoxFuture<'a, Option<ClipboardItemRequest>> {
+//     Box::pin(async move { Some(item) })
+// }
+pub trait CaptureHook: Send + Sync {
+    // 用于日志和调试
+    fn name(&self) -> &str;
+    fn process<'a>(&'a self, item: ClipboardItemRequest) -> BoxFuture<'a, Option<ClipboardItemRequest>>;
+}
+
+// 按注册顺序依次执行的钩子列表
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: RwLock<Vec<Arc<dyn CaptureHook>>>,
+}
+
+impl fmt::Debug for HookRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 包含内置钩子的注册表
+    pub fn with_defaults() -> Self {
+        let registry = Self::new();
+        registry.register(Arc::new(TrimHook));
+        registry
+    }
+
+    // 追加到列表末尾
+    pub fn register(&self, hook: Arc<dyn CaptureHook>) {
+        self.hooks.write().unwrap().push(hook);
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.hooks.read().unwrap().iter().map(|hook| hook.name().to_string()).collect()
+    }
+
+    // 依次执行全部钩子，任一钩子丢弃时立即返回 None
+    pub async fn run(&self, item: ClipboardItemRequest) -> Option<ClipboardItemRequest> {
+        // 先复制列表，执行钩子期间不持有锁
+        let hooks = self.hooks.read().unwrap().clone();
+
+        let mut item = item;
+        for hook in hooks {
+            item = hook.process(item).await?;
+        }
+        Some(item)
+    }
+}
+
+// 内置钩子：去掉文本内容首尾的空白和控制字符，内容为空时丢弃
+pub struct TrimHook;
+
+impl CaptureHook for TrimHook {
+    fn name(&self) -> &str {
+        "trim"
+    }
+
+    fn process<'a>(&'a self, mut item: ClipboardItemRequest) -> BoxFuture<'a, Option<ClipboardItemRequest>> {
+        Box::pin(async move {
+            if !text::is_text_content_type(&item.content_type) {
+                return Some(item);
+            }
+
+            let trimmed = text::sanitize_text(&item.content).trim_start().to_string();
+            if trimmed.is_empty() {
+                return None;
+            }
+            item.content = trimmed;
+            Some(item)
+        })
+    }
+}
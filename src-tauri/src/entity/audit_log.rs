@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+// 审计操作类型
+pub const AUDIT_REVEAL_ITEM: &str = "reveal_item";
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: String,
+    pub user_id: String,
+    pub action: String,
+    pub target_id: Option<String>,
+    pub created_at: i64,
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use crate::entity::clipboard_item::ClipboardItem;
+
+// 变更类型
+pub const CHANGE_OP_ADD: &str = "add";
+pub const CHANGE_OP_UPDATE: &str = "update";
+pub const CHANGE_OP_DELETE: &str = "delete";
+
+// 变更记录，seq 单调递增，用作前端的增量游标
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ChangeRecord {
+    pub seq: i64,
+    pub user_id: String,
+    pub op: String,
+    pub item_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChangeFeed {
+    pub changes: Vec<ChangeRecord>,
+    pub latest_seq: i64,
+    pub reset_required: bool, // 游标早于已清理的记录，需要重新获取全部数据
+}
+
+
+// 某时间之后变化的项目，供前端重新获得焦点时增量刷新
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ItemsChangedSince {
+    pub items: Vec<ClipboardItem>, // 新增或修改的项目，按更新时间升序
+    pub deleted_ids: Vec<String>,
+    pub latest_ts: i64, // 下次请求使用的时间戳
+}
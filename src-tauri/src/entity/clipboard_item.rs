@@ -2,8 +2,15 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc, TimeZone};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use crate::entity::content_type::ContentType;
+use crate::entity::item_format::FormatRequest;
+use crate::util::encoding;
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+// 对外（前端、服务层）content 始终是字符串，二进制类型为 base64；
+// 写入数据库时二进制内容以原始字节保存在 content_blob 列，见 ContentPayload
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClipboardItem {
     pub id: String,
     pub user_id: String,
@@ -12,21 +19,113 @@ pub struct ClipboardItem {
     pub encrypted: bool,
     pub created_at: i64,
     pub updated_at: i64,
+    pub raw_content: Option<String>, // 清理前的原始内容（仅在开启保留时存储）
+    pub source_app: Option<String>, // 复制来源的应用名称，平台不支持时为空
+    #[serde(default)]
+    pub is_sensitive: bool, // 敏感项目默认隐藏内容，需要确认后查看
+    #[serde(default)]
+    pub preview: Option<String>, // 列表中显示的截断预览，不保存到数据库
+    #[serde(default)]
+    pub note: Option<String>, // 用户为项目添加的备注，始终以明文保存
+    #[serde(default)]
+    pub decrypt_error: bool, // 加密项目的密钥无法加载，content 为占位内容，不保存到数据库
+    #[serde(default)]
+    pub thumbnail: Option<String>, // 图片项目的 PNG 缩略图（base64），保存在 item_thumbnails 表
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ClipboardItemRequest {
     pub content: String,
     pub content_type: String,
-    pub encrypt: bool,
+    #[serde(default)]
+    pub encrypt: Option<bool>, // 未指定时使用用户的默认加密策略
+    #[serde(default)]
+    pub source_app: Option<String>,
+    #[serde(default)]
+    pub is_sensitive: Option<bool>, // 未指定时自动检测
+    #[serde(default)]
+    pub alternate_formats: Vec<FormatRequest>, // 同一次复制的其他格式
+}
+
+// 分页查询结果，limit 为实际生效的每页条数（超过上限时被截断）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardItemPage {
+    pub items: Vec<ClipboardItem>,
+    pub limit: i64,
+    pub offset: i64,
 }
 
+// 搜索结果分页：total 为匹配的总数，next_offset 为下一页的偏移量，已经是最后一页时为 None
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchPage {
+    pub items: Vec<ClipboardItem>,
+    pub total: i64,
+    pub next_offset: Option<i64>,
+}
+
+// 未提供的字段保持不变
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClipboardItemUpdateRequest {
     pub id: String,
-    pub content: String,
-    pub content_type: String,
-    pub encrypt: bool,
+    pub content: Option<String>,
+    pub content_type: Option<String>,
+    pub encrypt: Option<bool>,
+    #[serde(default)]
+    pub is_sensitive: Option<bool>,
+}
+
+// 存储层的内容：文本保存在 content 列，图片等二进制内容保存在 content_blob 列
+// 加密的二进制项目保存的是 nonce + 密文的原始字节
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentPayload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl ContentPayload {
+    // 转换为对外使用的字符串表示
+    pub fn into_content(self) -> String {
+        match self {
+            ContentPayload::Text(text) => text,
+            ContentPayload::Binary(bytes) => encoding::encode(bytes),
+        }
+    }
+
+    // 对应 (content, content_blob) 两列的值，二进制内容的 content 列为空字符串
+    pub fn columns(&self) -> (&str, Option<&[u8]>) {
+        match self {
+            ContentPayload::Text(text) => (text, None),
+            ContentPayload::Binary(bytes) => ("", Some(bytes)),
+        }
+    }
+}
+
+impl<'r> FromRow<'r, SqliteRow> for ClipboardItem {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        // 旧数据的二进制内容仍以 base64 保存在 content 列
+        let content_blob: Option<Vec<u8>> = row.try_get("content_blob").unwrap_or(None);
+        let payload = match content_blob {
+            Some(bytes) => ContentPayload::Binary(bytes),
+            None => ContentPayload::Text(row.try_get("content")?),
+        };
+
+        Ok(Self {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            content: payload.into_content(),
+            content_type: row.try_get("content_type")?,
+            encrypted: row.try_get("encrypted")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            raw_content: row.try_get("raw_content")?,
+            source_app: row.try_get("source_app")?,
+            is_sensitive: row.try_get("is_sensitive")?,
+            preview: None,
+            note: row.try_get("note")?,
+            decrypt_error: false,
+            thumbnail: None,
+        })
+    }
 }
 
 impl ClipboardItem {
@@ -43,7 +142,25 @@ impl ClipboardItem {
             encrypted,
             created_at: now,
             updated_at: now,
+            raw_content: None,
+            source_app: None,
+            is_sensitive: false,
+            preview: None,
+            note: None,
+            decrypt_error: false,
+            thumbnail: None,
+        }
+    }
+
+    // 写入数据库时使用的内容，二进制类型解码为原始字节，无法解码的旧数据按文本保存
+    pub fn payload(&self) -> ContentPayload {
+        if ContentType::from_mime(&self.content_type).is_binary() {
+            if let Ok(bytes) = encoding::decode(&self.content) {
+                return ContentPayload::Binary(bytes);
+            }
         }
+
+        ContentPayload::Text(self.content.clone())
     }
 
     pub fn created_at_datetime(&self) -> DateTime<Utc> {
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+// SMTP 连接的加密方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls, // 明文连接后升级（通常为 587 端口）
+    Tls,      // 直接建立 TLS 连接（通常为 465 端口）
+    None,     // 不加密，仅用于本地测试服务器
+}
+
+// 待测试的 SMTP 配置，未提供用户名时只测试连接不测试认证
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+// 连接测试结果，失败时 error 为可读的错误信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConnectionTestResult {
+    pub success: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
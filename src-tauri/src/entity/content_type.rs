@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+// 无法识别的 content_type 规范化后使用的类型
+pub const FALLBACK_CONTENT_TYPE: &str = "application/octet-stream";
+
+// 按 MIME 类型划分的内容类别，用于校验内容与声明的类型是否一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Text,
+    Url,
+    Png,
+    Jpeg,
+    Image, // 其他图片类型
+    Other,
+}
+
+impl ContentType {
+    pub const ALL: [ContentType; 6] = [
+        ContentType::Text,
+        ContentType::Url,
+        ContentType::Png,
+        ContentType::Jpeg,
+        ContentType::Image,
+        ContentType::Other,
+    ];
+    
+    pub fn from_mime(content_type: &str) -> Self {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        
+        match mime.as_str() {
+            "text/uri-list" | "text/x-uri" => ContentType::Url,
+            "image/png" => ContentType::Png,
+            "image/jpeg" | "image/jpg" => ContentType::Jpeg,
+            m if m.starts_with("image/") => ContentType::Image,
+            m if m.starts_with("text/") => ContentType::Text,
+            _ => ContentType::Other,
+        }
+    }
+    
+    // 将旧数据中自由填写的 content_type 规范化为 MIME 类型：已是 "类型/子类型" 格式时只去除空白并转为小写，
+    // 常见简写映射到对应的 MIME 类型，其余无法识别的返回 None
+    pub fn normalize_mime(content_type: &str) -> Option<String> {
+        let value = content_type.trim().to_ascii_lowercase();
+        
+        let alias = match value.as_str() {
+            "text" | "txt" | "plain" | "plaintext" | "plain_text" | "string" | "text/text" => Some("text/plain"),
+            "html" | "htm" => Some("text/html"),
+            "rtf" => Some("text/rtf"),
+            "url" | "uri" | "link" => Some("text/uri-list"),
+            "png" => Some("image/png"),
+            "jpg" | "jpeg" | "image/jpg" => Some("image/jpeg"),
+            "gif" => Some("image/gif"),
+            _ => None,
+        };
+        if let Some(alias) = alias {
+            return Some(alias.to_string());
+        }
+        
+        let mime = value.split(';').next().unwrap_or("").trim();
+        let is_token = |part: &str| !part.is_empty()
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_'));
+        match mime.split_once('/') {
+            Some((kind, subtype)) if is_token(kind) && is_token(subtype) => Some(value),
+            _ => None,
+        }
+    }
+    
+    // 图片等二进制内容，对外以 base64 表示，存储时保存原始字节
+    pub fn is_binary(&self) -> bool {
+        matches!(self, ContentType::Png | ContentType::Jpeg | ContentType::Image)
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+// 同一次复制的其他格式（如网页复制时同时存在的 HTML），主格式保存在 clipboard_items 中
+// 内容是否加密与所属项目一致
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::FromRow)]
+pub struct ItemFormat {
+    pub item_id: String,
+    pub content_type: String,
+    pub content: String,
+}
+
+// 添加项目时附带的其他格式
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FormatRequest {
+    pub content_type: String,
+    pub content: String,
+}
+
+// 格式的丰富程度，数值越大越优先用于还原到剪贴板
+pub fn format_richness(content_type: &str) -> u8 {
+    match content_type {
+        "text/html" => 3,
+        "text/rtf" => 2,
+        t if t.starts_with("image/") => 1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+// 邮件队列状态
+pub const MAIL_STATUS_PENDING: &str = "pending";
+pub const MAIL_STATUS_SENT: &str = "sent";
+pub const MAIL_STATUS_DEAD: &str = "dead";
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct QueuedMail {
+    pub id: String,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MailQueueStatus {
+    pub pending: i64,
+    pub sent: i64,
+    pub dead: i64,
+}
@@ -1,3 +1,13 @@
 pub mod user;
 pub mod clipboard_item;
-pub mod session;
\ No newline at end of file
+pub mod session;
+pub mod tombstone;
+pub mod mail;
+pub mod audit_log;
+pub mod content_type;
+pub mod change;
+pub mod item_format;
+pub mod connection_test;
+pub mod provenance;
+pub mod share;
+pub mod support_bundle;
\ No newline at end of file
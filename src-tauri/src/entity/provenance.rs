@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use crate::entity::clipboard_item::ClipboardItem;
+
+// 某个来源设备贡献的项目数量，device_id 为空表示本机修改或来源未知的旧数据
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OriginDeviceCount {
+    pub device_id: Option<String>,
+    pub device_name: Option<String>, // 设备已解绑时为空
+    pub count: i64,
+}
+
+// 按来源设备查询的结果：指定设备的项目及所有设备的项目数量
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OriginReport {
+    pub items: Vec<ClipboardItem>,
+    pub devices: Vec<OriginDeviceCount>,
+}
+
+// 项目来源状态：remote 为最后由其他设备修改，unknown 为本机修改或没有记录来源的旧数据
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceStatus {
+    Remote,
+    Unknown,
+}
+
+// 单个项目的来源：最后修改的设备及修改时间，来源未知时设备字段为空
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ItemProvenance {
+    pub item_id: String,
+    pub status: ProvenanceStatus,
+    pub device_id: Option<String>,
+    pub device_name: Option<String>, // 设备已解绑时为空
+    pub updated_at: i64,
+}
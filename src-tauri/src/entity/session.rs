@@ -7,4 +7,15 @@ pub struct Session {
     pub device_id: Option<String>,
     pub created_at: i64,
     pub expires_at: i64,
-}
\ No newline at end of file
+}
+
+// 会话列表中的一项（不包含令牌），设备名称和最后活跃时间来自已绑定设备
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SessionInfo {
+    pub device_id: Option<String>,
+    pub device_name: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub last_seen: Option<i64>,
+    pub is_current: bool,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+// 分享记录，payload 为使用分享口令派生密钥加密的项目内容
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Share {
+    pub code: String,
+    pub user_id: String,
+    pub content_type: String,
+    pub payload: Vec<u8>,
+    pub salt: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+// 创建分享后返回给调用方的信息
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ShareLink {
+    pub code: String,
+    pub expires_at: i64,
+}
+
+// 兑换分享得到的明文内容
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SharedContent {
+    pub content: String,
+    pub content_type: String,
+    pub expires_at: i64,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+// 提交问题时附带的诊断包，只包含统计和状态信息，不包含剪贴板内容、令牌和密钥
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SupportBundle {
+    pub app_info: AppInfo,
+    pub generated_at: i64,
+    pub schema_version: i64,
+    pub counts: BundleCounts,
+    pub sync: SyncSummary,
+    pub recent_audit: Vec<AuditSummary>,
+    pub recent_errors: Vec<ErrorSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppInfo {
+    pub name: String,
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+}
+
+// 当前用户的数据量
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BundleCounts {
+    pub items: i64,
+    pub encrypted_items: i64,
+    pub tags: i64,
+    pub tombstones: i64,
+    pub unsynced_items: i64,
+    pub sessions: i64,
+}
+
+// 同步状态，服务器只保留主机和端口，路径和查询参数中可能带有凭据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncSummary {
+    pub server_host: Option<String>,
+    pub connected: bool,
+    pub last_sync_at: i64,
+    pub bound_devices: usize,
+}
+
+// 审计记录只保留操作类型和时间，不包含目标项目
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditSummary {
+    pub action: String,
+    pub created_at: i64,
+}
+
+// 错误信息已去除邮箱、URL 凭据和令牌
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ErrorSummary {
+    pub source: String,
+    pub message: String,
+    pub occurred_at: i64,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+// 删除记录，用于向离线设备传播删除操作
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct Tombstone {
+    pub item_id: String,
+    pub user_id: String,
+    pub deleted_at: i64,
+}
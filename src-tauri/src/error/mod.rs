@@ -5,17 +5,49 @@ pub enum AppError {
     #[error("数据库错误: {0}")]
     DatabaseError(String),
     
+    // 其他连接持有写锁（SQLITE_BUSY / SQLITE_LOCKED），稍后重试通常可以成功
+    #[error("数据库繁忙: {0}")]
+    DatabaseBusy(String),
+    
     #[error("未找到: {0}")]
     NotFound(String),
     
     #[error("无效的凭据")]
     InvalidCredentials,
     
+    // 会话存在但已过期，需要重新登录（与会话不存在的 NotFound 区分）
+    #[error("会话已过期")]
+    SessionExpired,
+    
     #[error("加密错误: {0}")]
     CryptoError(String),
     
     #[error("无效的数据: {0}")]
     InvalidData(String),
     
+    // 当前无法获取解密所需的密钥（例如密钥尚未加载），重新登录后可重试
+    #[error("加密密钥不可用: {0}")]
+    KeyUnavailable(String),
+    
+    // 密文本身已损坏或与密钥不匹配，重试无效
+    #[error("解密失败: {0}")]
+    DecryptionFailed(String),
+    
+    // 请求过于频繁，参数为距下次允许请求的秒数
+    #[error("请求过于频繁，请在 {0} 秒后重试")]
+    RateLimited(i64),
+    
+    // 设置了应用 PIN 且解锁令牌无效或已空闲超时，需要重新输入 PIN
+    #[error("应用已锁定")]
+    AppLocked,
+    
+    // 超出用户的存储硬配额，新内容被拒绝
+    #[error("超出存储配额: {0}")]
+    QuotaExceeded(String),
+    
+    // 读写本地文件失败
+    #[error("文件读写错误: {0}")]
+    IoError(String),
+    
     // 其他错误类型...
 }
\ No newline at end of file
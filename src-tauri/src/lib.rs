@@ -1,5 +1,6 @@
 use sqlx::SqlitePool;
 use std::sync::Arc;
+use std::time::Duration;
 
 // 导入模块
 pub mod entity;
@@ -8,25 +9,37 @@ pub mod service;
 pub mod api;
 pub mod error;
 pub mod util;
+pub mod sync;
+pub mod shutdown;
+pub mod monitor;
+pub mod capture_hook;
+pub mod cache;
+
+#[cfg(test)]
+mod tests;
 
 // 应用状态
 pub struct AppState {
     pub db: SqlitePool,
-    pub cache_queue: Arc<tokio::sync::Mutex<Vec<String>>>, // 简化示例
+    pub recent_items: cache::RecentItemsCache, // 最近同步的项目，按 id 查找，超过容量时淘汰最久未使用的
+    pub tasks: service::task_registry::TaskRegistry,
+    pub sync_notify: Arc<tokio::sync::Notify>, // 本地变更时唤醒同步循环
+    pub sync_manager: tokio::sync::Mutex<Option<Arc<sync::WebSocketManager>>>,
+    pub sync_switch_lock: tokio::sync::Mutex<()>,
+    pub session_cache: service::session_cache::SessionCache,
+    pub maintenance_gate: tokio::sync::RwLock<()>, // 后台任务持有读锁，数据库维护时持有写锁
+    pub compaction_lock: tokio::sync::Mutex<()>,
+    pub capture_hooks: Arc<capture_hook::HookRegistry>, // 剪贴板监控保存前依次执行的处理钩子
+    pub email_check_limiter: service::rate_limiter::RateLimiter, // 邮箱可用性检查的限流
+    pub password_check_limiter: service::rate_limiter::KeyedRateLimiter, // 确认密码的失败次数限制
+    pub credential_check_limiter: service::rate_limiter::KeyedRateLimiter, // 校验其他账号密码的失败次数限制，按邮箱计数
+    pub app_lock: service::app_lock_service::AppLock, // 应用 PIN 的解锁令牌
+    pub profile: service::profile_service::ProfileConfig, // 当前使用的数据目录和配置
 }
 
-// 初始化数据库
-async fn init_database() -> Result<SqlitePool, error::AppError> {
-    // 数据库初始化代码...
-    // 这里是简化的示例
-    let pool = SqlitePool::connect("sqlite:sharing-copyboard.db")
-        .await
-        .map_err(|e| error::AppError::DatabaseError(e.to_string()))?;
-    
-    // 初始化表
-    // ...
-    
-    Ok(pool)
+// 初始化数据库：每个配置使用独立的数据库文件
+async fn init_database(profile: &service::profile_service::ProfileConfig) -> Result<SqlitePool, error::AppError> {
+    service::profile_service::ProfileService::open_pool(&profile.data_dir, &profile.name).await
 }
 
 // 简单的问候函数，用于测试
@@ -38,8 +51,20 @@ fn greet(name: &str) -> String {
 // 应用入口
 pub fn run() {
     tauri::async_runtime::block_on(async {
+        // 按启动参数和环境变量选择配置
+        let profile = match service::profile_service::ProfileService::resolve(
+            std::env::args().skip(1),
+            |key| std::env::var(key).ok(),
+        ) {
+            Ok(profile) => profile,
+            Err(e) => {
+                eprintln!("配置选择失败: {:?}", e);
+                return;
+            }
+        };
+        
         // 初始化数据库
-        let db = match init_database().await {
+        let db = match init_database(&profile).await {
             Ok(pool) => pool,
             Err(e) => {
                 eprintln!("数据库初始化失败: {:?}", e);
@@ -47,15 +72,76 @@ pub fn run() {
             }
         };
         
-        // 初始化缓存系统 - 直接创建而不是使用不存在的模块
-        let cache_queue = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        // 首次运行时生成本机的设备 ID（包括从使用应用标识作为设备 ID 的旧版本升级）
+        if let Err(e) = service::sync_service::SyncService::get_device_id(&db).await {
+            eprintln!("设备 ID 初始化失败: {:?}", e);
+        }
+        
+        // 清理外键约束启用前遗留的孤儿记录（只执行一次）
+        match service::maintenance_service::MaintenanceService::repair_orphans_once(&db).await {
+            Ok(Some(report)) if !report.orphans.is_empty() => {
+                eprintln!("已清理孤儿记录: {:?}", report.orphans);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("孤儿记录检查失败: {:?}", e),
+        }
+        
+        // 规范化旧数据中自由填写的 content_type（只执行一次）
+        match service::maintenance_service::MaintenanceService::normalize_content_types_once(&db).await {
+            Ok(Some(report)) if report.changed > 0 => {
+                eprintln!("已规范化 content_type: {:?}", report.changes);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("content_type 规范化失败: {:?}", e),
+        }
+        
+        // 清理已过期的分享
+        if let Err(e) = service::maintenance_service::MaintenanceService::prune_expired_shares(&db).await {
+            eprintln!("清理过期分享失败: {:?}", e);
+        }
+        
+        // 清理已过期的快照
+        if let Err(e) = service::maintenance_service::MaintenanceService::prune_expired_snapshots(&db).await {
+            eprintln!("清理过期快照失败: {:?}", e);
+        }
         
         // 创建应用状态
         let app_state = Arc::new(AppState {
             db,
-            cache_queue,
+            recent_items: cache::RecentItemsCache::default(),
+            tasks: service::task_registry::TaskRegistry::new(),
+            sync_notify: Arc::new(tokio::sync::Notify::new()),
+            sync_manager: tokio::sync::Mutex::new(None),
+            sync_switch_lock: tokio::sync::Mutex::new(()),
+            session_cache: service::session_cache::SessionCache::default(),
+            maintenance_gate: tokio::sync::RwLock::new(()),
+            compaction_lock: tokio::sync::Mutex::new(()),
+            capture_hooks: Arc::new(capture_hook::HookRegistry::with_defaults()),
+            email_check_limiter: service::rate_limiter::RateLimiter::new(
+                service::user_service::EMAIL_CHECK_MAX_PER_MINUTE,
+                60,
+            ),
+            password_check_limiter: service::rate_limiter::KeyedRateLimiter::new(
+                service::auth_service::PASSWORD_CHECK_MAX_FAILURES,
+                service::auth_service::PASSWORD_CHECK_WINDOW_SECS,
+            ),
+            credential_check_limiter: service::rate_limiter::KeyedRateLimiter::new(
+                service::auth_service::PASSWORD_CHECK_MAX_FAILURES,
+                service::auth_service::PASSWORD_CHECK_WINDOW_SECS,
+            ),
+            app_lock: service::app_lock_service::AppLock::new(),
+            profile,
         });
         
+        // 启动邮件发送后台任务
+        let mail_handle = tauri::async_runtime::spawn(service::mail_service::MailService::run_worker(
+            app_state.db.clone(),
+            service::mail_service::LogMailSender,
+        ));
+        app_state.tasks.register(service::task_registry::MAIL_WORKER_TASK, mail_handle).await;
+        
+        let shutdown_state = app_state.clone();
+        
         tauri::Builder::default()
             .plugin(tauri_plugin_opener::init())
             .plugin(tauri_plugin_clipboard_manager::init())
@@ -67,20 +153,141 @@ pub fn run() {
                 api::clipboard_api::add_clipboard_item,
                 api::clipboard_api::update_clipboard_item,
                 api::clipboard_api::delete_clipboard_item,
+                api::clipboard_api::set_item_note,
+                api::clipboard_api::append_to_item,
                 api::clipboard_api::search_clipboard_items,
+                api::clipboard_api::tag_matching,
+                api::clipboard_api::dedupe_history,
+                api::clipboard_api::clear_history,
+                api::clipboard_api::get_items_by_source,
+                api::clipboard_api::get_items_by_origin,
+                api::clipboard_api::get_item_provenance,
+                api::clipboard_api::list_content_types,
+                api::clipboard_api::peek_item,
+                api::clipboard_api::get_item_formats,
+                api::clipboard_api::reveal_item,
+                api::clipboard_api::copy_item_transformed,
+                api::clipboard_api::reassign_items,
+                api::clipboard_api::move_item_to_account,
+                api::clipboard_api::reset_encryption,
+                api::clipboard_api::invalidate_key_cache,
+                api::stats_api::get_statistics,
+                api::stats_api::get_storage_usage,
+                api::change_api::get_changes_since,
+                api::change_api::get_items_changed_since,
+                api::backup_api::export_encrypted_backup,
+                api::backup_api::import_encrypted_backup,
+                api::backup_api::create_snapshot,
+                api::backup_api::restore_snapshot,
+                api::share_api::create_share_link,
+                api::share_api::redeem_share,
                 api::clipboard_api::start_clipboard_monitor,
                 
+                // 后台任务相关命令
+                api::task_api::list_background_tasks,
+                api::task_api::stop_background_task,
+                
+                // 同步相关命令
+                api::sync_api::switch_sync_server,
+                api::sync_api::get_tombstones,
+                api::sync_api::set_pinned_cert,
+                api::sync_api::retry_sync,
+                api::sync_api::get_reconnect_policy,
+                api::sync_api::set_reconnect_policy,
+                api::sync_api::get_merge_policy,
+                api::sync_api::set_merge_policy,
+                api::sync_api::get_device_sync_filter,
+                api::sync_api::set_device_sync_filter,
+                api::sync_api::get_device_id,
+                api::sync_api::regenerate_device_id,
+                api::sync_api::unbind_device,
+                api::sync_api::send_item_to_device,
+                api::sync_api::dedupe_devices,
+                api::sync_api::mark_all_synced,
+                api::sync_api::mark_all_unsynced,
+                
+                // 设置相关命令
+                api::settings_api::get_sanitize_settings,
+                api::settings_api::set_sanitize_settings,
+                api::settings_api::get_quiet_hours,
+                api::settings_api::set_quiet_hours,
+                api::settings_api::get_encryption_policy,
+                api::settings_api::set_encryption_policy,
+                api::settings_api::get_storage_quota,
+                api::settings_api::set_storage_quota,
+                api::settings_api::get_encrypted_search,
+                api::settings_api::set_encrypted_search,
+                api::settings_api::get_cipher,
+                api::settings_api::set_cipher,
+                api::settings_api::get_max_page_size,
+                api::settings_api::set_max_page_size,
+                api::settings_api::get_capture_cooldown,
+                api::settings_api::set_capture_cooldown,
+                api::settings_api::get_email_check_enabled,
+                api::settings_api::set_email_check_enabled,
+                api::settings_api::get_auto_monitor,
+                api::settings_api::set_auto_monitor,
+                api::settings_api::get_capture_types,
+                api::settings_api::set_capture_types,
+                api::settings_api::get_preview_lengths,
+                api::settings_api::set_preview_lengths,
+                api::settings_api::get_redaction_patterns,
+                api::settings_api::set_redaction_patterns,
+                
+                // 应用锁相关命令
+                api::app_lock_api::set_app_pin,
+                api::app_lock_api::verify_app_pin,
+                api::app_lock_api::check_app_unlock,
+                api::app_lock_api::get_app_lock_idle_secs,
+                api::app_lock_api::set_app_lock_idle_secs,
+                
+                // 诊断相关命令
+                api::diagnostics_api::get_mail_queue_status,
+                api::diagnostics_api::get_diagnostics,
+                api::diagnostics_api::export_support_bundle,
+                api::diagnostics_api::compact_database,
+                api::diagnostics_api::repair_orphans,
+                api::diagnostics_api::normalize_content_types,
+                api::diagnostics_api::test_smtp_config,
+                api::diagnostics_api::test_sync_server,
+                
+                // 配置相关命令
+                api::profile_api::list_profiles,
+                api::profile_api::switch_profile,
+                
                 // 账户相关命令
                 api::user_api::register_user,
+                api::user_api::resend_verification_code,
+                api::user_api::email_available,
                 api::user_api::login_user,
+                api::user_api::auto_start_monitor,
                 api::user_api::logout_user,
+                api::user_api::list_sessions,
+                api::user_api::logout_others,
+                api::user_api::revoke_device,
+                api::user_api::provision_key_to_device,
                 api::user_api::get_user_profile,
                 api::user_api::update_user_profile,
                 api::user_api::change_password,
+                api::user_api::verify_current_password,
                 api::user_api::request_password_reset,
+                api::user_api::cancel_password_reset,
                 api::user_api::reset_password
             ])
-            .run(tauri::generate_context!())
-            .expect("error while running tauri application");
+            .build(tauri::generate_context!())
+            .expect("error while building tauri application")
+            .run(move |_app_handle, event| {
+                if let tauri::RunEvent::Exit = event {
+                    // 在独立线程中等待关闭流程完成，避免在异步运行时内部阻塞
+                    let state = shutdown_state.clone();
+                    let _ = std::thread::spawn(move || {
+                        tauri::async_runtime::block_on(shutdown::graceful_shutdown(
+                            &state,
+                            Duration::from_secs(shutdown::SHUTDOWN_FLUSH_TIMEOUT_SECS),
+                        ));
+                    })
+                    .join();
+                }
+            });
     });
 }
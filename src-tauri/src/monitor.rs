@@ -0,0 +1,449 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use crate::AppState;
+use crate::capture_hook::HookRegistry;
+use crate::entity::clipboard_item::{ClipboardItem, ClipboardItemRequest};
+use crate::entity::content_type::ContentType;
+use crate::entity::item_format::FormatRequest;
+use crate::service::clipboard_service::ClipboardService;
+use crate::error::AppError;
+use crate::service::settings_service::SettingsService;
+use crate::service::task_registry::CLIPBOARD_MONITOR_TASK;
+use crate::util::encoding;
+use crate::util::source_app;
+use sha2::{Digest, Sha256};
+
+// 剪贴板轮询间隔（毫秒）
+pub const POLL_INTERVAL_MS: u64 = 500;
+// 读取连续失败后退避的最长轮询间隔（毫秒）
+pub const MAX_POLL_INTERVAL_MS: u64 = 10_000;
+// 连续读取失败达到该次数后通知前端并开始退避
+pub const READ_FAILURE_THRESHOLD: u32 = 5;
+
+// 剪贴板中的图片（RGBA 像素）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl ClipboardImage {
+    // 变化检测使用的标识：尺寸加像素的 SHA-256，不保存完整像素
+    fn signature(&self) -> String {
+        let digest = Sha256::digest(&self.rgba);
+        let hash: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("image:{}x{}:{}", self.width, self.height, hash)
+    }
+
+    // 编码为 PNG，保存为 image/png 项目
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&self.rgba).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        
+        Ok(bytes)
+    }
+}
+
+// 剪贴板访问的抽象：正式运行时使用 Tauri 插件，测试时使用 MockClipboardProvider
+pub trait ClipboardProvider: Send + Sync {
+    fn read_text(&self) -> Result<String, String>;
+    fn read_image(&self) -> Result<ClipboardImage, String>;
+    // 读取同一次复制中的 HTML 表示，没有时返回 None
+    fn read_html(&self) -> Result<Option<String>, String>;
+    fn write_text(&self, text: &str) -> Result<(), String>;
+}
+
+impl<R: Runtime> ClipboardProvider for AppHandle<R> {
+    fn read_text(&self) -> Result<String, String> {
+        self.clipboard().read_text().map_err(|e| e.to_string())
+    }
+
+    fn read_image(&self) -> Result<ClipboardImage, String> {
+        let image = self.clipboard().read_image().map_err(|e| e.to_string())?;
+        Ok(ClipboardImage {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.rgba().to_vec(),
+        })
+    }
+
+    // 剪贴板插件只支持写入 HTML，暂时无法读取
+    fn read_html(&self) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.clipboard().write_text(text.to_string()).map_err(|e| e.to_string())
+    }
+}
+
+// 内存中的剪贴板，读取结果可以预先排队（用于模拟读取失败），队列为空时返回当前内容
+#[derive(Debug, Default)]
+pub struct MockClipboardProvider {
+    text: Mutex<String>,
+    image: Mutex<Option<ClipboardImage>>,
+    html: Mutex<Option<String>>,
+    queued_reads: Mutex<Vec<Result<String, String>>>,
+}
+
+impl MockClipboardProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_image(&self, image: Option<ClipboardImage>) {
+        *self.image.lock().unwrap() = image;
+    }
+
+    pub fn set_html(&self, html: Option<String>) {
+        *self.html.lock().unwrap() = html;
+    }
+
+    // 让接下来的 read_text 依次返回这些结果
+    pub fn queue_reads(&self, results: Vec<Result<String, String>>) {
+        let mut queued = self.queued_reads.lock().unwrap();
+        queued.extend(results);
+    }
+}
+
+impl ClipboardProvider for MockClipboardProvider {
+    fn read_text(&self) -> Result<String, String> {
+        let mut queued = self.queued_reads.lock().unwrap();
+        if !queued.is_empty() {
+            return queued.remove(0);
+        }
+
+        Ok(self.text.lock().unwrap().clone())
+    }
+
+    fn read_image(&self) -> Result<ClipboardImage, String> {
+        self.image
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "clipboard does not contain an image".to_string())
+    }
+
+    fn read_html(&self) -> Result<Option<String>, String> {
+        Ok(self.html.lock().unwrap().clone())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        *self.text.lock().unwrap() = text.to_string();
+        Ok(())
+    }
+}
+
+// clipboard_access_error 事件的内容
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ClipboardAccessError {
+    pub consecutive_failures: u32,
+    pub message: String,
+}
+
+// 单次读取后需要通知前端的状态变化
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadEvent {
+    // 连续失败刚达到阈值
+    AccessLost(ClipboardAccessError),
+    // 已通知过失败，读取重新成功
+    AccessRestored,
+}
+
+// 统计连续读取失败次数并计算退避后的轮询间隔
+// 部分 Linux（尤其是 Wayland）环境下读取会间歇性失败，偶发失败不打扰用户
+#[derive(Debug, Default)]
+pub struct ReadFailureTracker {
+    consecutive_failures: u32,
+}
+
+impl ReadFailureTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    // 读取剪贴板并更新失败计数，返回读取到的内容和需要发出的事件
+    pub fn read(&mut self, provider: &impl ClipboardProvider) -> (Option<String>, Option<ReadEvent>) {
+        match provider.read_text() {
+            Ok(content) => {
+                let event = self.record_success();
+                (Some(content), event)
+            }
+            Err(message) => (None, self.record_failure(message)),
+        }
+    }
+
+    fn record_failure(&mut self, message: String) -> Option<ReadEvent> {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+
+        // 只在刚达到阈值时通知一次，避免持续失败时反复发送事件
+        if self.consecutive_failures == READ_FAILURE_THRESHOLD {
+            Some(ReadEvent::AccessLost(ClipboardAccessError {
+                consecutive_failures: self.consecutive_failures,
+                message,
+            }))
+        } else {
+            None
+        }
+    }
+
+    fn record_success(&mut self) -> Option<ReadEvent> {
+        let reported = self.consecutive_failures >= READ_FAILURE_THRESHOLD;
+        self.consecutive_failures = 0;
+
+        if reported {
+            Some(ReadEvent::AccessRestored)
+        } else {
+            None
+        }
+    }
+
+    // 未达到阈值时使用正常间隔，之后每多失败一次间隔翻倍，直到上限
+    pub fn poll_interval(&self) -> Duration {
+        if self.consecutive_failures < READ_FAILURE_THRESHOLD {
+            return Duration::from_millis(POLL_INTERVAL_MS);
+        }
+
+        let exponent = (self.consecutive_failures - READ_FAILURE_THRESHOLD + 1).min(16);
+        let interval = POLL_INTERVAL_MS.saturating_mul(1u64 << exponent);
+        Duration::from_millis(interval.min(MAX_POLL_INTERVAL_MS))
+    }
+}
+
+// 将读取状态变化通知前端
+pub fn emit_read_event<R: Runtime>(app_handle: &AppHandle<R>, event: ReadEvent) {
+    match event {
+        ReadEvent::AccessLost(error) => {
+            eprintln!("剪贴板连续读取失败 {} 次: {}", error.consecutive_failures, error.message);
+            let _ = app_handle.emit("clipboard_access_error", error);
+        }
+        ReadEvent::AccessRestored => {
+            let _ = app_handle.emit("clipboard_access_restored", ());
+        }
+    }
+}
+
+// 监控循环在两次轮询之间保留的状态
+#[derive(Debug, Default)]
+pub struct MonitorState {
+    last_content: String,
+    read_tracker: ReadFailureTracker,
+    hooks: Arc<HookRegistry>,
+    last_saved_at: Option<Instant>,
+}
+
+impl MonitorState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 保存前依次执行注册表中的钩子
+    pub fn with_hooks(hooks: Arc<HookRegistry>) -> Self {
+        Self {
+            hooks,
+            ..Self::default()
+        }
+    }
+
+    // 下一次轮询前的等待时间
+    pub fn poll_interval(&self) -> Duration {
+        self.read_tracker.poll_interval()
+    }
+}
+
+// 单次轮询的结果
+#[derive(Debug, Default)]
+pub struct PollOutcome {
+    // 新保存的项目
+    pub saved: Option<ClipboardItem>,
+    // 需要通知前端的读取状态变化
+    pub event: Option<ReadEvent>,
+}
+
+// 轮询一次剪贴板：内容变化时保存为新项目，剪贴板中没有文本时保存图片
+// 免打扰时段内只记录当前内容，不保存，避免结束后补采
+pub async fn poll_clipboard(
+    provider: &impl ClipboardProvider,
+    pool: &SqlitePool,
+    user_id: &str,
+    state: &mut MonitorState,
+) -> PollOutcome {
+    let quiet = SettingsService::is_quiet_now(pool).await.unwrap_or(false);
+
+    let (content, event) = state.read_tracker.read(provider);
+    let mut outcome = PollOutcome { saved: None, event };
+
+    let content = match content {
+        Some(content) => content,
+        None => return outcome,
+    };
+
+    // 图片以标识代替内容参与变化检测
+    let image = match content.is_empty() {
+        true => provider.read_image().ok(),
+        false => None,
+    };
+    let signature = match &image {
+        Some(image) => image.signature(),
+        None => content.clone(),
+    };
+
+    if quiet {
+        state.last_content = signature;
+        return outcome;
+    }
+
+    if signature.is_empty() || signature == state.last_content {
+        return outcome;
+    }
+
+    // 距上次保存不足冷却时间时暂不保存，也不记录当前内容：
+    // 冷却结束后的轮询会保存届时剪贴板中的最新内容，中间快速变化的内容被丢弃
+    let cooldown = SettingsService::get_capture_cooldown_ms(pool).await.unwrap_or(0);
+    if let Some(last_saved_at) = state.last_saved_at {
+        if last_saved_at.elapsed() < Duration::from_millis(cooldown as u64) {
+            return outcome;
+        }
+    }
+
+    // 不在允许类别中的内容不保存，同样记录当前内容，避免下次轮询重复处理
+    let content_type = match image {
+        Some(_) => ContentType::Png,
+        None => ContentType::Text,
+    };
+    let capture_types = SettingsService::get_capture_types(pool)
+        .await
+        .unwrap_or_else(|_| ContentType::ALL.to_vec());
+    if !capture_types.contains(&content_type) {
+        state.last_content = signature;
+        return outcome;
+    }
+
+    let item_request = match &image {
+        Some(image) => {
+            let png = match image.to_png() {
+                Ok(png) => png,
+                Err(e) => {
+                    eprintln!("编码剪贴板图片失败: {}", e);
+                    state.last_content = signature;
+                    return outcome;
+                }
+            };
+            ClipboardItemRequest {
+                content: encoding::encode(png),
+                content_type: "image/png".to_string(),
+                encrypt: None, // 使用用户的默认加密策略
+                source_app: source_app::foreground_app_name(),
+                is_sensitive: None,
+                alternate_formats: Vec::new(),
+            }
+        }
+        None => {
+            // 同一次复制的 HTML 表示作为其他格式保存到同一个项目，读取失败时只保存文本
+            let alternate_formats = match provider.read_html() {
+                Ok(Some(html)) if !html.is_empty() => vec![FormatRequest {
+                    content_type: "text/html".to_string(),
+                    content: html,
+                }],
+                _ => Vec::new(),
+            };
+
+            // 内容变化，保存到数据库
+            ClipboardItemRequest {
+                content,
+                content_type: "text/plain".to_string(),
+                encrypt: None, // 使用用户的默认加密策略
+                source_app: source_app::foreground_app_name(),
+                is_sensitive: None,
+                alternate_formats,
+            }
+        }
+    };
+
+    // 钩子可以修改或丢弃本次捕获；丢弃时同样记录当前内容，避免下次轮询重复处理
+    if let Some(item_request) = state.hooks.run(item_request).await {
+        match ClipboardService::add_item(pool, user_id, &item_request).await {
+            Ok(item) => {
+                outcome.saved = Some(item);
+                state.last_saved_at = Some(Instant::now());
+            }
+            Err(e) => eprintln!("保存剪贴板内容失败: {:?}", e),
+        }
+    }
+    state.last_content = signature;
+
+    outcome
+}
+
+// 启动监控循环：按轮询间隔读取剪贴板并保存新内容，on_event 接收需要通知前端的读取状态变化
+pub fn spawn_monitor<P, F>(
+    app_state: Arc<AppState>,
+    provider: P,
+    user_id: String,
+    on_event: F,
+) -> tauri::async_runtime::JoinHandle<()>
+where
+    P: ClipboardProvider + 'static,
+    F: Fn(ReadEvent) + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut monitor_state = MonitorState::with_hooks(app_state.capture_hooks.clone());
+        
+        loop {
+            // 数据库维护期间暂停
+            let maintenance = app_state.maintenance_gate.read().await;
+            
+            let outcome = poll_clipboard(&provider, &app_state.db, &user_id, &mut monitor_state).await;
+            
+            // 读取连续失败或恢复时通知前端
+            if let Some(event) = outcome.event {
+                on_event(event);
+            }
+            if outcome.saved.is_some() {
+                app_state.sync_notify.notify_one();
+            }
+            drop(maintenance);
+            
+            // 等待一段时间再检查，读取持续失败时间隔会逐步变长
+            tokio::time::sleep(monitor_state.poll_interval()).await;
+        }
+    })
+}
+
+// 开启了自动监控时启动监控任务，已在运行时不重复启动；返回是否启动了新任务
+pub async fn auto_start_monitor<P, F>(
+    app_state: &Arc<AppState>,
+    provider: P,
+    user_id: &str,
+    on_event: F,
+) -> Result<bool, AppError>
+where
+    P: ClipboardProvider + 'static,
+    F: Fn(ReadEvent) + Send + 'static,
+{
+    if !SettingsService::get_auto_monitor(&app_state.db).await? {
+        return Ok(false);
+    }
+    
+    let started = app_state.tasks
+        .register_if_absent(CLIPBOARD_MONITOR_TASK, || {
+            spawn_monitor(app_state.clone(), provider, user_id.to_string(), on_event)
+        })
+        .await;
+    Ok(started)
+}
@@ -0,0 +1,57 @@
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+use crate::entity::audit_log::AuditEntry;
+use crate::error::AppError;
+
+pub struct AuditRepository;
+
+impl AuditRepository {
+    pub async fn record(
+        pool: &SqlitePool,
+        user_id: &str,
+        action: &str,
+        target_id: Option<&str>
+    ) -> Result<(), AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        sqlx::query(
+            "INSERT INTO audit_log (id, user_id, action, target_id, created_at)
+             VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(action)
+        .bind(target_id)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    pub async fn find_by_user(
+        pool: &SqlitePool,
+        user_id: &str,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<AuditEntry>, AppError> {
+        let entries = sqlx::query_as::<_, AuditEntry>(
+            "SELECT id, user_id, action, target_id, created_at
+             FROM audit_log WHERE user_id = ?
+             ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?"
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(entries)
+    }
+}
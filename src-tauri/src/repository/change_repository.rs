@@ -0,0 +1,78 @@
+use crate::entity::change::ChangeRecord;
+use crate::error::AppError;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 保留的变更记录条数，更早的记录会被清理
+pub const CHANGE_RETENTION_COUNT: i64 = 10_000;
+
+pub struct ChangeRepository;
+
+impl ChangeRepository {
+    // 在调用方的连接或事务中追加变更记录，并清理超出保留范围的旧记录
+    pub async fn append(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        op: &str,
+        item_id: &str,
+    ) -> Result<i64, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let seq = sqlx::query(
+            "INSERT INTO changes (user_id, op, item_id, created_at)
+             VALUES (?, ?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(op)
+        .bind(item_id)
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .last_insert_rowid();
+
+        sqlx::query("DELETE FROM changes WHERE seq <= ?")
+            .bind(seq - CHANGE_RETENTION_COUNT)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(seq)
+    }
+
+    pub async fn find_since(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64,
+        limit: i64,
+    ) -> Result<Vec<ChangeRecord>, AppError> {
+        let changes = sqlx::query_as::<_, ChangeRecord>(
+            "SELECT seq, user_id, op, item_id, created_at
+             FROM changes WHERE user_id = ? AND seq > ?
+             ORDER BY seq ASC LIMIT ?"
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(changes)
+    }
+
+    // 返回 (最小 seq, 最大 seq)，没有记录时为 None
+    pub async fn seq_range(pool: &SqlitePool) -> Result<(Option<i64>, Option<i64>), AppError> {
+        let range = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+            "SELECT MIN(seq), MAX(seq) FROM changes"
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(range)
+    }
+}
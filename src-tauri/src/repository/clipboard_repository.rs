@@ -1,80 +1,335 @@
 use crate::entity::clipboard_item::ClipboardItem;
 use crate::error::AppError;
-use sqlx::SqlitePool;
+use crate::entity::change::{CHANGE_OP_ADD, CHANGE_OP_DELETE, CHANGE_OP_UPDATE};
+use crate::repository::change_repository::ChangeRepository;
+use crate::repository::search_index_repository::SearchIndexRepository;
+use crate::repository::item_format_repository::ItemFormatRepository;
+use crate::repository::tombstone_repository::TombstoneRepository;
+use crate::repository::thumbnail_repository::ThumbnailRepository;
+use crate::util::db::{retry_on_busy, write_error};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// SQLite 默认每条语句最多 999 个参数，批量插入按此拆分
+pub const MAX_SQL_PARAMS: usize = 999;
+// 批量插入每行绑定的参数个数
+const SAVE_MANY_COLUMNS: usize = 13;
+
+// search 的匹配条件，参数依次为 user_id、LIKE 模式、include_notes、LIKE 模式
+const SEARCH_FILTER: &str = "user_id = ? AND (content LIKE ? OR (? AND note LIKE ?))";
 
 pub struct ClipboardRepository;
 
 impl ClipboardRepository {
+    // 保存项目并追加变更记录，数据库繁忙时整体重试
     pub async fn save(pool: &SqlitePool, item: &ClipboardItem) -> Result<(), AppError> {
+        retry_on_busy(|| Self::save_once(pool, item)).await
+    }
+
+    async fn save_once(pool: &SqlitePool, item: &ClipboardItem) -> Result<(), AppError> {
+        let mut tx = pool.begin()
+            .await
+            .map_err(write_error)?;
+
+        Self::save_in(&mut tx, item).await?;
+
+        tx.commit()
+            .await
+            .map_err(write_error)?;
+
+        Ok(())
+    }
+
+    // 在调用方的事务中保存项目并追加变更记录
+    pub async fn save_in(conn: &mut SqliteConnection, item: &ClipboardItem) -> Result<(), AppError> {
+        let payload = item.payload();
+        let (content, content_blob) = payload.columns();
+
         sqlx::query(
-            "INSERT INTO clipboard_items (id, user_id, content, content_type, encrypted, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO clipboard_items (id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&item.id)
         .bind(&item.user_id)
-        .bind(&item.content)
+        .bind(content)
+        .bind(content_blob)
         .bind(&item.content_type)
         .bind(item.encrypted as i32)
         .bind(item.created_at)
         .bind(item.updated_at)
-        .execute(pool)
+        .bind(&item.raw_content)
+        .bind(&item.source_app)
+        .bind(item.is_sensitive as i32)
+        .bind(&item.note)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        .map_err(write_error)?;
+
+        ChangeRepository::append(&mut *conn, &item.user_id, CHANGE_OP_ADD, &item.id).await?;
 
         Ok(())
     }
 
+    // 在一个事务中批量保存项目，返回保存的数量；任一项目失败时全部回滚
+    pub async fn save_many(pool: &SqlitePool, items: &[ClipboardItem]) -> Result<u64, AppError> {
+        retry_on_busy(|| async move {
+            let mut tx = pool.begin()
+                .await
+                .map_err(write_error)?;
+
+            let saved = Self::save_many_in(&mut tx, items, None).await?;
+
+            tx.commit()
+                .await
+                .map_err(write_error)?;
+
+            Ok(saved)
+        }).await
+    }
+
+    // 在调用方的事务中使用多行 INSERT 批量保存项目并追加变更记录
+    // origin 为项目来源的远程设备，本地导入时为空
+    pub async fn save_many_in(
+        conn: &mut SqliteConnection,
+        items: &[ClipboardItem],
+        origin: Option<&str>,
+    ) -> Result<u64, AppError> {
+        let mut saved = 0;
+        for chunk in items.chunks(MAX_SQL_PARAMS / SAVE_MANY_COLUMNS) {
+            let payloads: Vec<_> = chunk.iter().map(|item| item.payload()).collect();
+            let rows = vec!["(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"; chunk.len()].join(", ");
+            let sql = format!(
+                "INSERT INTO clipboard_items (id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note, origin_device_id)
+                 VALUES {}",
+                rows
+            );
+
+            let mut query = sqlx::query(&sql);
+            for (item, payload) in chunk.iter().zip(&payloads) {
+                let (content, content_blob) = payload.columns();
+                query = query
+                    .bind(&item.id)
+                    .bind(&item.user_id)
+                    .bind(content)
+                    .bind(content_blob)
+                    .bind(&item.content_type)
+                    .bind(item.encrypted as i32)
+                    .bind(item.created_at)
+                    .bind(item.updated_at)
+                    .bind(&item.raw_content)
+                    .bind(&item.source_app)
+                    .bind(item.is_sensitive as i32)
+                    .bind(&item.note)
+                    .bind(origin);
+            }
+
+            let result = query
+                .execute(&mut *conn)
+                .await
+                .map_err(write_error)?;
+            saved += result.rows_affected();
+        }
+
+        for item in items {
+            ChangeRepository::append(&mut *conn, &item.user_id, CHANGE_OP_ADD, &item.id).await?;
+        }
+
+        Ok(saved)
+    }
+
+    // 返回给定 ID 中已存在的项目 ID（不区分用户，ID 全局唯一）
+    pub async fn find_existing_ids(pool: &SqlitePool, ids: &[String]) -> Result<HashSet<String>, AppError> {
+        let mut existing = HashSet::new();
+        for chunk in ids.chunks(MAX_SQL_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("SELECT id FROM clipboard_items WHERE id IN ({})", placeholders);
+
+            let mut query = sqlx::query_scalar::<_, String>(&sql);
+            for id in chunk {
+                query = query.bind(id);
+            }
+
+            existing.extend(query
+                .fetch_all(pool)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?);
+        }
+
+        Ok(existing)
+    }
+
     pub async fn update(pool: &SqlitePool, item: &ClipboardItem) -> Result<(), AppError> {
-        sqlx::query(
+        retry_on_busy(|| Self::update_once(pool, item)).await
+    }
+
+    async fn update_once(pool: &SqlitePool, item: &ClipboardItem) -> Result<(), AppError> {
+        let mut tx = pool.begin()
+            .await
+            .map_err(write_error)?;
+
+        Self::update_in(&mut tx, item).await?;
+
+        tx.commit()
+            .await
+            .map_err(write_error)?;
+
+        Ok(())
+    }
+
+    // 在调用方的事务中更新项目并追加变更记录
+    pub async fn update_in(conn: &mut SqliteConnection, item: &ClipboardItem) -> Result<(), AppError> {
+        let payload = item.payload();
+        let (content, content_blob) = payload.columns();
+
+        let result = sqlx::query(
             "UPDATE clipboard_items SET
              content = ?,
+             content_blob = ?,
              content_type = ?,
              encrypted = ?,
-             updated_at = ?
+             updated_at = ?,
+             raw_content = ?,
+             is_sensitive = ?,
+             note = ?,
+             origin_device_id = NULL
              WHERE id = ? AND user_id = ?",
         )
-        .bind(&item.content)
+        .bind(content)
+        .bind(content_blob)
         .bind(&item.content_type)
         .bind(item.encrypted as i32)
         .bind(item.updated_at)
+        .bind(&item.raw_content)
+        .bind(item.is_sensitive as i32)
+        .bind(&item.note)
         .bind(&item.id)
         .bind(&item.user_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        .map_err(write_error)?;
+
+        if result.rows_affected() > 0 {
+            ChangeRepository::append(&mut *conn, &item.user_id, CHANGE_OP_UPDATE, &item.id).await?;
+        }
 
         Ok(())
     }
 
+    // 删除项目并写入删除记录
     pub async fn delete(pool: &SqlitePool, id: &str, user_id: &str) -> Result<(), AppError> {
-        sqlx::query("DELETE FROM clipboard_items WHERE id = ? AND user_id = ?")
-            .bind(id)
-            .bind(user_id)
-            .execute(pool)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Self::delete_many(pool, &[id.to_string()], user_id).await?;
 
         Ok(())
     }
 
+    // 在一个事务中批量删除，返回删除的行数
+    pub async fn delete_many(pool: &SqlitePool, ids: &[String], user_id: &str) -> Result<u64, AppError> {
+        retry_on_busy(|| async move {
+            let mut tx = pool.begin()
+                .await
+                .map_err(write_error)?;
+            
+            let deleted = Self::delete_many_in(&mut tx, ids, user_id).await?;
+            
+            tx.commit()
+                .await
+                .map_err(write_error)?;
+
+            Ok(deleted)
+        }).await
+    }
+
+    // 在调用方的事务中批量删除，同时写入删除记录并清理索引、其他格式和缩略图
+    pub async fn delete_many_in(conn: &mut SqliteConnection, ids: &[String], user_id: &str) -> Result<u64, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let mut deleted = 0;
+        for id in ids {
+            let result = sqlx::query("DELETE FROM clipboard_items WHERE id = ? AND user_id = ?")
+                .bind(id)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+                .map_err(write_error)?;
+            
+            if result.rows_affected() > 0 {
+                TombstoneRepository::record(&mut *conn, id, user_id, now).await?;
+                ChangeRepository::append(&mut *conn, user_id, CHANGE_OP_DELETE, id).await?;
+                SearchIndexRepository::remove(&mut *conn, id).await?;
+                ItemFormatRepository::delete_by_item_id(&mut *conn, id).await?;
+                ThumbnailRepository::delete_by_item_id(&mut *conn, id).await?;
+                deleted += result.rows_affected();
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    // 在调用方的事务中清空用户的历史，include_pinned 为 false 时保留置顶项目，返回删除的数量
+    // 标签和同步状态显式清理，不依赖外键级联；删除记录由 delete_many_in 写入，供同步传播
+    pub async fn clear_history_in(conn: &mut SqliteConnection, user_id: &str, include_pinned: bool) -> Result<u64, AppError> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM clipboard_items WHERE user_id = ? AND (? OR is_pinned = 0)"
+        )
+        .bind(user_id)
+        .bind(include_pinned)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(write_error)?;
+
+        for id in &ids {
+            sqlx::query("DELETE FROM item_tags WHERE item_id = ?")
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .map_err(write_error)?;
+
+            sqlx::query("DELETE FROM sync_status WHERE item_id = ?")
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .map_err(write_error)?;
+        }
+
+        Self::delete_many_in(&mut *conn, &ids, user_id).await
+    }
+
     pub async fn find_by_id(
         pool: &SqlitePool,
         id: &str,
         user_id: &str,
+    ) -> Result<Option<ClipboardItem>, AppError> {
+        let mut conn = pool.acquire()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::find_by_id_in(&mut conn, id, user_id).await
+    }
+
+    // 在调用方的事务中读取项目
+    pub async fn find_by_id_in(
+        conn: &mut SqliteConnection,
+        id: &str,
+        user_id: &str,
     ) -> Result<Option<ClipboardItem>, AppError> {
         let item = sqlx::query_as::<_, ClipboardItem>(
-            "SELECT id, user_id, content, content_type, encrypted as \"encrypted: bool\", created_at, updated_at
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
              FROM clipboard_items WHERE id = ? AND user_id = ?"
         )
         .bind(id)
         .bind(user_id)
-        .fetch_optional(pool)
+        .fetch_optional(conn)
         .await
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         Ok(item)
     }
 
+    // 更新时间相同时按 id 排序，保证多次查询及分页的顺序稳定
     pub async fn find_all_by_user_id(
         pool: &SqlitePool,
         user_id: &str,
@@ -82,8 +337,8 @@ impl ClipboardRepository {
         offset: i64,
     ) -> Result<Vec<ClipboardItem>, AppError> {
         let items = sqlx::query_as::<_, ClipboardItem>(
-            "SELECT id, user_id, content, content_type, encrypted as \"encrypted: bool\", created_at, updated_at
-             FROM clipboard_items WHERE user_id = ? ORDER BY updated_at DESC LIMIT ? OFFSET ?"
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items WHERE user_id = ? ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
         )
         // user_id, limit, offset
         .bind(user_id)
@@ -96,24 +351,190 @@ impl ClipboardRepository {
         Ok(items)
     }
 
+    // 获取更新时间晚于指定时间的项目，按更新时间升序
+    pub async fn find_updated_since(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64,
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let items = sqlx::query_as::<_, ClipboardItem>(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items WHERE user_id = ? AND updated_at > ? ORDER BY updated_at ASC, id ASC"
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items)
+    }
+
+    // 获取用户全部项目，按创建时间升序
+    pub async fn find_all_by_user_id_oldest_first(
+        pool: &SqlitePool,
+        user_id: &str,
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let items = sqlx::query_as::<_, ClipboardItem>(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items WHERE user_id = ? ORDER BY created_at ASC, id ASC"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items)
+    }
+
+    pub async fn find_by_source_app(
+        pool: &SqlitePool,
+        user_id: &str,
+        source_app: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let items = sqlx::query_as::<_, ClipboardItem>(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items WHERE user_id = ? AND source_app = ?
+             ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
+        )
+        .bind(user_id)
+        .bind(source_app)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items)
+    }
+
+    // 获取最后由指定设备修改的项目
+    pub async fn find_by_origin_device(
+        pool: &SqlitePool,
+        user_id: &str,
+        device_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let items = sqlx::query_as::<_, ClipboardItem>(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items WHERE user_id = ? AND origin_device_id = ?
+             ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
+        )
+        .bind(user_id)
+        .bind(device_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items)
+    }
+
+    // 获取项目的来源设备和最后修改时间，项目不存在时返回 None
+    pub async fn find_origin(
+        pool: &SqlitePool,
+        id: &str,
+        user_id: &str,
+    ) -> Result<Option<(Option<String>, i64)>, AppError> {
+        let origin = sqlx::query_as(
+            "SELECT origin_device_id, updated_at FROM clipboard_items WHERE id = ? AND user_id = ?"
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(origin)
+    }
+
+    // 在调用方的事务中统计全部用户项目的 content_type（规范化旧数据时使用）
+    pub async fn count_all_content_types_in(conn: &mut SqliteConnection) -> Result<Vec<(String, i64)>, AppError> {
+        let counts = sqlx::query_as(
+            "SELECT content_type, COUNT(*) FROM clipboard_items GROUP BY content_type ORDER BY content_type"
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(counts)
+    }
+
+    // 在调用方的事务中将全部项目的 content_type 从 from 改为 to，返回修改的行数
+    // 只修正类型标记，不修改 updated_at，也不追加变更记录
+    pub async fn rename_content_type_in(conn: &mut SqliteConnection, from: &str, to: &str) -> Result<u64, AppError> {
+        let result = sqlx::query("UPDATE clipboard_items SET content_type = ? WHERE content_type = ?")
+            .bind(to)
+            .bind(from)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 按声明的类型统计用户的项目数量（加密项目同样按其类型计数）
+    pub async fn count_by_content_type(
+        pool: &SqlitePool,
+        user_id: &str,
+    ) -> Result<Vec<(String, i64)>, AppError> {
+        let counts = sqlx::query_as(
+            "SELECT content_type, COUNT(*) FROM clipboard_items
+             WHERE user_id = ? GROUP BY content_type ORDER BY COUNT(*) DESC, content_type"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(counts)
+    }
+
+    // 按来源设备统计用户的项目数量，来源为空的项目归为一组
+    pub async fn count_by_origin_device(
+        pool: &SqlitePool,
+        user_id: &str,
+    ) -> Result<Vec<(Option<String>, i64)>, AppError> {
+        let counts = sqlx::query_as(
+            "SELECT origin_device_id, COUNT(*) FROM clipboard_items
+             WHERE user_id = ? GROUP BY origin_device_id ORDER BY COUNT(*) DESC, origin_device_id"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(counts)
+    }
+
+    // include_notes 为 true 时备注匹配的项目也会返回
     pub async fn search(
         pool: &SqlitePool,
         user_id: &str,
         query: &str,
+        include_notes: bool,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<ClipboardItem>, AppError> {
         let search_query = format!("%{}%", query);
 
-        let items = sqlx::query_as::<_, ClipboardItem>(
-            "SELECT id, user_id, content, content_type, encrypted as \"encrypted: bool\", created_at, updated_at
+        let sql = format!(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
              FROM clipboard_items 
-             WHERE user_id = ? AND content LIKE ? 
-             ORDER BY updated_at DESC LIMIT ? OFFSET ?"
-        )
-        //     user_id, search_query, limit, offset
+             WHERE {} 
+             ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?",
+            SEARCH_FILTER
+        );
+        let items = sqlx::query_as::<_, ClipboardItem>(&sql)
+        //     user_id, search_query, include_notes, search_query, limit, offset
         .bind(user_id)
-        .bind(search_query)
+        .bind(&search_query)
+        .bind(include_notes)
+        .bind(&search_query)
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
@@ -122,4 +543,282 @@ impl ClipboardRepository {
 
         Ok(items)
     }
+
+    // 统计 search 匹配的项目总数，匹配条件与 search 相同
+    pub async fn count_search(
+        pool: &SqlitePool,
+        user_id: &str,
+        query: &str,
+        include_notes: bool,
+    ) -> Result<i64, AppError> {
+        let search_query = format!("%{}%", query);
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM clipboard_items WHERE {}", SEARCH_FILTER))
+            .bind(user_id)
+            .bind(&search_query)
+            .bind(include_notes)
+            .bind(&search_query)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(total)
+    }
+
+    // 明文项目按 LIKE 匹配，加密项目按搜索索引匹配（需包含全部查询词哈希）
+    pub async fn search_with_index(
+        pool: &SqlitePool,
+        user_id: &str,
+        query: &str,
+        token_hashes: &[String],
+        include_notes: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let search_query = format!("%{}%", query);
+
+        let sql = format!(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items 
+             WHERE {} 
+             ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?",
+            Self::index_search_filter(token_hashes.len())
+        );
+
+        let mut query = sqlx::query_as::<_, ClipboardItem>(&sql)
+            .bind(user_id)
+            .bind(search_query.clone())
+            .bind(include_notes)
+            .bind(search_query);
+
+        if !token_hashes.is_empty() {
+            query = query.bind(user_id);
+            for token_hash in token_hashes {
+                query = query.bind(token_hash);
+            }
+            query = query.bind(token_hashes.len() as i64);
+        }
+
+        let items = query
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items)
+    }
+
+    // 统计 search_with_index 匹配的项目总数，匹配条件与 search_with_index 相同
+    pub async fn count_search_with_index(
+        pool: &SqlitePool,
+        user_id: &str,
+        query: &str,
+        token_hashes: &[String],
+        include_notes: bool,
+    ) -> Result<i64, AppError> {
+        let search_query = format!("%{}%", query);
+
+        let sql = format!(
+            "SELECT COUNT(*) FROM clipboard_items WHERE {}",
+            Self::index_search_filter(token_hashes.len())
+        );
+        let mut query = sqlx::query_scalar::<_, i64>(&sql)
+            .bind(user_id)
+            .bind(search_query.clone())
+            .bind(include_notes)
+            .bind(search_query);
+
+        if !token_hashes.is_empty() {
+            query = query.bind(user_id);
+            for token_hash in token_hashes {
+                query = query.bind(token_hash);
+            }
+            query = query.bind(token_hashes.len() as i64);
+        }
+
+        let total = query
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(total)
+    }
+
+    // search_with_index 的匹配条件，参数依次为 user_id、LIKE 模式、include_notes、LIKE 模式，
+    // 有查询词哈希时再加上 user_id、各哈希和哈希数量
+    fn index_search_filter(token_count: usize) -> String {
+        // 没有可用的查询词时只搜索明文项目
+        let index_clause = if token_count == 0 {
+            String::new()
+        } else {
+            let placeholders = vec!["?"; token_count].join(", ");
+            format!(
+                " OR (encrypted = 1 AND id IN (
+                    SELECT item_id FROM search_index
+                    WHERE user_id = ? AND token_hash IN ({})
+                    GROUP BY item_id HAVING COUNT(DISTINCT token_hash) = ?))",
+                placeholders
+            )
+        };
+
+        format!("user_id = ? AND ((encrypted = 0 AND content LIKE ?) OR (? AND note LIKE ?){})", index_clause)
+    }
+
+    // 在调用方的事务中获取用户的全部加密项目
+    pub async fn find_encrypted_by_user_id(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let items = sqlx::query_as::<_, ClipboardItem>(
+            "SELECT id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note
+             FROM clipboard_items 
+             WHERE user_id = ? AND encrypted = 1"
+        )
+        .bind(user_id)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items)
+    }
+
+    // 在调用方的事务中写入项目的当前内容（重新加密时使用，不修改 updated_at）
+    pub async fn set_content(conn: &mut SqliteConnection, item: &ClipboardItem) -> Result<(), AppError> {
+        let payload = item.payload();
+        let (content, content_blob) = payload.columns();
+
+        sqlx::query("UPDATE clipboard_items SET content = ?, content_blob = ? WHERE id = ?")
+            .bind(content)
+            .bind(content_blob)
+            .bind(&item.id)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 在调用方的事务中将项目转移给另一个用户，返回转移的数量
+    // 转移的项目会标记为未同步，并为双方追加变更记录；旧用户的搜索索引同时删除
+    pub async fn reassign_items(
+        conn: &mut SqliteConnection,
+        from_user_id: &str,
+        to_user_id: &str,
+    ) -> Result<u64, AppError> {
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM clipboard_items WHERE user_id = ?")
+            .bind(from_user_id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for id in &ids {
+            Self::reassign_item_in(&mut *conn, id, from_user_id, to_user_id).await?;
+        }
+
+        Ok(ids.len() as u64)
+    }
+
+    // 在调用方的事务中将单个项目转移给另一个用户，返回是否转移
+    // 与 reassign_items 相同：标记为未同步，追加双方的变更记录并删除旧用户的搜索索引
+    pub async fn reassign_item_in(
+        conn: &mut SqliteConnection,
+        id: &str,
+        from_user_id: &str,
+        to_user_id: &str,
+    ) -> Result<bool, AppError> {
+        // 明文哈希由原用户的密钥计算，转移后清空，合并重复项目时按新用户的密钥重新计算
+        let result = sqlx::query("UPDATE clipboard_items SET user_id = ?, content_hash = NULL WHERE id = ? AND user_id = ?")
+            .bind(to_user_id)
+            .bind(id)
+            .bind(from_user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
+             VALUES (?, 0, NULL)
+             ON CONFLICT(item_id) DO UPDATE SET
+             is_synced = 0"
+        )
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        SearchIndexRepository::remove(&mut *conn, id).await?;
+        ChangeRepository::append(&mut *conn, from_user_id, CHANGE_OP_DELETE, id).await?;
+        ChangeRepository::append(&mut *conn, to_user_id, CHANGE_OP_ADD, id).await?;
+
+        Ok(true)
+    }
+
+    // 在调用方的事务中清空用户全部项目的明文哈希（更换密钥后旧哈希失效）
+    pub async fn clear_content_hashes(conn: &mut SqliteConnection, user_id: &str) -> Result<(), AppError> {
+        sqlx::query("UPDATE clipboard_items SET content_hash = NULL WHERE user_id = ?")
+            .bind(user_id)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 保存明文内容的哈希（加密前计算，加密与明文副本的哈希相同）
+    // 在调用方的事务中写入明文哈希
+    pub async fn set_content_hash_in(conn: &mut SqliteConnection, id: &str, content_hash: Option<&str>) -> Result<(), AppError> {
+        sqlx::query("UPDATE clipboard_items SET content_hash = ? WHERE id = ?")
+            .bind(content_hash)
+            .bind(id)
+            .execute(conn)
+            .await
+            .map_err(write_error)?;
+
+        Ok(())
+    }
+
+    pub async fn set_content_hash(pool: &SqlitePool, id: &str, content_hash: Option<&str>) -> Result<(), AppError> {
+        retry_on_busy(|| async move {
+            sqlx::query("UPDATE clipboard_items SET content_hash = ? WHERE id = ?")
+                .bind(content_hash)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(write_error)
+        }).await?;
+
+        Ok(())
+    }
+
+    // 获取用户全部项目的明文哈希，未计算的项目为 None
+    pub async fn find_content_hashes(
+        pool: &SqlitePool,
+        user_id: &str,
+    ) -> Result<HashMap<String, Option<String>>, AppError> {
+        let rows: Vec<(String, Option<String>)> = sqlx::query_as(
+            "SELECT id, content_hash FROM clipboard_items WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    pub async fn find_pinned_ids(pool: &SqlitePool, user_id: &str) -> Result<HashSet<String>, AppError> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM clipboard_items WHERE user_id = ? AND is_pinned = 1"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(ids.into_iter().collect())
+    }
 }
@@ -1,9 +1,14 @@
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use crate::error::AppError;
+use crate::util::db::{retry_on_busy, write_error};
 use uuid::Uuid;
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 
+// AES-256-GCM 的密钥与 nonce 长度（字节）
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 12;
+
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)] // 添加 sqlx::FromRow
 pub struct EncryptionKey {
     pub id: String,
@@ -13,10 +18,93 @@ pub struct EncryptionKey {
     pub created_at: i64,
 }
 
+impl EncryptionKey {
+    // 校验密钥和 nonce 长度，避免损坏的数据进入 AES-GCM
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.key_data.len() != KEY_LENGTH {
+            return Err(AppError::InvalidData(format!(
+                "加密密钥长度错误: 期望 {} 字节，实际 {} 字节",
+                KEY_LENGTH,
+                self.key_data.len()
+            )));
+        }
+        
+        if self.nonce.len() != NONCE_LENGTH {
+            return Err(AppError::InvalidData(format!(
+                "nonce 长度错误: 期望 {} 字节，实际 {} 字节",
+                NONCE_LENGTH,
+                self.nonce.len()
+            )));
+        }
+        
+        Ok(())
+    }
+}
+
+// 为某个设备包装（加密）后的用户密钥，用于在设备绑定时传输
+// wrapped_key 为 base64(nonce + 密文)，包装密钥由配对口令和 salt 派生
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::FromRow)]
+pub struct WrappedKey {
+    pub user_id: String,
+    pub device_id: String,
+    pub idempotency_key: String,
+    pub wrapped_key: String,
+    pub salt: String,
+    pub created_at: i64,
+}
+
 pub struct EncryptionRepository;
 
 impl EncryptionRepository {
     pub async fn save(pool: &SqlitePool, key: &EncryptionKey) -> Result<(), AppError> {
+        key.validate()?;
+        
+        retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO encryption_keys (id, user_id, key_data, nonce, created_at)
+                 VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(&key.id)
+            .bind(&key.user_id)
+            .bind(&key.key_data)
+            .bind(&key.nonce)
+            .bind(key.created_at)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
+        
+        Ok(())
+    }
+    
+    pub async fn find_by_user_id(pool: &SqlitePool, user_id: &str) -> Result<Option<EncryptionKey>, AppError> {
+        let key = sqlx::query_as::<_, EncryptionKey>(
+            "SELECT id, user_id, key_data, nonce, created_at
+             FROM encryption_keys WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        // 读取时校验，损坏的密钥直接返回错误
+        if let Some(key) = &key {
+            key.validate()?;
+        }
+        
+        Ok(key)
+    }
+    
+    // 在调用方的事务中用新密钥替换用户的现有密钥
+    pub async fn replace_for_user(conn: &mut SqliteConnection, key: &EncryptionKey) -> Result<(), AppError> {
+        key.validate()?;
+        
+        sqlx::query("DELETE FROM encryption_keys WHERE user_id = ?")
+            .bind(&key.user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
         sqlx::query(
             "INSERT INTO encryption_keys (id, user_id, key_data, nonce, created_at)
              VALUES (?, ?, ?, ?, ?)"
@@ -26,24 +114,27 @@ impl EncryptionRepository {
         .bind(&key.key_data)
         .bind(&key.nonce)
         .bind(key.created_at)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         
         Ok(())
     }
     
-    pub async fn find_by_user_id(pool: &SqlitePool, user_id: &str) -> Result<Option<EncryptionKey>, AppError> {
-        let key = sqlx::query_as::<_, EncryptionKey>(
-            "SELECT id, user_id, key_data, nonce, created_at
-             FROM encryption_keys WHERE user_id = ?"
-        )
-        .bind(user_id)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    // 生成新密钥但不保存
+    pub fn generate(user_id: &str) -> EncryptionKey {
+        use crate::util::crypto;
         
-        Ok(key)
+        EncryptionKey {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            key_data: crypto::generate_encryption_key().to_vec(),
+            nonce: crypto::generate_nonce().to_vec(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64,
+        }
     }
     
     pub async fn create_for_user(pool: &SqlitePool, user_id: &str) -> Result<EncryptionKey, AppError> {
@@ -54,26 +145,80 @@ impl EncryptionRepository {
         }
         
         // 生成新密钥
-        use crate::util::crypto;
-        let key_data = crypto::generate_encryption_key().to_vec();
-        let nonce = crypto::generate_nonce().to_vec();
-        
-        let id = Uuid::new_v4().to_string();
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-        
-        let key = EncryptionKey {
-            id,
-            user_id: user_id.to_string(),
-            key_data,
-            nonce,
-            created_at: now,
-        };
+        let key = Self::generate(user_id);
         
         Self::save(pool, &key).await?;
         
         Ok(key)
     }
-}
\ No newline at end of file
+    
+    // 在调用方的事务中获取已为设备包装的密钥
+    pub async fn find_wrapped_for_device(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        device_id: &str
+    ) -> Result<Option<WrappedKey>, AppError> {
+        let wrapped = sqlx::query_as::<_, WrappedKey>(
+            "SELECT user_id, device_id, idempotency_key, wrapped_key, salt, created_at
+             FROM wrapped_keys WHERE user_id = ? AND device_id = ?"
+        )
+        .bind(user_id)
+        .bind(device_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(wrapped)
+    }
+    
+    pub async fn find_wrapped_by_idempotency_key(
+        conn: &mut SqliteConnection,
+        idempotency_key: &str
+    ) -> Result<Option<WrappedKey>, AppError> {
+        let wrapped = sqlx::query_as::<_, WrappedKey>(
+            "SELECT user_id, device_id, idempotency_key, wrapped_key, salt, created_at
+             FROM wrapped_keys WHERE idempotency_key = ?"
+        )
+        .bind(idempotency_key)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(wrapped)
+    }
+    
+    // 删除用户的全部包装密钥（更换密钥后旧的包装密钥失效）
+    pub async fn delete_wrapped_for_user(conn: &mut SqliteConnection, user_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM wrapped_keys WHERE user_id = ?")
+            .bind(user_id)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    // 每个设备只保留一份包装密钥，重新配对时覆盖
+    pub async fn save_wrapped(conn: &mut SqliteConnection, wrapped: &WrappedKey) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO wrapped_keys (user_id, device_id, idempotency_key, wrapped_key, salt, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(user_id, device_id) DO UPDATE SET
+             idempotency_key = excluded.idempotency_key,
+             wrapped_key = excluded.wrapped_key,
+             salt = excluded.salt,
+             created_at = excluded.created_at"
+        )
+        .bind(&wrapped.user_id)
+        .bind(&wrapped.device_id)
+        .bind(&wrapped.idempotency_key)
+        .bind(&wrapped.wrapped_key)
+        .bind(&wrapped.salt)
+        .bind(wrapped.created_at)
+        .execute(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(())
+    }
+}
@@ -1,7 +1,23 @@
 use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
 use crate::error::AppError;
+use crate::repository::settings_repository::SettingsRepository;
+
+// 设置项：数据库结构版本，完成对应的数据迁移后更新
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+// 旧版单表数据迁移完成后的结构版本
+pub const LEGACY_MIGRATION_VERSION: i64 = 1;
+// content_type 规范化完成后的结构版本
+pub const CONTENT_TYPE_MIGRATION_VERSION: i64 = 2;
+// 旧版数据没有所属用户，迁移后归属本机用户，登录后可通过 reassign_items 转移到自己的账号
+pub const LEGACY_LOCAL_USER_ID: &str = "local";
+// 旧版剪贴板表重命名后的表名，迁移完成后删除
+const LEGACY_ITEMS_TABLE: &str = "clipboard_items_legacy";
 
 pub async fn init_tables(pool: &SqlitePool) -> Result<(), AppError> {
+    // 早期版本的剪贴板表没有 user_id 列，先移走，建好新表后再迁移数据
+    rename_legacy_items_table(pool).await?;
+    
     // 初始化用户表
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS users (
@@ -62,6 +78,23 @@ pub async fn init_tables(pool: &SqlitePool) -> Result<(), AppError> {
     .await
     .map_err(|e| AppError::DatabaseError(e.to_string()))?;
     
+    // 为绑定设备包装的用户密钥
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS wrapped_keys (
+            user_id TEXT NOT NULL,
+            device_id TEXT NOT NULL,
+            idempotency_key TEXT NOT NULL UNIQUE,
+            wrapped_key TEXT NOT NULL,
+            salt TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (user_id, device_id),
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
     // 初始化验证码表
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS verification_codes (
@@ -92,5 +125,387 @@ pub async fn init_tables(pool: &SqlitePool) -> Result<(), AppError> {
     .await
     .map_err(|e| AppError::DatabaseError(e.to_string()))?;
     
+    // 初始化项目其他格式表（同一次复制的 HTML、图片等）
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS item_formats (
+            item_id TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            content TEXT NOT NULL,
+            PRIMARY KEY (item_id, content_type),
+            FOREIGN KEY (item_id) REFERENCES clipboard_items(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化图片项目的缩略图表（PNG 字节，仅明文图片项目生成）
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS item_thumbnails (
+            item_id TEXT PRIMARY KEY,
+            data BLOB NOT NULL,
+            FOREIGN KEY (item_id) REFERENCES clipboard_items(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化同步状态表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sync_status (
+            item_id TEXT PRIMARY KEY,
+            is_synced INTEGER DEFAULT 0,
+            last_sync_attempt INTEGER,
+            FOREIGN KEY (item_id) REFERENCES clipboard_items(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化设备时钟偏差表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS device_clock_skew (
+            device_id TEXT PRIMARY KEY,
+            skew_secs INTEGER NOT NULL,
+            observed_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化删除记录表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS tombstones (
+            item_id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            deleted_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化邮件发送队列表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS mail_queue (
+            id TEXT PRIMARY KEY,
+            recipient TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            body TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at INTEGER NOT NULL,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化审计日志表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            action TEXT NOT NULL,
+            target_id TEXT,
+            created_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化变更记录表（只追加，seq 作为前端增量游标）
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS changes (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id TEXT NOT NULL,
+            op TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化加密项目的搜索索引表（只保存带密钥的词哈希）
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS search_index (
+            item_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            token_hash TEXT NOT NULL,
+            PRIMARY KEY (item_id, token_hash),
+            FOREIGN KEY (item_id) REFERENCES clipboard_items(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化项目标签表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS item_tags (
+            item_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (item_id, tag),
+            FOREIGN KEY (item_id) REFERENCES clipboard_items(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化分享表（内容使用分享口令派生的密钥加密，过期后由维护任务清理）
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS shares (
+            code TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            payload BLOB NOT NULL,
+            salt BLOB NOT NULL,
+            nonce BLOB NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化快照表，快照中的项目和标签按原样复制（加密项目保持密文）
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS snapshots (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS snapshot_items (
+            snapshot_id TEXT NOT NULL,
+            id TEXT NOT NULL,
+            content TEXT NOT NULL,
+            content_blob BLOB,
+            content_type TEXT NOT NULL,
+            encrypted INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            raw_content TEXT,
+            source_app TEXT,
+            note TEXT,
+            is_sensitive INTEGER NOT NULL,
+            content_hash TEXT,
+            origin_device_id TEXT,
+            PRIMARY KEY (snapshot_id, id),
+            FOREIGN KEY (snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS snapshot_tags (
+            snapshot_id TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (snapshot_id, item_id, tag),
+            FOREIGN KEY (snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 初始化设置表
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS user_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 为已有数据库补充新增的列
+    ensure_column(pool, "clipboard_items", "raw_content", "TEXT").await?;
+    ensure_column(pool, "clipboard_items", "source_app", "TEXT").await?;
+    ensure_column(pool, "clipboard_items", "note", "TEXT").await?;
+    ensure_column(pool, "clipboard_items", "is_sensitive", "INTEGER NOT NULL DEFAULT 0").await?;
+    ensure_column(pool, "clipboard_items", "content_hash", "TEXT").await?;
+    ensure_column(pool, "clipboard_items", "content_blob", "BLOB").await?;
+    // 最后修改该项目的远程设备，本地修改或旧数据为空
+    ensure_column(pool, "clipboard_items", "origin_device_id", "TEXT").await?;
+    
+    // 旧版数据的标题迁移到备注，置顶状态保存在该列
+    ensure_column(pool, "clipboard_items", "is_pinned", "INTEGER NOT NULL DEFAULT 0").await?;
+    
+    // 索引依赖上面补充的列，必须在建表和补列之后创建
+    create_indexes(pool).await?;
+    
+    migrate_legacy_items(pool).await?;
+    
+    Ok(())
+}
+
+// 旧版剪贴板表（id, content, title, created_at, updated_at, is_pinned，没有 user_id）重命名，等待迁移
+async fn rename_legacy_items_table(pool: &SqlitePool) -> Result<(), AppError> {
+    let columns = table_columns(pool, "clipboard_items").await?;
+    if columns.is_empty() || columns.iter().any(|name| name == "user_id") {
+        return Ok(());
+    }
+    
+    sqlx::query(&format!("ALTER TABLE clipboard_items RENAME TO {}", LEGACY_ITEMS_TABLE))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    Ok(())
+}
+
+// 将旧版剪贴板表的数据迁移到新表，归属本机用户；已迁移过（结构版本已更新）时跳过
+pub async fn migrate_legacy_items(pool: &SqlitePool) -> Result<u64, AppError> {
+    let version = SettingsRepository::get_i64(pool, SCHEMA_VERSION_KEY, 0).await?;
+    if version >= LEGACY_MIGRATION_VERSION {
+        return Ok(0);
+    }
+    
+    let legacy_columns = table_columns(pool, LEGACY_ITEMS_TABLE).await?;
+    if legacy_columns.is_empty() {
+        SettingsRepository::set(pool, SCHEMA_VERSION_KEY, &LEGACY_MIGRATION_VERSION.to_string()).await?;
+        return Ok(0);
+    }
+    
+    // 旧表的列随版本不同，缺少的列使用默认值
+    let title = if legacy_columns.iter().any(|name| name == "title") { "title" } else { "NULL" };
+    let is_pinned = if legacy_columns.iter().any(|name| name == "is_pinned") { "COALESCE(is_pinned, 0)" } else { "0" };
+    
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    
+    let mut tx = pool.begin()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 本机用户只用于承载旧数据，密码哈希无效，无法登录
+    sqlx::query(
+        "INSERT OR IGNORE INTO users (id, email, username, password_hash, created_at, updated_at)
+         VALUES (?, ?, ?, '!', ?, ?)"
+    )
+    .bind(LEGACY_LOCAL_USER_ID)
+    .bind(LEGACY_LOCAL_USER_ID)
+    .bind(LEGACY_LOCAL_USER_ID)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    let migrated = sqlx::query(&format!(
+        "INSERT OR IGNORE INTO clipboard_items (id, user_id, content, content_type, encrypted, created_at, updated_at, note, is_pinned)
+         SELECT id, ?, content, 'text/plain', 0, created_at, updated_at, {}, {} FROM {}",
+        title, is_pinned, LEGACY_ITEMS_TABLE
+    ))
+    .bind(LEGACY_LOCAL_USER_ID)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    // 没有同步状态的旧项目按待同步处理
+    sqlx::query(&format!(
+        "INSERT OR IGNORE INTO sync_status (item_id, is_synced, last_sync_attempt)
+         SELECT id, 0, NULL FROM {}",
+        LEGACY_ITEMS_TABLE
+    ))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    sqlx::query(&format!("DROP TABLE {}", LEGACY_ITEMS_TABLE))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    sqlx::query(
+        "INSERT INTO user_settings (key, value, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at"
+    )
+    .bind(SCHEMA_VERSION_KEY)
+    .bind(LEGACY_MIGRATION_VERSION.to_string())
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    tx.commit()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    
+    Ok(migrated.rows_affected())
+}
+
+// 表的列名，表不存在时为空
+async fn table_columns(pool: &SqlitePool, table: &str) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+}
+
+// 为常用查询创建索引
+// 新增索引统一加在这里，CREATE INDEX IF NOT EXISTS 对已有数据库同样生效
+pub async fn create_indexes(pool: &SqlitePool) -> Result<(), AppError> {
+    let indexes = [
+        // 列表按用户筛选并按更新时间排序，增量刷新也按更新时间筛选
+        "CREATE INDEX IF NOT EXISTS idx_clipboard_items_user_updated ON clipboard_items(user_id, updated_at)",
+        // 合并重复项目时按明文哈希查找
+        "CREATE INDEX IF NOT EXISTS idx_clipboard_items_content_hash ON clipboard_items(user_id, content_hash)",
+        // 统计和同步未同步项目时按同步状态筛选
+        "CREATE INDEX IF NOT EXISTS idx_sync_status_is_synced ON sync_status(is_synced)",
+        // 注销全部设备、统计设备数时按用户查找会话
+        "CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id)",
+        "CREATE INDEX IF NOT EXISTS idx_search_index_user_token ON search_index(user_id, token_hash)",
+        "CREATE INDEX IF NOT EXISTS idx_item_tags_tag ON item_tags(tag)",
+    ];
+    
+    for statement in indexes {
+        sqlx::query(statement)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    
+    Ok(())
+}
+
+// 列不存在时通过 ALTER TABLE 添加（CREATE TABLE IF NOT EXISTS 不会更新旧表结构）
+async fn ensure_column(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> Result<(), AppError> {
+    let columns = table_columns(pool, table).await?;
+    
+    if !columns.iter().any(|name| name == column) {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+    
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+use crate::entity::item_format::ItemFormat;
+use crate::error::AppError;
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub struct ItemFormatRepository;
+
+impl ItemFormatRepository {
+    // 在调用方的事务中替换项目的全部其他格式
+    pub async fn replace(
+        conn: &mut SqliteConnection,
+        item_id: &str,
+        formats: &[ItemFormat],
+    ) -> Result<(), AppError> {
+        Self::delete_by_item_id(&mut *conn, item_id).await?;
+
+        for format in formats {
+            sqlx::query(
+                "INSERT INTO item_formats (item_id, content_type, content)
+                 VALUES (?, ?, ?)
+                 ON CONFLICT(item_id, content_type) DO UPDATE SET
+                 content = excluded.content"
+            )
+            .bind(item_id)
+            .bind(&format.content_type)
+            .bind(&format.content)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn find_by_item_id(pool: &SqlitePool, item_id: &str) -> Result<Vec<ItemFormat>, AppError> {
+        let formats = sqlx::query_as::<_, ItemFormat>(
+            "SELECT item_id, content_type, content
+             FROM item_formats WHERE item_id = ? ORDER BY content_type"
+        )
+        .bind(item_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(formats)
+    }
+
+    // 在调用方的事务中获取用户全部加密项目的其他格式
+    pub async fn find_encrypted_by_user_id(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+    ) -> Result<Vec<ItemFormat>, AppError> {
+        let formats = sqlx::query_as::<_, ItemFormat>(
+            "SELECT f.item_id, f.content_type, f.content
+             FROM item_formats f
+             JOIN clipboard_items c ON c.id = f.item_id
+             WHERE c.user_id = ? AND c.encrypted = 1"
+        )
+        .bind(user_id)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(formats)
+    }
+
+    // 在调用方的事务中替换单个格式的内容（重新加密时使用）
+    pub async fn set_content(conn: &mut SqliteConnection, format: &ItemFormat) -> Result<(), AppError> {
+        sqlx::query("UPDATE item_formats SET content = ? WHERE item_id = ? AND content_type = ?")
+            .bind(&format.content)
+            .bind(&format.item_id)
+            .bind(&format.content_type)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 在调用方的事务中删除单个格式
+    pub async fn delete_format(conn: &mut SqliteConnection, item_id: &str, content_type: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM item_formats WHERE item_id = ? AND content_type = ?")
+            .bind(item_id)
+            .bind(content_type)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn delete_by_item_id(conn: &mut SqliteConnection, item_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM item_formats WHERE item_id = ?")
+            .bind(item_id)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::entity::mail::{MailQueueStatus, QueuedMail, MAIL_STATUS_DEAD, MAIL_STATUS_PENDING, MAIL_STATUS_SENT};
+use crate::error::AppError;
+use crate::util::db::{retry_on_busy, write_error};
+use sqlx::{Row, SqlitePool};
+
+pub struct MailRepository;
+
+impl MailRepository {
+    pub async fn save(pool: &SqlitePool, mail: &QueuedMail) -> Result<(), AppError> {
+        retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO mail_queue (id, recipient, subject, body, status, attempts, next_attempt_at, last_error, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&mail.id)
+            .bind(&mail.recipient)
+            .bind(&mail.subject)
+            .bind(&mail.body)
+            .bind(&mail.status)
+            .bind(mail.attempts)
+            .bind(mail.next_attempt_at)
+            .bind(&mail.last_error)
+            .bind(mail.created_at)
+            .bind(mail.updated_at)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
+
+        Ok(())
+    }
+
+    // 获取已到重试时间的待发送邮件
+    pub async fn find_due(pool: &SqlitePool, now: i64, limit: i64) -> Result<Vec<QueuedMail>, AppError> {
+        let mails = sqlx::query_as::<_, QueuedMail>(
+            "SELECT id, recipient, subject, body, status, attempts, next_attempt_at, last_error, created_at, updated_at
+             FROM mail_queue WHERE status = ? AND next_attempt_at <= ?
+             ORDER BY next_attempt_at ASC LIMIT ?"
+        )
+        .bind(MAIL_STATUS_PENDING)
+        .bind(now)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(mails)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: &str) -> Result<Option<QueuedMail>, AppError> {
+        let mail = sqlx::query_as::<_, QueuedMail>(
+            "SELECT id, recipient, subject, body, status, attempts, next_attempt_at, last_error, created_at, updated_at
+             FROM mail_queue WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(mail)
+    }
+
+    pub async fn mark_sent(pool: &SqlitePool, id: &str, now: i64) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE mail_queue SET
+             status = ?,
+             attempts = attempts + 1,
+             last_error = NULL,
+             updated_at = ?
+             WHERE id = ?"
+        )
+        .bind(MAIL_STATUS_SENT)
+        .bind(now)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 记录失败：更新下次重试时间，或在超过次数后转为死信
+    pub async fn mark_failed(
+        pool: &SqlitePool,
+        id: &str,
+        error: &str,
+        next_attempt_at: Option<i64>,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let status = if next_attempt_at.is_some() { MAIL_STATUS_PENDING } else { MAIL_STATUS_DEAD };
+
+        sqlx::query(
+            "UPDATE mail_queue SET
+             status = ?,
+             attempts = attempts + 1,
+             next_attempt_at = COALESCE(?, next_attempt_at),
+             last_error = ?,
+             updated_at = ?
+             WHERE id = ?"
+        )
+        .bind(status)
+        .bind(next_attempt_at)
+        .bind(error)
+        .bind(now)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 最近的发送错误（错误信息和时间），按时间倒序
+    pub async fn find_recent_errors(pool: &SqlitePool, limit: i64) -> Result<Vec<(String, i64)>, AppError> {
+        sqlx::query_as::<_, (String, i64)>(
+            "SELECT last_error, updated_at FROM mail_queue
+             WHERE last_error IS NOT NULL
+             ORDER BY updated_at DESC, id DESC LIMIT ?"
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    pub async fn count_by_status(pool: &SqlitePool) -> Result<MailQueueStatus, AppError> {
+        let rows = sqlx::query("SELECT status, COUNT(*) as count FROM mail_queue GROUP BY status")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut status = MailQueueStatus::default();
+        for row in rows {
+            let count: i64 = row.get("count");
+            match row.get::<String, _>("status").as_str() {
+                MAIL_STATUS_PENDING => status.pending = count,
+                MAIL_STATUS_SENT => status.sent = count,
+                MAIL_STATUS_DEAD => status.dead = count,
+                _ => {}
+            }
+        }
+
+        Ok(status)
+    }
+}
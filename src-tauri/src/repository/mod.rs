@@ -2,6 +2,17 @@ pub mod user_repository;
 pub mod session_repository;
 pub mod clipboard_repository;
 pub mod encryption_repository;
+pub mod settings_repository;
+pub mod tombstone_repository;
+pub mod mail_repository;
+pub mod audit_repository;
+pub mod change_repository;
+pub mod search_index_repository;
+pub mod item_format_repository;
+pub mod share_repository;
+pub mod tag_repository;
+pub mod snapshot_repository;
+pub mod thumbnail_repository;
 pub mod init;
 
 // 重新导出初始化函数
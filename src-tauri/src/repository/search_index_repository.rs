@@ -0,0 +1,82 @@
+use crate::error::AppError;
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub struct SearchIndexRepository;
+
+impl SearchIndexRepository {
+    // 替换项目的全部索引词（先删除旧索引再写入）
+    pub async fn replace(
+        pool: &SqlitePool,
+        item_id: &str,
+        user_id: &str,
+        token_hashes: &[String],
+    ) -> Result<(), AppError> {
+        let mut tx = pool.begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::replace_in(&mut tx, item_id, user_id, token_hashes).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 在调用方的事务中替换项目的全部索引词
+    pub async fn replace_in(
+        conn: &mut SqliteConnection,
+        item_id: &str,
+        user_id: &str,
+        token_hashes: &[String],
+    ) -> Result<(), AppError> {
+        Self::remove(&mut *conn, item_id).await?;
+
+        for token_hash in token_hashes {
+            sqlx::query(
+                "INSERT OR IGNORE INTO search_index (item_id, user_id, token_hash)
+                 VALUES (?, ?, ?)"
+            )
+            .bind(item_id)
+            .bind(user_id)
+            .bind(token_hash)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    // 在调用方的连接或事务中删除项目的索引
+    pub async fn remove(conn: &mut SqliteConnection, item_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM search_index WHERE item_id = ?")
+            .bind(item_id)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn clear_for_user(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM search_index WHERE user_id = ?")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn count_for_user(pool: &SqlitePool, user_id: &str) -> Result<i64, AppError> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM search_index WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(count)
+    }
+}
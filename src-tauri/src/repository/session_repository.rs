@@ -1,23 +1,26 @@
 use crate::entity::session::Session;
 use crate::error::AppError;
+use crate::util::db::{retry_on_busy, write_error};
 use sqlx::SqlitePool;
 
 pub struct SessionRepository;
 
 impl SessionRepository {
     pub async fn save(pool: &SqlitePool, session: &Session) -> Result<(), AppError> {
-        sqlx::query(
-            "INSERT INTO sessions (token, user_id, device_id, created_at, expires_at)
-             VALUES (?, ?, ?, ?, ?)",
-        )
-        .bind(&session.token)
-        .bind(&session.user_id)
-        .bind(&session.device_id)
-        .bind(session.created_at)
-        .bind(session.expires_at)
-        .execute(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO sessions (token, user_id, device_id, created_at, expires_at)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&session.token)
+            .bind(&session.user_id)
+            .bind(&session.device_id)
+            .bind(session.created_at)
+            .bind(session.expires_at)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
 
         Ok(())
     }
@@ -38,12 +41,70 @@ impl SessionRepository {
         Ok(session)
     }
 
+    // 获取用户的全部会话，按创建时间从新到旧排序
+    pub async fn find_all_by_user_id(pool: &SqlitePool, user_id: &str) -> Result<Vec<Session>, AppError> {
+        let sessions = sqlx::query_as::<_, Session>(
+            "SELECT token, user_id, device_id, created_at, expires_at 
+             FROM sessions WHERE user_id = ? ORDER BY created_at DESC, token DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(sessions)
+    }
+
+    // 删除用户除指定令牌外的全部会话，返回删除的数量
+    pub async fn delete_others(pool: &SqlitePool, user_id: &str, keep_token: &str) -> Result<u64, AppError> {
+        let result = retry_on_busy(|| async move {
+            sqlx::query("DELETE FROM sessions WHERE user_id = ? AND token != ?")
+                .bind(user_id)
+                .bind(keep_token)
+                .execute(pool)
+                .await
+                .map_err(write_error)
+        }).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 删除用户在某个设备上的全部会话，返回删除的数量
+    pub async fn delete_by_device(pool: &SqlitePool, user_id: &str, device_id: &str) -> Result<u64, AppError> {
+        let result = retry_on_busy(|| async move {
+            sqlx::query("DELETE FROM sessions WHERE user_id = ? AND device_id = ?")
+                .bind(user_id)
+                .bind(device_id)
+                .execute(pool)
+                .await
+                .map_err(write_error)
+        }).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 将某个设备 ID 下的会话改到新的设备 ID，返回修改的数量
+    pub async fn reassign_device(pool: &SqlitePool, old_device_id: &str, new_device_id: &str) -> Result<u64, AppError> {
+        let result = retry_on_busy(|| async move {
+            sqlx::query("UPDATE sessions SET device_id = ? WHERE device_id = ?")
+                .bind(new_device_id)
+                .bind(old_device_id)
+                .execute(pool)
+                .await
+                .map_err(write_error)
+        }).await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn delete_by_token(pool: &SqlitePool, token: &str) -> Result<(), AppError> {
-        sqlx::query("DELETE FROM sessions WHERE token = ?")
-            .bind(token)
-            .execute(pool)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        retry_on_busy(|| async move {
+            sqlx::query("DELETE FROM sessions WHERE token = ?")
+                .bind(token)
+                .execute(pool)
+                .await
+                .map_err(write_error)
+        }).await?;
 
         Ok(())
     }
@@ -0,0 +1,105 @@
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::AppError;
+use crate::util::db::{retry_on_busy, write_error};
+
+pub struct SettingsRepository;
+
+impl SettingsRepository {
+    pub async fn get(pool: &SqlitePool, key: &str) -> Result<Option<String>, AppError> {
+        let value = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM user_settings WHERE key = ?"
+        )
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(value)
+    }
+    
+    // 读取布尔设置，不存在或无法解析时返回默认值
+    pub async fn get_bool(pool: &SqlitePool, key: &str, default: bool) -> Result<bool, AppError> {
+        let value = Self::get(pool, key).await?;
+        Ok(value.and_then(|v| v.parse::<bool>().ok()).unwrap_or(default))
+    }
+    
+    // 读取整数设置，不存在或无法解析时返回默认值
+    pub async fn get_i64(pool: &SqlitePool, key: &str, default: i64) -> Result<i64, AppError> {
+        let value = Self::get(pool, key).await?;
+        Ok(value.and_then(|v| v.parse::<i64>().ok()).unwrap_or(default))
+    }
+    
+    // 读取全部设置项
+    pub async fn all(pool: &SqlitePool) -> Result<Vec<(String, String)>, AppError> {
+        let settings = sqlx::query_as::<_, (String, String)>(
+            "SELECT key, value FROM user_settings ORDER BY key"
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(settings)
+    }
+    
+    pub async fn set(pool: &SqlitePool, key: &str, value: &str) -> Result<(), AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO user_settings (key, value, updated_at)
+                 VALUES (?, ?, ?)
+                 ON CONFLICT(key) DO UPDATE SET
+                 value = excluded.value,
+                 updated_at = excluded.updated_at"
+            )
+            .bind(key)
+            .bind(value)
+            .bind(now)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
+        
+        Ok(())
+    }
+    
+    // 仅在设置项不存在时写入，返回是否写入
+    pub async fn set_if_absent(pool: &SqlitePool, key: &str, value: &str) -> Result<bool, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let result = retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO user_settings (key, value, updated_at)
+                 VALUES (?, ?, ?)
+                 ON CONFLICT(key) DO NOTHING"
+            )
+            .bind(key)
+            .bind(value)
+            .bind(now)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    pub async fn delete(pool: &SqlitePool, key: &str) -> Result<(), AppError> {
+        retry_on_busy(|| async move {
+            sqlx::query("DELETE FROM user_settings WHERE key = ?")
+                .bind(key)
+                .execute(pool)
+                .await
+                .map_err(write_error)
+        }).await?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use crate::entity::share::Share;
+use crate::error::AppError;
+use crate::util::db::{retry_on_busy, write_error};
+use sqlx::SqlitePool;
+
+pub struct ShareRepository;
+
+impl ShareRepository {
+    pub async fn save(pool: &SqlitePool, share: &Share) -> Result<(), AppError> {
+        retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO shares (code, user_id, content_type, payload, salt, nonce, created_at, expires_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&share.code)
+            .bind(&share.user_id)
+            .bind(&share.content_type)
+            .bind(&share.payload)
+            .bind(&share.salt)
+            .bind(&share.nonce)
+            .bind(share.created_at)
+            .bind(share.expires_at)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
+
+        Ok(())
+    }
+
+    // 查找未过期的分享
+    pub async fn find_active(pool: &SqlitePool, code: &str, now: i64) -> Result<Option<Share>, AppError> {
+        let share = sqlx::query_as::<_, Share>(
+            "SELECT code, user_id, content_type, payload, salt, nonce, created_at, expires_at
+             FROM shares WHERE code = ? AND expires_at > ?"
+        )
+        .bind(code)
+        .bind(now)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(share)
+    }
+
+    // 清理已过期的分享，返回清理数量
+    pub async fn prune_expired(pool: &SqlitePool, now: i64) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM shares WHERE expires_at <= ?")
+            .bind(now)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,193 @@
+use crate::entity::change::CHANGE_OP_UPDATE;
+use crate::error::AppError;
+use crate::repository::change_repository::ChangeRepository;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use sqlx::{SqliteConnection, SqlitePool};
+
+// 快照与项目表共用的列（不含 user_id）
+const ITEM_COLUMNS: &str = "id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, note, is_sensitive, content_hash, origin_device_id";
+
+pub struct SnapshotRepository;
+
+impl SnapshotRepository {
+    // 在调用方的事务中复制用户当前的项目和标签，返回复制的项目数
+    pub async fn create_in(
+        conn: &mut SqliteConnection,
+        id: &str,
+        user_id: &str,
+        created_at: i64,
+        expires_at: i64,
+    ) -> Result<u64, AppError> {
+        sqlx::query("INSERT INTO snapshots (id, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .bind(created_at)
+            .bind(expires_at)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let items = sqlx::query(&format!(
+            "INSERT INTO snapshot_items (snapshot_id, {columns})
+             SELECT ?, {columns} FROM clipboard_items WHERE user_id = ?",
+            columns = ITEM_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO snapshot_tags (snapshot_id, item_id, tag)
+             SELECT ?, t.item_id, t.tag FROM item_tags t
+             JOIN clipboard_items c ON c.id = t.item_id
+             WHERE c.user_id = ?"
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(items.rows_affected())
+    }
+
+    // 在调用方的事务中判断快照是否属于该用户且未过期
+    pub async fn is_active_in(
+        conn: &mut SqliteConnection,
+        id: &str,
+        user_id: &str,
+        now: i64,
+    ) -> Result<bool, AppError> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM snapshots WHERE id = ? AND user_id = ? AND expires_at > ?"
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(now)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(count > 0)
+    }
+
+    // 在调用方的事务中用快照替换用户当前的项目和标签，返回恢复的项目数
+    // 快照之后新增的项目按普通删除处理（写入删除记录），快照中的项目覆盖写回并标记为待同步
+    pub async fn restore_in(
+        conn: &mut SqliteConnection,
+        id: &str,
+        user_id: &str,
+    ) -> Result<u64, AppError> {
+        let added_since: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM clipboard_items
+             WHERE user_id = ? AND id NOT IN (SELECT id FROM snapshot_items WHERE snapshot_id = ?)"
+        )
+        .bind(user_id)
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "DELETE FROM item_tags WHERE item_id IN (SELECT id FROM clipboard_items WHERE user_id = ?)"
+        )
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        ClipboardRepository::delete_many_in(&mut *conn, &added_since, user_id).await?;
+
+        // 使用 UPSERT 而不是 INSERT OR REPLACE，避免删除行时级联清理项目的其他格式
+        let restored = sqlx::query(&format!(
+            "INSERT INTO clipboard_items (user_id, {columns})
+             SELECT ?, {columns} FROM snapshot_items WHERE snapshot_id = ?
+             ON CONFLICT(id) DO UPDATE SET
+             content = excluded.content,
+             content_blob = excluded.content_blob,
+             content_type = excluded.content_type,
+             encrypted = excluded.encrypted,
+             created_at = excluded.created_at,
+             updated_at = excluded.updated_at,
+             raw_content = excluded.raw_content,
+             source_app = excluded.source_app,
+             note = excluded.note,
+             is_sensitive = excluded.is_sensitive,
+             content_hash = excluded.content_hash,
+             origin_device_id = excluded.origin_device_id",
+            columns = ITEM_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("INSERT INTO item_tags (item_id, tag) SELECT item_id, tag FROM snapshot_tags WHERE snapshot_id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // 恢复的项目重新出现，清除之前的删除记录并重新推送
+        sqlx::query("DELETE FROM tombstones WHERE item_id IN (SELECT id FROM snapshot_items WHERE snapshot_id = ?)")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
+             SELECT id, 0, NULL FROM snapshot_items WHERE snapshot_id = ?
+             ON CONFLICT(item_id) DO UPDATE SET
+             is_synced = 0"
+        )
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let restored_ids: Vec<String> = sqlx::query_scalar("SELECT id FROM snapshot_items WHERE snapshot_id = ?")
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        for item_id in &restored_ids {
+            ChangeRepository::append(&mut *conn, user_id, CHANGE_OP_UPDATE, item_id).await?;
+        }
+
+        Ok(restored.rows_affected())
+    }
+
+    // 清理已过期的快照及其项目和标签，返回清理的快照数
+    pub async fn prune_expired(pool: &SqlitePool, now: i64) -> Result<u64, AppError> {
+        let mut tx = pool.begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for table in ["snapshot_tags", "snapshot_items"] {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE snapshot_id IN (SELECT id FROM snapshots WHERE expires_at <= ?)",
+                table
+            ))
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        let result = sqlx::query("DELETE FROM snapshots WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,46 @@
+use crate::error::AppError;
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub struct TagRepository;
+
+impl TagRepository {
+    // 在调用方的事务中为项目添加标签，已有该标签时不变；返回是否新增
+    pub async fn add_in(conn: &mut SqliteConnection, item_id: &str, tag: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("INSERT OR IGNORE INTO item_tags (item_id, tag) VALUES (?, ?)")
+            .bind(item_id)
+            .bind(tag)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // 在调用方的事务中把一个项目的标签转移到另一个项目，目标项目已有的标签不重复
+    pub async fn move_in(conn: &mut SqliteConnection, from_item_id: &str, to_item_id: &str) -> Result<(), AppError> {
+        sqlx::query("INSERT OR IGNORE INTO item_tags (item_id, tag) SELECT ?, tag FROM item_tags WHERE item_id = ?")
+            .bind(to_item_id)
+            .bind(from_item_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM item_tags WHERE item_id = ?")
+            .bind(from_item_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_item_id(pool: &SqlitePool, item_id: &str) -> Result<Vec<String>, AppError> {
+        let tags = sqlx::query_scalar("SELECT tag FROM item_tags WHERE item_id = ? ORDER BY tag")
+            .bind(item_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(tags)
+    }
+}
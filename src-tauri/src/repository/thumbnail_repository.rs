@@ -0,0 +1,65 @@
+use crate::error::AppError;
+use crate::repository::clipboard_repository::MAX_SQL_PARAMS;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+
+pub struct ThumbnailRepository;
+
+impl ThumbnailRepository {
+    // 保存项目的缩略图（PNG 字节），已存在时覆盖
+    pub async fn save(pool: &SqlitePool, item_id: &str, data: &[u8]) -> Result<(), AppError> {
+        let mut conn = pool.acquire()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Self::save_in(&mut conn, item_id, data).await
+    }
+
+    // 在调用方的连接或事务中保存缩略图
+    pub async fn save_in(conn: &mut SqliteConnection, item_id: &str, data: &[u8]) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO item_thumbnails (item_id, data)
+             VALUES (?, ?)
+             ON CONFLICT(item_id) DO UPDATE SET
+             data = excluded.data"
+        )
+        .bind(item_id)
+        .bind(data)
+        .execute(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 批量获取缩略图，返回 item_id -> PNG 字节，没有缩略图的项目不在结果中
+    pub async fn find_by_item_ids(pool: &SqlitePool, item_ids: &[String]) -> Result<HashMap<String, Vec<u8>>, AppError> {
+        let mut thumbnails = HashMap::new();
+
+        for chunk in item_ids.chunks(MAX_SQL_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("SELECT item_id, data FROM item_thumbnails WHERE item_id IN ({})", placeholders);
+            let mut query = sqlx::query_as::<_, (String, Vec<u8>)>(&sql);
+            for id in chunk {
+                query = query.bind(id);
+            }
+
+            let rows = query
+                .fetch_all(pool)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            thumbnails.extend(rows);
+        }
+
+        Ok(thumbnails)
+    }
+
+    pub async fn delete_by_item_id(conn: &mut SqliteConnection, item_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM item_thumbnails WHERE item_id = ?")
+            .bind(item_id)
+            .execute(conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
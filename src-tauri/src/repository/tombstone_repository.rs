@@ -0,0 +1,133 @@
+use crate::entity::tombstone::Tombstone;
+use crate::error::AppError;
+use crate::repository::clipboard_repository::MAX_SQL_PARAMS;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashSet;
+
+// 删除记录保留时长（秒），需要长于设备可能离线的最长时间
+pub const TOMBSTONE_RETENTION_SECS: i64 = 90 * 24 * 60 * 60;
+
+pub struct TombstoneRepository;
+
+impl TombstoneRepository {
+    // 在调用方的事务中写入删除记录
+    pub async fn record(
+        conn: &mut SqliteConnection,
+        item_id: &str,
+        user_id: &str,
+        deleted_at: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO tombstones (item_id, user_id, deleted_at)
+             VALUES (?, ?, ?)
+             ON CONFLICT(item_id) DO UPDATE SET
+             deleted_at = MAX(deleted_at, excluded.deleted_at)"
+        )
+        .bind(item_id)
+        .bind(user_id)
+        .bind(deleted_at)
+        .execute(conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // 获取某时间之后的全部删除记录（同步时使用）
+    pub async fn find_since(pool: &SqlitePool, since: i64) -> Result<Vec<Tombstone>, AppError> {
+        let tombstones = sqlx::query_as::<_, Tombstone>(
+            "SELECT item_id, user_id, deleted_at
+             FROM tombstones WHERE deleted_at > ? ORDER BY deleted_at ASC, item_id ASC"
+        )
+        .bind(since)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(tombstones)
+    }
+
+    pub async fn find_by_user_since(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Tombstone>, AppError> {
+        let tombstones = sqlx::query_as::<_, Tombstone>(
+            "SELECT item_id, user_id, deleted_at
+             FROM tombstones WHERE user_id = ? AND deleted_at > ?
+             ORDER BY deleted_at ASC, item_id ASC LIMIT ? OFFSET ?"
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(tombstones)
+    }
+
+    // 获取用户某时间之后的全部删除记录（前端增量刷新时使用）
+    pub async fn find_all_by_user_since(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64,
+    ) -> Result<Vec<Tombstone>, AppError> {
+        let tombstones = sqlx::query_as::<_, Tombstone>(
+            "SELECT item_id, user_id, deleted_at
+             FROM tombstones WHERE user_id = ? AND deleted_at > ?
+             ORDER BY deleted_at ASC, item_id ASC"
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(tombstones)
+    }
+
+    // 项目的删除时间，没有删除记录时为 None
+    pub async fn find_deleted_at(pool: &SqlitePool, item_id: &str) -> Result<Option<i64>, AppError> {
+        sqlx::query_scalar("SELECT deleted_at FROM tombstones WHERE item_id = ?")
+            .bind(item_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    // 返回给定 ID 中有删除记录的项目 ID
+    pub async fn find_deleted_ids(pool: &SqlitePool, ids: &[String]) -> Result<HashSet<String>, AppError> {
+        let mut deleted = HashSet::new();
+        for chunk in ids.chunks(MAX_SQL_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("SELECT item_id FROM tombstones WHERE item_id IN ({})", placeholders);
+
+            let mut query = sqlx::query_scalar::<_, String>(&sql);
+            for id in chunk {
+                query = query.bind(id);
+            }
+
+            deleted.extend(query
+                .fetch_all(pool)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?);
+        }
+
+        Ok(deleted)
+    }
+
+    // 清理早于指定时间的删除记录，返回清理数量
+    pub async fn prune_before(pool: &SqlitePool, cutoff: i64) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM tombstones WHERE deleted_at < ?")
+            .bind(cutoff)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::entity::user::User;
 use crate::error::AppError;
+use crate::util::db::{retry_on_busy, write_error};
 use sqlx::SqlitePool;
 
 pub struct UserRepository;
@@ -40,19 +41,21 @@ impl UserRepository {
     }
 
     pub async fn save(pool: &SqlitePool, user: &User, password_hash: &str) -> Result<(), AppError> {
-        sqlx::query(
-            "INSERT INTO users (id, email, username, password_hash, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?)",
-        )
-        .bind(&user.id)
-        .bind(&user.email)
-        .bind(&user.username)
-        .bind(password_hash)
-        .bind(user.created_at)
-        .bind(user.updated_at)
-        .execute(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        retry_on_busy(|| async move {
+            sqlx::query(
+                "INSERT INTO users (id, email, username, password_hash, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&user.id)
+            .bind(&user.email)
+            .bind(&user.username)
+            .bind(password_hash)
+            .bind(user.created_at)
+            .bind(user.updated_at)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
 
         Ok(())
     }
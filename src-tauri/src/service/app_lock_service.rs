@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sqlx::SqlitePool;
+use crate::error::AppError;
+use crate::repository::settings_repository::SettingsRepository;
+use crate::service::auth_service::AuthService;
+use crate::service::rate_limiter::RateLimiter;
+use crate::service::settings_service::{SettingsService, APP_PIN_HASH_KEY};
+use crate::util::crypto;
+
+// 应用 PIN 的位数范围
+pub const MIN_APP_PIN_LEN: usize = 4;
+pub const MAX_APP_PIN_LEN: usize = 12;
+// 校验 PIN 的失败次数限制：窗口时间（秒）内最多失败的次数
+pub const APP_PIN_MAX_FAILURES: usize = 5;
+pub const APP_PIN_WINDOW_SECS: i64 = 300;
+
+// 本机界面的解锁令牌，只保存在内存中，重启应用后需要重新输入 PIN
+// 令牌记录最近一次使用的时间，空闲超过设定时间后失效
+pub struct AppLock {
+    unlocks: Mutex<HashMap<String, i64>>,
+    pin_failures: RateLimiter,
+}
+
+impl Default for AppLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppLock {
+    pub fn new() -> Self {
+        Self {
+            unlocks: Mutex::new(HashMap::new()),
+            pin_failures: RateLimiter::new(APP_PIN_MAX_FAILURES, APP_PIN_WINDOW_SECS),
+        }
+    }
+
+    // 签发新的解锁令牌
+    pub fn issue(&self, now: i64) -> String {
+        let token = AuthService::generate_session_token();
+        self.unlocks.lock().unwrap().insert(token.clone(), now);
+        token
+    }
+
+    // 令牌未空闲超时时刷新最近使用时间并返回 true，超时的令牌被移除
+    pub fn touch(&self, token: &str, now: i64, idle_secs: i64) -> bool {
+        let mut unlocks = self.unlocks.lock().unwrap();
+        unlocks.retain(|_, last_used| now - *last_used < idle_secs);
+
+        match unlocks.get_mut(token) {
+            Some(last_used) => {
+                *last_used = now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 使全部解锁令牌失效（修改 PIN 后）
+    pub fn clear(&self) {
+        self.unlocks.lock().unwrap().clear();
+    }
+}
+
+pub struct AppLockService;
+
+impl AppLockService {
+    // 设置应用 PIN（与账户密码无关），已签发的解锁令牌全部失效
+    // 已设置过 PIN 时需要提供当前 PIN 或有效的解锁令牌
+    pub async fn set_app_pin(
+        pool: &SqlitePool,
+        lock: &AppLock,
+        pin: &str,
+        current_pin: Option<&str>,
+        unlock_token: Option<&str>
+    ) -> Result<(), AppError> {
+        if !(MIN_APP_PIN_LEN..=MAX_APP_PIN_LEN).contains(&pin.len()) || !pin.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::InvalidData(format!(
+                "PIN 必须是 {} 到 {} 位数字",
+                MIN_APP_PIN_LEN, MAX_APP_PIN_LEN
+            )));
+        }
+        
+        Self::authorize_change(pool, lock, current_pin, unlock_token).await?;
+        
+        let hash = crypto::hash_password(pin)
+            .map_err(AppError::CryptoError)?;
+        SettingsRepository::set(pool, APP_PIN_HASH_KEY, &hash).await?;
+        lock.clear();
+        
+        Ok(())
+    }
+    
+    // 修改空闲锁定时间，已设置 PIN 时同样需要当前 PIN 或有效的解锁令牌
+    pub async fn set_idle_secs(
+        pool: &SqlitePool,
+        lock: &AppLock,
+        secs: i64,
+        current_pin: Option<&str>,
+        unlock_token: Option<&str>
+    ) -> Result<(), AppError> {
+        Self::authorize_change(pool, lock, current_pin, unlock_token).await?;
+        
+        SettingsService::set_app_lock_idle_secs(pool, secs).await
+    }
+    
+    // 未设置 PIN 时总是通过；否则要求有效的解锁令牌或当前 PIN
+    async fn authorize_change(
+        pool: &SqlitePool,
+        lock: &AppLock,
+        current_pin: Option<&str>,
+        unlock_token: Option<&str>
+    ) -> Result<(), AppError> {
+        if !Self::is_pin_set(pool).await? {
+            return Ok(());
+        }
+        
+        match (current_pin, unlock_token) {
+            (_, Some(unlock_token)) if Self::require_unlocked(pool, lock, unlock_token).await.is_ok() => Ok(()),
+            (Some(current_pin), _) => Self::check_pin(pool, lock, current_pin).await,
+            _ => Err(AppError::AppLocked),
+        }
+    }
+    
+    pub async fn is_pin_set(pool: &SqlitePool) -> Result<bool, AppError> {
+        Ok(SettingsRepository::get(pool, APP_PIN_HASH_KEY).await?.is_some())
+    }
+    
+    // 校验 PIN，成功时返回解锁令牌；失败次数过多时返回 RateLimited(剩余秒数)
+    pub async fn verify_app_pin(pool: &SqlitePool, lock: &AppLock, pin: &str) -> Result<String, AppError> {
+        Self::check_pin(pool, lock, pin).await?;
+        
+        Ok(lock.issue(Self::now()))
+    }
+    
+    // 先在限流器中预占一次失败再校验，校验通过或出错时归还
+    async fn check_pin(pool: &SqlitePool, lock: &AppLock, pin: &str) -> Result<(), AppError> {
+        let hash = SettingsRepository::get(pool, APP_PIN_HASH_KEY).await?
+            .ok_or_else(|| AppError::NotFound("尚未设置应用 PIN".to_string()))?;
+        
+        let now = Self::now();
+        lock.pin_failures.check(now).map_err(AppError::RateLimited)?;
+        
+        let result = crypto::verify_password(&hash, pin)
+            .map_err(AppError::CryptoError);
+        if !matches!(result, Ok(false)) {
+            lock.pin_failures.release(now);
+        }
+        
+        match result? {
+            true => Ok(()),
+            false => Err(AppError::InvalidCredentials),
+        }
+    }
+    
+    // 未设置 PIN 时总是通过；否则要求解锁令牌在空闲时间内使用过，通过时刷新空闲计时
+    pub async fn require_unlocked(pool: &SqlitePool, lock: &AppLock, unlock_token: &str) -> Result<(), AppError> {
+        if !Self::is_pin_set(pool).await? {
+            return Ok(());
+        }
+        
+        let idle_secs = SettingsService::get_app_lock_idle_secs(pool).await?;
+        if lock.touch(unlock_token, Self::now(), idle_secs) {
+            Ok(())
+        } else {
+            Err(AppError::AppLocked)
+        }
+    }
+    
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+}
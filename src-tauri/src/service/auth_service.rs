@@ -1,17 +1,69 @@
 use sqlx::SqlitePool;
 use uuid::Uuid;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::{Rng, thread_rng};
 use std::time::{SystemTime, UNIX_EPOCH};
 use crate::entity::user::User;
-use crate::entity::session::Session;
+use crate::entity::session::{Session, SessionInfo};
 use crate::repository::user_repository::UserRepository;
 use crate::repository::session_repository::SessionRepository;
 use crate::error::AppError;
+use crate::service::key_cache::KeyCache;
+use crate::service::rate_limiter::KeyedRateLimiter;
+use crate::service::session_cache::SessionCache;
 use crate::util::crypto;
+use crate::util::validation;
+use crate::util::db;
+use crate::sync;
+
+// 确认密码的失败次数限制：窗口内失败次数达到上限后暂时拒绝校验
+pub const PASSWORD_CHECK_MAX_FAILURES: usize = 5;
+pub const PASSWORD_CHECK_WINDOW_SECS: i64 = 300;
+// 会话令牌的随机字节数
+pub const SESSION_TOKEN_BYTES: usize = 32;
 
 pub struct AuthService;
 
 impl AuthService {
     pub async fn login(pool: &SqlitePool, email: &str, password: &str, device_id: &str) -> Result<Session, AppError> {
+        let user = Self::verify_credentials(pool, email, password).await?;
+        
+        // 能正常登录说明用户记得密码，未使用的重置令牌作废
+        Self::delete_password_resets(pool, &user.id).await?;
+        
+        // 创建会话
+        let token = Self::generate_session_token();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let expires_at = now + 30 * 24 * 60 * 60; // 30天过期
+        
+        let session = Session {
+            token: token.clone(),
+            user_id: user.id,
+            device_id: Some(device_id.to_string()),
+            created_at: now,
+            expires_at,
+        };
+        
+        // 保存会话
+        SessionRepository::save(pool, &session).await?;
+        
+        Ok(session)
+    }
+    
+    // 生成会话令牌：32 字节随机数的 URL 安全 base64 编码（无填充，43 个字符）
+    // 令牌对数据库只是不透明的字符串，列类型不变
+    pub fn generate_session_token() -> String {
+        let mut bytes = [0u8; SESSION_TOKEN_BYTES];
+        thread_rng().fill(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+    
+    // 校验邮箱和密码，成功时返回用户（不创建会话）
+    pub async fn verify_credentials(pool: &SqlitePool, email: &str, password: &str) -> Result<User, AppError> {
         // 查找用户
         let user = match UserRepository::find_by_email(pool, email).await? {
             Some(user) => user,
@@ -29,40 +81,182 @@ impl AuthService {
         .password_hash;
         
         // 验证密码
-        let is_valid = crypto::verify_password(&password_hash, password)
-            .map_err(|e| AppError::CryptoError(e))?;
+        let pepper = crypto::password_pepper();
+        let password_match = crypto::check_password(&password_hash, password, pepper.as_deref())
+            .map_err(AppError::CryptoError)?;
         
-        if !is_valid {
-            return Err(AppError::InvalidCredentials);
+        match password_match {
+            crypto::PasswordMatch::Mismatch => return Err(AppError::InvalidCredentials),
+            // 加入 pepper 之前的旧哈希，登录成功后使用 pepper 重新哈希
+            crypto::PasswordMatch::Legacy => {
+                let new_password_hash = crypto::hash_password_with_pepper(password, pepper.as_deref())
+                    .map_err(AppError::CryptoError)?;
+                db::retry_on_busy(|| async {
+                    sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+                        .bind(&new_password_hash)
+                        .bind(&user.id)
+                        .execute(pool)
+                        .await
+                        .map_err(db::write_error)
+                }).await?;
+            }
+            crypto::PasswordMatch::Current => {}
         }
         
-        // 创建会话
-        let token = Uuid::new_v4().to_string();
+        Ok(user)
+    }
+    
+    // 校验另一个账号的邮箱和密码（账号合并等），不创建会话
+    // 邮箱不存在或密码错误都计入该邮箱的失败次数，失败次数过多时返回 RateLimited(剩余秒数)，不再校验密码
+    pub async fn verify_credentials_limited(
+        pool: &SqlitePool,
+        limiter: &KeyedRateLimiter,
+        email: &str,
+        password: &str
+    ) -> Result<User, AppError> {
+        let key = email.trim().to_lowercase();
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
-        let expires_at = now + 30 * 24 * 60 * 60; // 30天过期
+        // 先预占一次失败，校验通过或出错时再归还，并发的错误尝试不会越过上限
+        limiter.reserve(&key, now).map_err(AppError::RateLimited)?;
         
-        let session = Session {
-            token: token.clone(),
-            user_id: user.id,
-            device_id: Some(device_id.to_string()),
-            created_at: now,
-            expires_at,
-        };
+        let result = Self::verify_credentials(pool, email, password).await;
+        if !matches!(result, Err(AppError::InvalidCredentials) | Err(AppError::NotFound(_))) {
+            limiter.release(&key, now);
+        }
         
-        // 保存会话
-        SessionRepository::save(pool, &session).await?;
+        result
+    }
+    
+    // 确认当前会话用户的密码，不创建新会话；用于删除账户、查看敏感内容、导出备份等操作前的二次确认
+    // 密码错误时返回 false 并计入该用户的失败次数，失败次数过多时返回 RateLimited(剩余秒数)，不再校验密码
+    pub async fn verify_current_password(
+        pool: &SqlitePool,
+        limiter: &KeyedRateLimiter,
+        token: &str,
+        password: &str
+    ) -> Result<bool, AppError> {
+        let (_, user) = Self::load_session(pool, token).await?;
         
-        Ok(session)
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        // 先预占一次失败，校验通过或出错时再归还，并发的错误尝试不会越过上限
+        limiter.reserve(&user.id, now).map_err(AppError::RateLimited)?;
+        
+        let result = Self::check_password(pool, &user.id, password).await;
+        if !matches!(result, Ok(false)) {
+            limiter.release(&user.id, now);
+        }
+        
+        result
+    }
+    
+    async fn check_password(pool: &SqlitePool, user_id: &str, password: &str) -> Result<bool, AppError> {
+        let password_hash = sqlx::query!(
+            "SELECT password_hash FROM users WHERE id = ?",
+            user_id
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?
+        .password_hash;
+        
+        crypto::verify_password(&password_hash, password)
+            .map_err(AppError::CryptoError)
     }
     
     pub async fn logout(pool: &SqlitePool, token: &str) -> Result<(), AppError> {
         SessionRepository::delete_by_token(pool, token).await
     }
     
+    // 列出用户未过期的会话，标记当前会话
+    pub async fn list_sessions(pool: &SqlitePool, user_id: &str, current_token: &str) -> Result<Vec<SessionInfo>, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let sessions = SessionRepository::find_all_by_user_id(pool, user_id).await?;
+        let devices = sync::get_bound_devices(pool).await?;
+        
+        Ok(sessions
+            .into_iter()
+            .filter(|session| session.expires_at > now)
+            .map(|session| {
+                let device = session.device_id.as_ref()
+                    .and_then(|id| devices.iter().find(|device| &device.device_id == id));
+                
+                SessionInfo {
+                    device_name: device.map(|device| device.device_name.clone()),
+                    last_seen: device.map(|device| device.last_sync),
+                    is_current: session.token == current_token,
+                    device_id: session.device_id,
+                    created_at: session.created_at,
+                    expires_at: session.expires_at,
+                }
+            })
+            .collect())
+    }
+    
+    // 注销当前会话以外的全部会话
+    pub async fn logout_others(pool: &SqlitePool, user_id: &str, current_token: &str) -> Result<u64, AppError> {
+        SessionRepository::delete_others(pool, user_id, current_token).await
+    }
+    
+    // 注销某个设备上的全部会话
+    pub async fn revoke_device(pool: &SqlitePool, user_id: &str, device_id: &str) -> Result<u64, AppError> {
+        SessionRepository::delete_by_device(pool, user_id, device_id).await
+    }
+    
     pub async fn verify_session(pool: &SqlitePool, token: &str) -> Result<User, AppError> {
+        let (_, user) = Self::load_session(pool, token).await?;
+        Ok(user)
+    }
+    
+    // 优先从缓存验证会话，未命中时查询数据库并写入缓存
+    // 缓存条目与会话同时过期，过期后走数据库路径，与 verify_session 一样返回 SessionExpired
+    pub async fn verify_session_cached(
+        pool: &SqlitePool,
+        cache: &SessionCache,
+        token: &str
+    ) -> Result<User, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        if let Some(user) = cache.get(token, now) {
+            return Ok(user);
+        }
+        
+        let (session, user) = Self::load_session(pool, token).await?;
+        cache.insert(token, user.clone(), session.expires_at);
+        
+        Ok(user)
+    }
+    
+    // 验证两个会话都有效且属于同一设备，返回两个会话的用户；用于本机账号之间转移项目
+    pub async fn verify_same_device(
+        pool: &SqlitePool,
+        from_token: &str,
+        to_token: &str
+    ) -> Result<(User, User), AppError> {
+        let (from_session, from_user) = Self::load_session(pool, from_token).await?;
+        let (to_session, to_user) = Self::load_session(pool, to_token).await?;
+    
+        // 没有记录设备的会话无法确认来源，同样拒绝
+        match (&from_session.device_id, &to_session.device_id) {
+            (Some(from_device), Some(to_device)) if from_device == to_device => Ok((from_user, to_user)),
+            _ => Err(AppError::InvalidData("两个会话不在同一设备上".to_string())),
+        }
+    }
+    
+    async fn load_session(pool: &SqlitePool, token: &str) -> Result<(Session, User), AppError> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -71,7 +265,7 @@ impl AuthService {
         // 查找有效会话
         let session = match SessionRepository::find_by_token(pool, token).await? {
             Some(session) if session.expires_at > now => session,
-            Some(_) => return Err(AppError::InvalidData("会话已过期".to_string())),
+            Some(_) => return Err(AppError::SessionExpired),
             None => return Err(AppError::NotFound("会话不存在".to_string())),
         };
         
@@ -81,7 +275,7 @@ impl AuthService {
             None => return Err(AppError::NotFound("用户不存在".to_string())),
         };
         
-        Ok(user)
+        Ok((session, user))
     }
     
     pub async fn change_password(
@@ -90,52 +284,95 @@ impl AuthService {
         old_password: &str, 
         new_password: &str
     ) -> Result<(), AppError> {
-        // 获取当前密码哈希
-        let password_hash = sqlx::query!(
-            "SELECT password_hash FROM users WHERE id = ?", 
-            user_id
-        )
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?
-        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?
-        .password_hash;
+        let owner = user_id.to_string();
+        let old_password = old_password.to_string();
+        let new_password = new_password.to_string();
         
-        // 验证旧密码
-        let is_valid = crypto::verify_password(&password_hash, old_password)
-            .map_err(|e| AppError::CryptoError(e))?;
-        
-        if !is_valid {
-            return Err(AppError::InvalidData("旧密码不正确".to_string()));
-        }
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            let user_id = owner;
+            // 获取当前密码哈希
+            let password_hash = sqlx::query!(
+                "SELECT password_hash FROM users WHERE id = ?", 
+                user_id
+            )
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?
+            .password_hash;
+            
+            // 验证旧密码
+            let is_valid = crypto::verify_password(&password_hash, &old_password)
+                .map_err(AppError::CryptoError)?;
+            
+            if !is_valid {
+                return Err(AppError::InvalidData("旧密码不正确".to_string()));
+            }
+            
+            // 哈希新密码
+            let new_password_hash = crypto::hash_password(&new_password)
+                .map_err(AppError::CryptoError)?;
+            
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            
+            // 更新密码
+            sqlx::query(
+                "UPDATE users SET
+                 password_hash = ?,
+                 updated_at = ?
+                 WHERE id = ?"
+            )
+            .bind(&new_password_hash)
+            .bind(now)
+            .bind(&user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            
+            // 密码已修改，未使用的重置令牌作废
+            sqlx::query("DELETE FROM password_resets WHERE user_id = ?")
+                .bind(&user_id)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            
+            Ok(())
+        })).await?;
         
-        // 哈希新密码
-        let new_password_hash = crypto::hash_password(new_password)
-            .map_err(|e| AppError::CryptoError(e))?;
+        // 缓存的密钥重新从数据库读取
+        KeyCache::invalidate_user(pool, user_id);
         
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        Ok(())
+    }
+    
+    // 用户主动撤销未使用的重置令牌；无论是否存在都返回成功，避免泄露邮箱是否注册
+    pub async fn cancel_password_reset(pool: &SqlitePool, email: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM password_resets WHERE email = ?")
+            .bind(email)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         
-        // 更新密码
-        sqlx::query(
-            "UPDATE users SET
-             password_hash = ?,
-             updated_at = ?
-             WHERE id = ?"
-        )
-        .bind(&new_password_hash)
-        .bind(now)
-        .bind(user_id)
-        .execute(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+    
+    async fn delete_password_resets(pool: &SqlitePool, user_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM password_resets WHERE user_id = ?")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         
         Ok(())
     }
     
     pub async fn request_password_reset(pool: &SqlitePool, email: &str) -> Result<String, AppError> {
+        // 校验邮箱格式
+        validation::validate_email(email)?;
+        
         // 检查用户是否存在
         let user = match UserRepository::find_by_email(pool, email).await? {
             Some(user) => user,
@@ -150,25 +387,27 @@ impl AuthService {
             .as_secs() as i64;
         let expires_at = now + 24 * 60 * 60; // 24小时过期
         
-        // 删除旧的重置令牌
-        sqlx::query!("DELETE FROM password_resets WHERE email = ?", email)
+        // 按邮箱主键写入，旧令牌在同一条语句中被替换，不会出现没有可用令牌的中间状态；
+        // 重复执行只会更新令牌，数据库繁忙时重试
+        db::retry_on_busy(|| async {
+            sqlx::query(
+                "INSERT INTO password_resets (email, token, user_id, created_at, expires_at)
+                 VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT(email) DO UPDATE SET
+                 token = excluded.token,
+                 user_id = excluded.user_id,
+                 created_at = excluded.created_at,
+                 expires_at = excluded.expires_at"
+            )
+            .bind(email)
+            .bind(&token)
+            .bind(&user.id)
+            .bind(now)
+            .bind(expires_at)
             .execute(pool)
             .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        
-        // 创建新的重置令牌
-        sqlx::query(
-            "INSERT INTO password_resets (email, token, user_id, created_at, expires_at)
-             VALUES (?, ?, ?, ?, ?)"
-        )
-        .bind(email)
-        .bind(&token)
-        .bind(&user.id)
-        .bind(now)
-        .bind(expires_at)
-        .execute(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            .map_err(db::write_error)
+        }).await?;
         
         Ok(token)
     }
@@ -179,49 +418,73 @@ impl AuthService {
         reset_token: &str, 
         new_password: &str
     ) -> Result<(), AppError> {
-        // 验证重置令牌
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        // 哈希较慢，在重试循环外只计算一次
+        let new_password_hash = crypto::hash_password(new_password)
+            .map_err(AppError::CryptoError)?;
         
-        let reset = sqlx::query!(
-            "SELECT user_id FROM password_resets WHERE email = ? AND token = ? AND expires_at > ?",
-            email, reset_token, now
-        )
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        // 更新密码和删除令牌在同一事务中完成，数据库繁忙时整体重试
+        let user_id = db::retry_on_busy(|| Self::reset_password_once(pool, email, reset_token, &new_password_hash)).await?;
         
-        let user_id = match reset {
-            Some(reset) => reset.user_id,
-            None => return Err(AppError::InvalidData("无效或已过期的重置令牌".to_string())),
-        };
+        // 缓存的密钥重新从数据库读取
+        KeyCache::invalidate_user(pool, &user_id);
         
-        // 哈希新密码
-        let new_password_hash = crypto::hash_password(new_password)
-            .map_err(|e| AppError::CryptoError(e))?;
-        
-        // 更新密码
-        sqlx::query(
-            "UPDATE users SET
-             password_hash = ?,
-             updated_at = ?
-             WHERE id = ?"
-        )
-        .bind(&new_password_hash)
-        .bind(now)
-        .bind(&user_id)
-        .execute(pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+    
+    // 成功时返回重置了密码的用户 ID
+    async fn reset_password_once(
+        pool: &SqlitePool,
+        email: &str,
+        reset_token: &str,
+        new_password_hash: &str
+    ) -> Result<String, AppError> {
+        let email = email.to_string();
+        let reset_token = reset_token.to_string();
+        let new_password_hash = new_password_hash.to_string();
         
-        // 删除使用过的重置令牌
-        sqlx::query!("DELETE FROM password_resets WHERE email = ?", email)
-            .execute(pool)
+        // 任何一步失败时事务回滚，密码和令牌都保持原样
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            // 验证重置令牌
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            
+            let reset = sqlx::query!(
+                "SELECT user_id FROM password_resets WHERE email = ? AND token = ? AND expires_at > ?",
+                email, reset_token, now
+            )
+            .fetch_optional(&mut *conn)
             .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        
-        Ok(())
+            .map_err(db::write_error)?;
+            
+            let user_id = match reset {
+                Some(reset) => reset.user_id,
+                None => return Err(AppError::InvalidData("无效或已过期的重置令牌".to_string())),
+            };
+            
+            // 更新密码
+            sqlx::query(
+                "UPDATE users SET
+                 password_hash = ?,
+                 updated_at = ?
+                 WHERE id = ?"
+            )
+            .bind(&new_password_hash)
+            .bind(now)
+            .bind(&user_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(db::write_error)?;
+            
+            // 删除使用过的重置令牌
+            sqlx::query("DELETE FROM password_resets WHERE email = ?")
+                .bind(&email)
+                .execute(&mut *conn)
+                .await
+                .map_err(db::write_error)?;
+            
+            Ok(user_id)
+        })).await
     }
 }
\ No newline at end of file
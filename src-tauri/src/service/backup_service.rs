@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::entity::clipboard_item::ClipboardItem;
+use crate::entity::item_format::ItemFormat;
+use crate::error::AppError;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::encryption_repository::EncryptionRepository;
+use crate::repository::init::SCHEMA_VERSION_KEY;
+use crate::repository::item_format_repository::ItemFormatRepository;
+use crate::repository::settings_repository::SettingsRepository;
+use crate::service::clipboard_service::ClipboardService;
+use crate::service::maintenance_service::{LAST_COMPACTION_KEY, ORPHANS_CHECKED_KEY};
+use crate::sync;
+use crate::util::crypto;
+use crate::util::db;
+
+// 备份文件格式：魔数(4) | 版本(1) | 盐(16) | nonce(12) | 密文
+pub const BACKUP_MAGIC: &[u8; 4] = b"SCBK";
+pub const BACKUP_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = BACKUP_MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+// 记录本地数据库状态的设置项，不导出也不从备份恢复
+const INTERNAL_SETTING_KEYS: [&str; 3] = [SCHEMA_VERSION_KEY, LAST_COMPACTION_KEY, ORPHANS_CHECKED_KEY];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSetting {
+    pub key: String,
+    pub value: String,
+}
+
+// 备份内容，加密项目以明文保存，导入时使用目标用户的密钥重新加密
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupBundle {
+    pub version: u8,
+    pub exported_at: i64,
+    pub items: Vec<ClipboardItem>,
+    // 项目的其他格式，同样以明文保存；旧版本备份中没有该字段
+    #[serde(default)]
+    pub formats: Vec<ItemFormat>,
+    pub settings: Vec<BackupSetting>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupImportResult {
+    pub items_imported: usize,
+    pub items_skipped: usize, // 已存在的项目
+    pub settings_restored: usize,
+}
+
+pub struct BackupService;
+
+impl BackupService {
+    pub async fn export_encrypted(
+        pool: &SqlitePool,
+        user_id: &str,
+        passphrase: &str
+    ) -> Result<Vec<u8>, AppError> {
+        if passphrase.is_empty() {
+            return Err(AppError::InvalidData("备份密码不能为空".to_string()));
+        }
+        
+        // 导出全部项目及其他格式，加密内容先解密
+        let mut items = ClipboardRepository::find_all_by_user_id_oldest_first(pool, user_id).await?;
+        let mut formats = Vec::new();
+        for item in items.iter_mut() {
+            let mut item_formats = ItemFormatRepository::find_by_item_id(pool, &item.id).await?;
+            if item.encrypted {
+                item.content = ClipboardService::decrypt_item(pool, user_id, item).await?;
+                for format in item_formats.iter_mut() {
+                    format.content = ClipboardService::decrypt_content(pool, user_id, &format.content_type, &format.content).await?;
+                }
+            }
+            formats.extend(item_formats);
+        }
+        
+        let settings = SettingsRepository::all(pool).await?
+            .into_iter()
+            .filter(|(key, _)| !INTERNAL_SETTING_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| BackupSetting { key, value })
+            .collect();
+        
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let bundle = BackupBundle {
+            version: BACKUP_VERSION,
+            exported_at: now,
+            items,
+            formats,
+            settings,
+        };
+        
+        let plaintext = serde_json::to_vec(&bundle)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        
+        // 使用口令派生的密钥加密整个备份
+        let salt = crypto::generate_salt();
+        let nonce = crypto::generate_nonce();
+        let key = crypto::derive_key_from_passphrase(passphrase, &salt)
+            .map_err(AppError::CryptoError)?;
+        let ciphertext = crypto::encrypt_data(&plaintext, &key, &nonce)
+            .map_err(AppError::CryptoError)?;
+        
+        let mut output = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        output.extend_from_slice(BACKUP_MAGIC);
+        output.push(BACKUP_VERSION);
+        output.extend_from_slice(&salt);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        
+        Ok(output)
+    }
+    
+    // 解析并解密备份文件
+    pub fn decrypt_bundle(passphrase: &str, bytes: &[u8]) -> Result<BackupBundle, AppError> {
+        if bytes.len() < HEADER_LEN || &bytes[..BACKUP_MAGIC.len()] != BACKUP_MAGIC {
+            return Err(AppError::InvalidData("不是有效的备份文件".to_string()));
+        }
+        
+        let version = bytes[BACKUP_MAGIC.len()];
+        if version > BACKUP_VERSION {
+            return Err(AppError::InvalidData(format!("不支持的备份版本: {}", version)));
+        }
+        
+        let salt_start = BACKUP_MAGIC.len() + 1;
+        let salt = &bytes[salt_start..salt_start + SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&bytes[salt_start + SALT_LEN..HEADER_LEN]);
+        
+        let key = crypto::derive_key_from_passphrase(passphrase, salt)
+            .map_err(AppError::CryptoError)?;
+        let plaintext = crypto::decrypt_data(&bytes[HEADER_LEN..], &key, &nonce)
+            .map_err(|_| AppError::CryptoError("备份密码错误或文件已损坏".to_string()))?;
+        
+        serde_json::from_str(&plaintext)
+            .map_err(|e| AppError::InvalidData(format!("备份内容无效: {}", e)))
+    }
+    
+    pub async fn import_encrypted(
+        pool: &SqlitePool,
+        user_id: &str,
+        passphrase: &str,
+        bytes: &[u8]
+    ) -> Result<BackupImportResult, AppError> {
+        let bundle = Self::decrypt_bundle(passphrase, bytes)?;
+        
+        // 加密项目需要用户密钥，不存在时创建
+        if bundle.items.iter().any(|item| item.encrypted)
+            && EncryptionRepository::find_by_user_id(pool, user_id).await?.is_none()
+        {
+            EncryptionRepository::create_for_user(pool, user_id).await?;
+        }
+        
+        let mut items_skipped = 0;
+        
+        // 按项目分组其他格式
+        let mut formats_by_item: HashMap<String, Vec<ItemFormat>> = HashMap::new();
+        for format in bundle.formats {
+            formats_by_item.entry(format.item_id.clone()).or_default().push(format);
+        }
+        
+        let ids: Vec<String> = bundle.items.iter().map(|item| item.id.clone()).collect();
+        let existing = ClipboardRepository::find_existing_ids(pool, &ids).await?;
+        
+        let mut seen = HashSet::new();
+        let mut new_items = Vec::new();
+        let mut new_formats = Vec::new();
+        for mut item in bundle.items {
+            // 已存在的项目不覆盖，备份中重复的项目只导入第一条
+            if existing.contains(&item.id) || !seen.insert(item.id.clone()) {
+                items_skipped += 1;
+                continue;
+            }
+            
+            item.user_id = user_id.to_string();
+            if item.encrypted {
+                item.content = ClipboardService::encrypt_content(pool, user_id, &item.content_type, &item.content).await?;
+            }
+            
+            // 其他格式的加密状态与项目一致
+            if let Some(mut formats) = formats_by_item.remove(&item.id) {
+                if item.encrypted {
+                    for format in formats.iter_mut() {
+                        format.content = ClipboardService::encrypt_content(pool, user_id, &format.content_type, &format.content).await?;
+                    }
+                }
+                new_formats.push((item.id.clone(), formats));
+            }
+            
+            new_items.push(item);
+        }
+        
+        // 全部项目在一个事务中批量写入，失败时不会留下部分导入的数据
+        let items_imported = new_items.len();
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            ClipboardRepository::save_many_in(&mut *conn, &new_items, None).await?;
+            for item in &new_items {
+                sync::mark_item_unsynced_in(&mut *conn, &item.id).await?;
+            }
+            for (item_id, formats) in &new_formats {
+                ItemFormatRepository::replace(&mut *conn, item_id, formats).await?;
+            }
+            Ok(())
+        })).await?;
+        
+        let mut settings_restored = 0;
+        for setting in bundle.settings {
+            if INTERNAL_SETTING_KEYS.contains(&setting.key.as_str()) {
+                continue;
+            }
+            SettingsRepository::set(pool, &setting.key, &setting.value).await?;
+            settings_restored += 1;
+        }
+        
+        Ok(BackupImportResult {
+            items_imported,
+            items_skipped,
+            settings_restored,
+        })
+    }
+}
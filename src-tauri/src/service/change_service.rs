@@ -0,0 +1,36 @@
+use sqlx::SqlitePool;
+use crate::entity::change::ChangeFeed;
+use crate::error::AppError;
+use crate::repository::change_repository::ChangeRepository;
+
+pub struct ChangeService;
+
+impl ChangeService {
+    // 获取游标之后的变更记录（按 seq 升序）
+    pub async fn get_changes_since(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64,
+        limit: i64
+    ) -> Result<ChangeFeed, AppError> {
+        let (min_seq, max_seq) = ChangeRepository::seq_range(pool).await?;
+        
+        // 游标与最早的保留记录之间有缺口时，增量结果不完整
+        let reset_required = min_seq.map(|min| since < min - 1).unwrap_or(false);
+        
+        let changes = ChangeRepository::find_since(pool, user_id, since, limit).await?;
+        
+        // 结果被截断时只把游标推进到最后一条返回的记录
+        let latest_seq = if changes.len() as i64 >= limit {
+            changes.last().map(|change| change.seq).unwrap_or(since)
+        } else {
+            max_seq.unwrap_or(0).max(since)
+        };
+        
+        Ok(ChangeFeed {
+            changes,
+            latest_seq,
+            reset_required,
+        })
+    }
+}
@@ -1,22 +1,163 @@
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use uuid::Uuid;
+use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
-use crate::entity::clipboard_item::{ClipboardItem, ClipboardItemRequest, ClipboardItemUpdateRequest};
+use crate::entity::clipboard_item::{ClipboardItem, ClipboardItemRequest, ClipboardItemUpdateRequest, SearchPage};
+use crate::entity::provenance::{ItemProvenance, OriginDeviceCount, OriginReport, ProvenanceStatus};
+use crate::entity::change::ItemsChangedSince;
+use crate::entity::content_type::ContentType;
 use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::tombstone_repository::TombstoneRepository;
+use crate::repository::tag_repository::TagRepository;
+use crate::repository::thumbnail_repository::ThumbnailRepository;
 use crate::error::AppError;
-use crate::util::crypto;
+use crate::util::crypto::{self, AeadCipher};
 use crate::repository::encryption_repository::EncryptionRepository;
+use crate::service::key_cache::KeyCache;
+use crate::service::settings_service::{PreviewLengths, RedactionPattern, SettingsService};
+use crate::sync;
+use crate::util::db;
+use crate::util::encoding::{self, Base64Content};
+use crate::util::text;
+use crate::util::thumbnail::{self, THUMBNAIL_MAX_SIZE};
+use crate::util::transform::{self, Transform};
+use crate::util::validation;
+use crate::entity::audit_log::AUDIT_REVEAL_ITEM;
+use crate::repository::audit_repository::AuditRepository;
+use crate::repository::item_format_repository::ItemFormatRepository;
+use crate::entity::item_format::{format_richness, ItemFormat};
+use crate::service::search_index_service::SearchIndexService;
+use crate::repository::search_index_repository::SearchIndexRepository;
+use crate::service::stats_service::StatsService;
+
+// 敏感项目未确认查看时显示的占位内容
+pub const SENSITIVE_PLACEHOLDER: &str = "••••••••";
+
+// 加密项目的密钥无法加载时显示的占位内容
+pub const UNDECRYPTABLE_PLACEHOLDER: &str = "[无法解密]";
+
+// 备注的最大长度（字符数）
+pub const MAX_NOTE_CHARS: usize = 1000;
+
+// 标签的最大长度（字符数）
+pub const MAX_TAG_CHARS: usize = 64;
+
+// 批量打标签时超过该数量的匹配项目需要调用方确认
+pub const TAG_MATCHING_CONFIRM_THRESHOLD: usize = 200;
+
+// 派生明文哈希密钥时使用的域分隔标签
+const CONTENT_HASH_CONTEXT: &[u8] = b"sharing-copyboard/content-hash/v1";
+
+// 重置加密的结果：undecryptable 为当前密钥无法解密的加密项目数，
+// 未确认时只统计，discarded 为 0 且不更换密钥
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EncryptionResetResult {
+    pub undecryptable: u64,
+    pub discarded: u64,
+    pub reencrypted: u64,
+    pub rekeyed: bool,
+}
 
 pub struct ClipboardService;
 
 impl ClipboardService {
+    // 校验分页参数：拒绝负数，limit 超过上限时截断为上限，返回实际生效的 (limit, offset)
+    pub async fn page_bounds(pool: &SqlitePool, limit: i64, offset: i64) -> Result<(i64, i64), AppError> {
+        if limit < 0 || offset < 0 {
+            return Err(AppError::InvalidData("分页参数不能为负数".to_string()));
+        }
+        
+        let max_page_size = SettingsService::get_max_page_size(pool).await?;
+        Ok((limit.min(max_page_size), offset))
+    }
+    
+    // reveal_sensitive 为 false 时隐藏敏感项目的内容
     pub async fn get_items(
         pool: &SqlitePool, 
         user_id: &str, 
         limit: i64, 
-        offset: i64
+        offset: i64,
+        reveal_sensitive: bool
     ) -> Result<Vec<ClipboardItem>, AppError> {
-        ClipboardRepository::find_all_by_user_id(pool, user_id, limit, offset).await
+        let (limit, offset) = Self::page_bounds(pool, limit, offset).await?;
+        let items = ClipboardRepository::find_all_by_user_id(pool, user_id, limit, offset).await?;
+        let items = Self::with_thumbnails(pool, items).await?;
+        
+        // 无法解密的加密项目单独标记，不影响列表中的其他项目；在隐藏敏感内容前检查，此时内容仍是密文
+        let items = Self::mark_undecryptable(pool, user_id, items).await?;
+        
+        let items = if reveal_sensitive {
+            items
+        } else {
+            Self::mask_sensitive(items)
+        };
+        
+        // 脱敏只影响返回的内容，完整内容仍可通过 reveal_item 查看
+        let patterns = SettingsService::get_redaction_patterns(pool).await?;
+        let items = Self::redact(items, &patterns);
+        
+        // 预览在隐藏敏感内容和脱敏之后生成，不会泄露被隐藏的内容
+        let lengths = SettingsService::get_preview_lengths(pool).await?;
+        Ok(Self::with_previews(items, &lengths))
+    }
+    
+    // 获取某时间之后新增或修改的项目，以及之后删除的项目 id
+    pub async fn get_items_changed_since(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64
+    ) -> Result<ItemsChangedSince, AppError> {
+        let items = ClipboardRepository::find_updated_since(pool, user_id, since).await?;
+        let tombstones = TombstoneRepository::find_all_by_user_since(pool, user_id, since).await?;
+        
+        let latest_ts = items.iter().map(|item| item.updated_at)
+            .chain(tombstones.iter().map(|tombstone| tombstone.deleted_at))
+            .fold(since, i64::max);
+        
+        // 删除后又同步回来的项目以现有项目为准
+        let deleted_ids = tombstones
+            .into_iter()
+            .map(|tombstone| tombstone.item_id)
+            .filter(|id| !items.iter().any(|item| &item.id == id))
+            .collect();
+        
+        let lengths = SettingsService::get_preview_lengths(pool).await?;
+        Ok(ItemsChangedSince {
+            items: Self::with_previews(Self::mask_sensitive(items), &lengths),
+            deleted_ids,
+            latest_ts,
+        })
+    }
+    
+    // 返回项目的明文内容（加密项目解密后返回），不修改数据库
+    pub async fn peek_item(pool: &SqlitePool, user_id: &str, id: &str) -> Result<String, AppError> {
+        // 只能查看自己的项目
+        let item = ClipboardRepository::find_by_id(pool, id, user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        
+        Self::decrypt_item(pool, user_id, &item).await
+    }
+    
+    // 查看敏感项目的真实内容，并写入审计记录
+    pub async fn reveal_item(pool: &SqlitePool, user_id: &str, id: &str) -> Result<String, AppError> {
+        let content = Self::peek_item(pool, user_id, id).await?;
+        AuditRepository::record(pool, user_id, AUDIT_REVEAL_ITEM, Some(id)).await?;
+        
+        Ok(content)
+    }
+    
+    // 读取项目明文并按指定方式转换，用于“复制为”
+    pub async fn transform_item(
+        pool: &SqlitePool,
+        user_id: &str,
+        id: &str,
+        transform: Transform
+    ) -> Result<String, AppError> {
+        let item = ClipboardRepository::find_by_id(pool, id, user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        let content = Self::decrypt_item(pool, user_id, &item).await?;
+        
+        transform::apply(transform, &item.content_type, &content)
     }
     
     pub async fn add_item(
@@ -30,36 +171,141 @@ impl ClipboardService {
         //     .unwrap()
         //     .as_secs() as i64;
         
+        // 校验内容与声明的类型一致，其他格式同样校验且类型不能重复
+        validation::validate_content(&request.content_type, &request.content)?;
+        let mut format_types = HashSet::from([request.content_type.clone()]);
+        for format in &request.alternate_formats {
+            validation::validate_content(&format.content_type, &format.content)?;
+            if !format_types.insert(format.content_type.clone()) {
+                return Err(AppError::InvalidData(format!("重复的格式: {}", format.content_type)));
+            }
+        }
+        
+        // 超出硬配额时拒绝新内容，按明文大小估算
+        let quota = SettingsService::get_storage_quota(pool, user_id).await?;
+        if let Some(hard_limit) = quota.hard_limit_bytes {
+            let incoming = request.content.len()
+                + request.alternate_formats.iter().map(|f| f.content.len()).sum::<usize>();
+            let used = StatsService::storage_used(pool, user_id).await?;
+            if used + incoming as i64 > hard_limit {
+                return Err(AppError::QuotaExceeded(format!(
+                    "已使用 {} 字节，新增 {} 字节，上限 {} 字节", used, incoming, hard_limit
+                )));
+            }
+        }
+        
         let mut content = request.content.clone();
         let mut encrypted = false;
+        let mut raw_content = None;
+        
+        // 未指定是否加密时使用用户的默认加密策略
+        let encrypt = match request.encrypt {
+            Some(encrypt) => encrypt,
+            None => SettingsService::get_encryption_policy(pool, user_id).await?.default_encrypt,
+        };
+        
+        // 未手动指定时按内容自动检测敏感信息
+        let is_sensitive = request.is_sensitive.unwrap_or_else(|| {
+            text::is_text_content_type(&request.content_type) && text::looks_sensitive(&request.content)
+        });
+        
+        // 按设置清理文本内容，图片等非文本内容不做处理
+        if text::is_text_content_type(&request.content_type) {
+            let sanitize = SettingsService::get_sanitize_settings(pool).await?;
+            if sanitize.enabled {
+                let sanitized = text::sanitize_text(&content);
+                // 加密项目不保留明文原始内容
+                if sanitize.keep_raw && !encrypt && sanitized != content {
+                    raw_content = Some(content.clone());
+                }
+                content = sanitized;
+            }
+        }
         
         // 如果需要加密
-        if request.encrypt {
-            // 获取用户的加密密钥
-            let encryption_key = EncryptionRepository::find_by_user_id(pool, user_id).await?
-                .ok_or_else(|| AppError::NotFound("加密密钥不存在".to_string()))?;
-            
-            // 加密内容
-            let nonce = crypto::generate_nonce();
-            let encrypted_data = crypto::encrypt_data(
-                content.as_bytes(),
-                &encryption_key.key_data,
-                &nonce
-            ).map_err(|e| AppError::CryptoError(e))?;
-            
-            // 将加密后的数据和nonce一起存储
-            let combined = [&nonce[..], &encrypted_data[..]].concat();
-            content = base64::encode(combined);
+        let plaintext = content.clone();
+        if encrypt {
+            content = Self::encrypt_content(pool, user_id, &request.content_type, &content).await?;
             encrypted = true;
         }
         
-        let item = ClipboardItem::new(user_id, &content, &request.content_type.clone(), encrypted);
+        let mut item = ClipboardItem::new(user_id, &content, &request.content_type.clone(), encrypted);
+        item.raw_content = raw_content;
+        item.source_app = request.source_app.clone();
+        item.is_sensitive = is_sensitive;
+        
+        // 明文哈希在加密前计算，用于识别加密与明文的重复副本
+        let content_hash = Self::content_hash(pool, user_id, &plaintext).await?;
+        let index_hashes = SearchIndexService::item_hashes(pool, user_id, &item, &plaintext).await?;
+        let thumbnail = Self::generate_thumbnail(&item);
+        
+        // 其他格式的加密状态与项目一致
+        let mut formats = Vec::with_capacity(request.alternate_formats.len());
+        for format in &request.alternate_formats {
+            let content = if encrypted {
+                Self::encrypt_content(pool, user_id, &format.content_type, &format.content).await?
+            } else {
+                format.content.clone()
+            };
+            formats.push(ItemFormat {
+                item_id: item.id.clone(),
+                content_type: format.content_type.clone(),
+                content,
+            });
+        }
+        
+        // 项目和哈希、同步状态、索引、缩略图、其他格式在同一事务中写入，任一步失败时不会留下不完整的项目
+        let saved = item.clone();
+        let owner = user_id.to_string();
+        let thumbnail_data = thumbnail.clone();
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            ClipboardRepository::save_in(&mut *conn, &saved).await?;
+            ClipboardRepository::set_content_hash_in(&mut *conn, &saved.id, content_hash.as_deref()).await?;
+            sync::mark_item_unsynced_in(&mut *conn, &saved.id).await?;
+            if let Some(hashes) = index_hashes {
+                SearchIndexRepository::replace_in(&mut *conn, &saved.id, &owner, &hashes).await?;
+            }
+            if let Some(data) = thumbnail_data {
+                ThumbnailRepository::save_in(&mut *conn, &saved.id, &data).await?;
+            }
+            if !formats.is_empty() {
+                ItemFormatRepository::replace(&mut *conn, &saved.id, &formats).await?;
+            }
+            Ok(())
+        })).await?;
         
-        ClipboardRepository::save(pool, &item).await?;
+        if let Some(data) = thumbnail {
+            item.thumbnail = Some(encoding::encode(data));
+        }
         
         Ok(item)
     }
     
+    // 获取项目的全部格式（包括主格式），内容已解密，按丰富程度从高到低排序
+    pub async fn get_item_formats(pool: &SqlitePool, user_id: &str, id: &str) -> Result<Vec<ItemFormat>, AppError> {
+        // 只能查看自己的项目
+        let item = ClipboardRepository::find_by_id(pool, id, user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        
+        let mut formats = vec![ItemFormat {
+            item_id: item.id.clone(),
+            content_type: item.content_type.clone(),
+            content: Self::decrypt_item(pool, user_id, &item).await?,
+        }];
+        
+        for mut format in ItemFormatRepository::find_by_item_id(pool, &item.id).await? {
+            if item.encrypted {
+                format.content = Self::decrypt_content(pool, user_id, &format.content_type, &format.content).await?;
+            }
+            formats.push(format);
+        }
+        
+        // 丰富程度相同时主格式在前
+        formats.sort_by_key(|format| std::cmp::Reverse(format_richness(&format.content_type)));
+        
+        Ok(formats)
+    }
+    
     pub async fn update_item(
         pool: &SqlitePool, 
         user_id: &str, 
@@ -69,31 +315,200 @@ impl ClipboardService {
         let existing = ClipboardRepository::find_by_id(pool, &request.id, user_id).await?
             .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
         
-        let mut content = request.content.clone();
-        let mut encrypted = false;
+        // 内容或类型变化时重新校验
+        if request.content.is_some() || request.content_type.is_some() {
+            let content_type = request.content_type.as_deref().unwrap_or(&existing.content_type);
+            match &request.content {
+                Some(content) => validation::validate_content(content_type, content)?,
+                None => {
+                    let plaintext = Self::decrypt_item(pool, user_id, &existing).await?;
+                    validation::validate_content(content_type, &plaintext)?;
+                }
+            }
+        }
         
-        // 如果需要加密
-        if request.encrypt {
-            // 获取用户的加密密钥
-            let encryption_key = EncryptionRepository::find_by_user_id(pool, user_id).await?
-                .ok_or_else(|| AppError::NotFound("加密密钥不存在".to_string()))?;
-            
-            // 加密内容
-            let nonce = crypto::generate_nonce();
-            let encrypted_data = crypto::encrypt_data(
-                content.as_bytes(),
-                &encryption_key.key_data,
-                &nonce
-            ).map_err(|e| AppError::CryptoError(e))?;
+        let mut item = existing.clone();
+        let encrypt = request.encrypt.unwrap_or(existing.encrypted);
+        let content_type = request.content_type.clone().unwrap_or_else(|| existing.content_type.clone());
+        
+        match &request.content {
+            // 内容变化时按目标加密状态重新处理
+            Some(content) => {
+                item.content = if encrypt {
+                    Self::encrypt_content(pool, user_id, &content_type, content).await?
+                } else {
+                    content.clone()
+                };
+                item.encrypted = encrypt;
+            }
+            // 仅切换加密状态，或加密项目的类型变化（二进制与文本的加密方式不同）时，先解密原内容再处理
+            None if encrypt != existing.encrypted || (encrypt && content_type != existing.content_type) => {
+                let plaintext = Self::decrypt_item(pool, user_id, &existing).await?;
+                item.content = if encrypt {
+                    Self::encrypt_content(pool, user_id, &content_type, &plaintext).await?
+                } else {
+                    plaintext
+                };
+                item.encrypted = encrypt;
+            }
+            None => {}
+        }
+        
+        item.content_type = content_type;
+        
+        // 手动指定优先；否则新内容被检测为敏感时标记，不会自动取消已有标记
+        match (request.is_sensitive, &request.content) {
+            (Some(is_sensitive), _) => item.is_sensitive = is_sensitive,
+            (None, Some(content)) if text::is_text_content_type(&item.content_type) => {
+                item.is_sensitive = existing.is_sensitive || text::looks_sensitive(content);
+            }
+            _ => {}
+        }
+        
+        item.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        ClipboardRepository::update(pool, &item).await?;
+        sync::mark_item_unsynced(pool, &item.id).await?;
+        
+        // 内容被替换后其他格式已过期，直接删除；仅切换加密状态时按新状态重新处理
+        if request.content.is_some() {
+            let mut conn = pool.acquire()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            ItemFormatRepository::delete_by_item_id(&mut conn, &item.id).await?;
+        } else if item.encrypted != existing.encrypted {
+            let mut formats = ItemFormatRepository::find_by_item_id(pool, &item.id).await?;
+            for format in formats.iter_mut() {
+                format.content = if item.encrypted {
+                    Self::encrypt_content(pool, user_id, &format.content_type, &format.content).await?
+                } else {
+                    Self::decrypt_content(pool, user_id, &format.content_type, &format.content).await?
+                };
+            }
             
-            // 将加密后的数据和nonce一起存储
-            let combined = [&nonce[..], &encrypted_data[..]].concat();
-            content = base64::encode(combined);
-            encrypted = true;
+            let mut conn = pool.acquire()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            ItemFormatRepository::replace(&mut conn, &item.id, &formats).await?;
+        }
+        
+        // 内容或加密状态变化后重新生成缩略图，加密后不保留明文缩略图
+        if request.content.is_some() || item.encrypted != existing.encrypted {
+            let mut conn = pool.acquire()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            ThumbnailRepository::delete_by_item_id(&mut conn, &item.id).await?;
+            drop(conn);
+            Self::store_thumbnail(pool, &mut item).await?;
+        }
+        
+        // 更新搜索索引（加密状态切换后也需要同步增删）
+        let plaintext = match &request.content {
+            Some(content) => content.clone(),
+            None => Self::decrypt_item(pool, user_id, &item).await?,
+        };
+        SearchIndexService::index_item(pool, user_id, &item, &plaintext).await?;
+        
+        // 内容变化时重新计算明文哈希；仅切换加密状态时明文不变，哈希无需更新
+        if request.content.is_some() {
+            let content_hash = Self::content_hash(pool, user_id, &plaintext).await?;
+            ClipboardRepository::set_content_hash(pool, &item.id, content_hash.as_deref()).await?;
+        }
+        
+        Ok(item)
+    }
+    
+    // 设置或清除项目备注，空白备注视为清除；备注会随项目同步
+    pub async fn set_item_note(
+        pool: &SqlitePool, 
+        user_id: &str, 
+        id: &str, 
+        note: Option<&str>
+    ) -> Result<ClipboardItem, AppError> {
+        let note = note.map(str::trim).filter(|note| !note.is_empty());
+        if let Some(note) = note {
+            if note.chars().count() > MAX_NOTE_CHARS {
+                return Err(AppError::InvalidData(format!("备注不能超过 {} 个字符", MAX_NOTE_CHARS)));
+            }
         }
-        let item = ClipboardItem::new(user_id, &content, &request.content_type.clone(), encrypted);
+        
+        let mut item = ClipboardRepository::find_by_id(pool, id, user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        
+        item.note = note.map(str::to_string);
+        item.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
         
         ClipboardRepository::update(pool, &item).await?;
+        sync::mark_item_unsynced(pool, &item.id).await?;
+        
+        Ok(item)
+    }
+    
+    // 将文本追加到项目内容末尾（便签式累积），加密项目解密后追加再重新加密
+    // 读取和写入在同一事务中完成，连续追加不会丢失；合并后的内容同样受长度限制
+    pub async fn append_to_item(
+        pool: &SqlitePool, 
+        user_id: &str, 
+        id: &str, 
+        addition: &str
+    ) -> Result<ClipboardItem, AppError> {
+        if addition.is_empty() {
+            return Err(AppError::InvalidData("追加的内容不能为空".to_string()));
+        }
+        
+        let key = EncryptionRepository::find_by_user_id(pool, user_id).await?;
+        let cipher = SettingsService::get_cipher(pool).await?;
+        let owner = user_id.to_string();
+        let id = id.to_string();
+        let addition = addition.to_string();
+        
+        let (item, plaintext) = db::with_transaction(pool, move |conn| Box::pin(async move {
+            let mut item = ClipboardRepository::find_by_id_in(&mut *conn, &id, &owner).await?
+                .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+            if !text::is_text_content_type(&item.content_type) {
+                return Err(AppError::InvalidData("只能向文本项目追加内容".to_string()));
+            }
+            
+            let key_data = match (&key, item.encrypted) {
+                (Some(key), true) => Some(key.key_data.as_slice()),
+                (None, true) => return Err(AppError::KeyUnavailable("加密密钥不存在".to_string())),
+                (_, false) => None,
+            };
+            
+            let mut plaintext = match key_data {
+                Some(key_data) => Self::decrypt_with_key(key_data, &item.content_type, &item.content)?,
+                None => item.content.clone(),
+            };
+            plaintext.push_str(&addition);
+            validation::validate_content(&item.content_type, &plaintext)?;
+            
+            item.content = match key_data {
+                Some(key_data) => Self::encrypt_with_key(cipher, key_data, &item.content_type, &plaintext)?,
+                None => plaintext.clone(),
+            };
+            item.is_sensitive = item.is_sensitive || text::looks_sensitive(&addition);
+            item.updated_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            
+            ClipboardRepository::update_in(&mut *conn, &item).await?;
+            // 内容已变化，其他格式过期
+            ItemFormatRepository::delete_by_item_id(&mut *conn, &item.id).await?;
+            
+            Ok((item, plaintext))
+        })).await?;
+        
+        sync::mark_item_unsynced(pool, &item.id).await?;
+        SearchIndexService::index_item(pool, user_id, &item, &plaintext).await?;
+        let content_hash = Self::content_hash(pool, user_id, &plaintext).await?;
+        ClipboardRepository::set_content_hash(pool, &item.id, content_hash.as_deref()).await?;
         
         Ok(item)
     }
@@ -102,14 +517,668 @@ impl ClipboardService {
         ClipboardRepository::delete(pool, id, user_id).await
     }
     
+    pub async fn get_items_by_source(
+        pool: &SqlitePool, 
+        user_id: &str, 
+        source_app: &str, 
+        limit: i64, 
+        offset: i64
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        let (limit, offset) = Self::page_bounds(pool, limit, offset).await?;
+        let items = ClipboardRepository::find_by_source_app(pool, user_id, source_app, limit, offset).await?;
+        Ok(Self::mask_sensitive(items))
+    }
+    
+    // 用户历史中出现的内容类型及数量，供前端构建筛选列表
+    pub async fn list_content_types(pool: &SqlitePool, user_id: &str) -> Result<Vec<(String, i64)>, AppError> {
+        ClipboardRepository::count_by_content_type(pool, user_id).await
+    }
+    
+    // 查询最后由指定设备修改的项目，并按来源设备统计数量（设备名称取自绑定设备列表）
+    pub async fn get_items_by_origin(
+        pool: &SqlitePool, 
+        user_id: &str, 
+        device_id: &str, 
+        limit: i64, 
+        offset: i64
+    ) -> Result<OriginReport, AppError> {
+        let (limit, offset) = Self::page_bounds(pool, limit, offset).await?;
+        let items = ClipboardRepository::find_by_origin_device(pool, user_id, device_id, limit, offset).await?;
+        
+        let bound_devices = sync::get_bound_devices(pool).await?;
+        let devices = ClipboardRepository::count_by_origin_device(pool, user_id).await?
+            .into_iter()
+            .map(|(device_id, count)| {
+                let device_name = device_id.as_ref().and_then(|id| {
+                    bound_devices.iter()
+                        .find(|device| &device.device_id == id)
+                        .map(|device| device.device_name.clone())
+                });
+                OriginDeviceCount { device_id, device_name, count }
+            })
+            .collect();
+        
+        Ok(OriginReport {
+            items: Self::mask_sensitive(items),
+            devices,
+        })
+    }
+    
+    // 查询项目最后由哪个设备修改；本机修改和没有记录来源的旧数据返回 unknown
+    pub async fn get_item_provenance(pool: &SqlitePool, user_id: &str, id: &str) -> Result<ItemProvenance, AppError> {
+        let (device_id, updated_at) = ClipboardRepository::find_origin(pool, id, user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        
+        let device_name = match &device_id {
+            Some(id) => sync::get_bound_devices(pool).await?
+                .into_iter()
+                .find(|device| &device.device_id == id)
+                .map(|device| device.device_name),
+            None => None,
+        };
+        let status = match device_id {
+            Some(_) => ProvenanceStatus::Remote,
+            None => ProvenanceStatus::Unknown,
+        };
+        
+        Ok(ItemProvenance {
+            item_id: id.to_string(),
+            status,
+            device_id,
+            device_name,
+            updated_at,
+        })
+    }
+    
     pub async fn search_items(
         pool: &SqlitePool, 
         user_id: &str, 
         query: &str, 
+        include_notes: bool, 
         limit: i64, 
         offset: i64
     ) -> Result<Vec<ClipboardItem>, AppError> {
-        ClipboardRepository::search(pool, user_id, query, limit, offset).await
+        let (limit, offset) = Self::page_bounds(pool, limit, offset).await?;
+        
+        // 开启加密搜索索引后，加密项目按查询词哈希匹配
+        let items = if SearchIndexService::is_enabled(pool, user_id).await? {
+            let hashes = SearchIndexService::query_hashes(pool, user_id, query).await?;
+            ClipboardRepository::search_with_index(pool, user_id, query, &hashes, include_notes, limit, offset).await?
+        } else {
+            ClipboardRepository::search(pool, user_id, query, include_notes, limit, offset).await?
+        };
+        let items = Self::with_thumbnails(pool, items).await?;
+        Ok(Self::mask_sensitive(items))
+    }
+    
+    // 分页搜索，同时返回匹配总数和下一页的偏移量（已经是最后一页时为 None）
+    pub async fn search_page(
+        pool: &SqlitePool,
+        user_id: &str,
+        query: &str,
+        include_notes: bool,
+        limit: i64,
+        offset: i64
+    ) -> Result<SearchPage, AppError> {
+        let (limit, offset) = Self::page_bounds(pool, limit, offset).await?;
+        
+        let items = Self::search_items(pool, user_id, query, include_notes, limit, offset).await?;
+        // 总数与 search_items 使用相同的匹配条件
+        let total = if SearchIndexService::is_enabled(pool, user_id).await? {
+            let hashes = SearchIndexService::query_hashes(pool, user_id, query).await?;
+            ClipboardRepository::count_search_with_index(pool, user_id, query, &hashes, include_notes).await?
+        } else {
+            ClipboardRepository::count_search(pool, user_id, query, include_notes).await?
+        };
+        
+        let end = offset + items.len() as i64;
+        let next_offset = if !items.is_empty() && end < total { Some(end) } else { None };
+        
+        Ok(SearchPage { items, total, next_offset })
+    }
+    
+    // 为所有匹配搜索的项目添加标签（单个事务），返回新打上标签的项目数
+    // 匹配数量超过确认阈值且未确认时拒绝，避免误操作大量项目
+    pub async fn tag_matching(
+        pool: &SqlitePool,
+        user_id: &str,
+        query: &str,
+        include_notes: bool,
+        tag: &str,
+        confirm: bool
+    ) -> Result<u64, AppError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(AppError::InvalidData("标签不能为空".to_string()));
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            return Err(AppError::InvalidData(format!("标签不能超过 {} 个字符", MAX_TAG_CHARS)));
+        }
+        if query.trim().is_empty() {
+            return Err(AppError::InvalidData("搜索词不能为空".to_string()));
+        }
+        
+        // 与 search_items 的匹配规则一致，但不分页；未确认时只多取一条用于判断是否超过阈值
+        let limit = if confirm { i64::MAX } else { TAG_MATCHING_CONFIRM_THRESHOLD as i64 + 1 };
+        let items = if SearchIndexService::is_enabled(pool, user_id).await? {
+            let hashes = SearchIndexService::query_hashes(pool, user_id, query).await?;
+            ClipboardRepository::search_with_index(pool, user_id, query, &hashes, include_notes, limit, 0).await?
+        } else {
+            ClipboardRepository::search(pool, user_id, query, include_notes, limit, 0).await?
+        };
+        
+        if !confirm && items.len() > TAG_MATCHING_CONFIRM_THRESHOLD {
+            return Err(AppError::InvalidData(format!(
+                "匹配的项目超过 {} 个，需要确认后才能批量添加标签",
+                TAG_MATCHING_CONFIRM_THRESHOLD
+            )));
+        }
+        
+        let ids: Vec<String> = items.into_iter().map(|item| item.id).collect();
+        let tag = tag.to_string();
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            let mut tagged = 0;
+            for id in &ids {
+                if TagRepository::add_in(&mut *conn, id, &tag).await? {
+                    tagged += 1;
+                }
+            }
+            Ok(tagged)
+        })).await
+    }
+    
+    // 清空历史，include_pinned 为 false 时保留置顶项目，返回删除的数量
+    pub async fn clear_history(pool: &SqlitePool, user_id: &str, include_pinned: bool) -> Result<u64, AppError> {
+        let owner = user_id.to_string();
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            ClipboardRepository::clear_history_in(&mut *conn, &owner, include_pinned).await
+        })).await
+    }
+    
+    // 合并重复项目：按明文哈希分组，保留置顶的一条，没有置顶时保留最早的一条，返回删除的数量
+    // 被删除项目的标签转移到保留的项目上
+    pub async fn dedupe_items(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+        let items = ClipboardRepository::find_all_by_user_id_oldest_first(pool, user_id).await?;
+        let hashes = ClipboardRepository::find_content_hashes(pool, user_id).await?;
+        let pinned = ClipboardRepository::find_pinned_ids(pool, user_id).await?;
+        // 用户还没有加密密钥时用临时密钥在内存中分组，算出的哈希不保存
+        let stored_key = Self::content_hash_key(pool, user_id).await?;
+        let persist_hashes = stored_key.is_some();
+        let hash_key = stored_key.unwrap_or_else(|| crypto::generate_encryption_key().to_vec());
+        
+        // 按类型和哈希分组，组内按创建时间从早到晚排列
+        let mut group_index: HashMap<(String, String), usize> = HashMap::new();
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        
+        for item in &items {
+            let content_hash = match hashes.get(&item.id).cloned().flatten() {
+                Some(content_hash) => content_hash,
+                // 旧数据或同步来的项目没有哈希，解密后计算并补存
+                None => {
+                    let plaintext = match Self::decrypt_item(pool, user_id, item).await {
+                        Ok(plaintext) => plaintext,
+                        // 密文损坏的项目不参与合并；密钥不可用时整体失败，重新登录后再试
+                        Err(AppError::DecryptionFailed(e)) => {
+                            eprintln!("跳过无法解密的项目 {}: {}", item.id, e);
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    let content_hash = Self::hash_with_key(&hash_key, &plaintext);
+                    if persist_hashes {
+                        ClipboardRepository::set_content_hash(pool, &item.id, Some(&content_hash)).await?;
+                    }
+                    content_hash
+                }
+            };
+            
+            let key = (item.content_type.clone(), content_hash);
+            match group_index.get(&key) {
+                Some(&index) => groups[index].push(item.id.clone()),
+                None => {
+                    group_index.insert(key, groups.len());
+                    groups.push(vec![item.id.clone()]);
+                }
+            }
+        }
+        
+        let merges: Vec<(String, Vec<String>)> = groups
+            .into_iter()
+            .filter(|ids| ids.len() > 1)
+            .map(|mut ids| {
+                let keep = ids.iter().position(|id| pinned.contains(id)).unwrap_or(0);
+                let survivor = ids.remove(keep);
+                (survivor, ids)
+            })
+            .collect();
+        
+        if merges.is_empty() {
+            return Ok(0);
+        }
+        
+        let owner = user_id.to_string();
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            let mut deleted = 0;
+            for (survivor, duplicates) in &merges {
+                for duplicate in duplicates {
+                    TagRepository::move_in(&mut *conn, duplicate, survivor).await?;
+                }
+                deleted += ClipboardRepository::delete_many_in(&mut *conn, duplicates, &owner).await?;
+            }
+            Ok(deleted)
+        })).await
+    }
+    
+    // 重置加密：生成新密钥，能解密的项目用新密钥重新加密，无法解密的项目删除
+    // confirm 为 false 时不做任何修改，只返回无法解密的项目数；已绑定的设备需要重新配对获取新密钥
+    pub async fn reset_encryption(pool: &SqlitePool, user_id: &str, confirm: bool) -> Result<EncryptionResetResult, AppError> {
+        let current_key = EncryptionRepository::find_by_user_id(pool, user_id).await?;
+        
+        let mut conn = pool.acquire()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let encrypted_items = ClipboardRepository::find_encrypted_by_user_id(&mut conn, user_id).await?;
+        let encrypted_formats = ItemFormatRepository::find_encrypted_by_user_id(&mut conn, user_id).await?;
+        drop(conn);
+        
+        // 按当前密钥分为可解密和不可解密两组，没有密钥时全部无法解密
+        let try_decrypt = |content_type: &str, content: &str| current_key
+            .as_ref()
+            .and_then(|key| Self::decrypt_with_key(&key.key_data, content_type, content).ok());
+        
+        let mut readable = Vec::new();
+        let mut unreadable_ids = Vec::new();
+        for item in encrypted_items {
+            match try_decrypt(&item.content_type, &item.content) {
+                Some(plaintext) => readable.push((item, plaintext)),
+                None => unreadable_ids.push(item.id.clone()),
+            }
+        }
+        
+        let undecryptable = unreadable_ids.len() as u64;
+        if !confirm {
+            return Ok(EncryptionResetResult {
+                undecryptable,
+                discarded: 0,
+                reencrypted: 0,
+                rekeyed: false,
+            });
+        }
+        
+        // 其他格式跟随所属项目：项目会被删除时不处理，无法解密的格式直接丢弃
+        let mut formats = Vec::new();
+        for format in encrypted_formats {
+            if unreadable_ids.contains(&format.item_id) {
+                continue;
+            }
+            formats.push((try_decrypt(&format.content_type, &format.content), format));
+        }
+        
+        let cipher = SettingsService::get_cipher(pool).await?;
+        let new_key = EncryptionRepository::generate(user_id);
+        let owner = user_id.to_string();
+        let reencrypted_ids: Vec<String> = readable.iter().map(|(item, _)| item.id.clone()).collect();
+        
+        let discarded = db::with_transaction(pool, move |conn| Box::pin(async move {
+            EncryptionRepository::replace_for_user(&mut *conn, &new_key).await?;
+            EncryptionRepository::delete_wrapped_for_user(&mut *conn, &owner).await?;
+            
+            for (mut item, plaintext) in readable {
+                item.content = Self::encrypt_with_key(cipher, &new_key.key_data, &item.content_type, &plaintext)?;
+                ClipboardRepository::set_content(&mut *conn, &item).await?;
+            }
+            
+            for (plaintext, mut format) in formats {
+                match plaintext {
+                    Some(plaintext) => {
+                        format.content = Self::encrypt_with_key(cipher, &new_key.key_data, &format.content_type, &plaintext)?;
+                        ItemFormatRepository::set_content(&mut *conn, &format).await?;
+                    }
+                    None => ItemFormatRepository::delete_format(&mut *conn, &format.item_id, &format.content_type).await?,
+                }
+            }
+            
+            // 明文哈希由旧密钥派生，清空后合并重复项目时按新密钥重新计算
+            ClipboardRepository::clear_content_hashes(&mut *conn, &owner).await?;
+            ClipboardRepository::delete_many_in(&mut *conn, &unreadable_ids, &owner).await
+        })).await?;
+        
+        // 不再使用缓存的旧密钥
+        KeyCache::invalidate_user(pool, user_id);
+        
+        // 重新加密的项目需要重新推送
+        for id in &reencrypted_ids {
+            sync::mark_item_unsynced(pool, id).await?;
+        }
+        
+        // 搜索索引同样由密钥派生
+        if SearchIndexService::is_enabled(pool, user_id).await? {
+            SearchIndexService::rebuild_for_user(pool, user_id).await?;
+        }
+        
+        Ok(EncryptionResetResult {
+            undecryptable,
+            discarded,
+            reencrypted: reencrypted_ids.len() as u64,
+            rekeyed: true,
+        })
+    }
+    
+    // 将一个用户的全部项目转移给另一个用户（账号合并），返回转移的数量
+    // 加密项目在同一事务中用目标用户的密钥重新加密
+    pub async fn reassign_items(pool: &SqlitePool, from_user_id: &str, to_user_id: &str) -> Result<u64, AppError> {
+        if from_user_id == to_user_id {
+            return Err(AppError::InvalidData("不能将项目转移给同一用户".to_string()));
+        }
+        
+        // 事务开始前准备双方密钥，目标用户没有密钥时创建
+        let cipher = SettingsService::get_cipher(pool).await?;
+        let from_key = EncryptionRepository::find_by_user_id(pool, from_user_id).await?;
+        let to_key = match EncryptionRepository::find_by_user_id(pool, to_user_id).await? {
+            Some(key) => key,
+            None => EncryptionRepository::create_for_user(pool, to_user_id).await?,
+        };
+        
+        let from_user_id = from_user_id.to_string();
+        let to_user_id = to_user_id.to_string();
+        let target_user_id = to_user_id.clone();
+        
+        let moved = db::with_transaction(pool, move |conn| Box::pin(async move {
+            let encrypted_items = ClipboardRepository::find_encrypted_by_user_id(&mut *conn, &from_user_id).await?;
+            
+            if !encrypted_items.is_empty() {
+                let from_key = from_key
+                    .ok_or_else(|| AppError::KeyUnavailable("加密密钥不存在".to_string()))?;
+                
+                for mut item in encrypted_items {
+                    let plaintext = Self::decrypt_with_key(&from_key.key_data, &item.content_type, &item.content)?;
+                    item.content = Self::encrypt_with_key(cipher, &to_key.key_data, &item.content_type, &plaintext)?;
+                    ClipboardRepository::set_content(&mut *conn, &item).await?;
+                }
+                
+                for mut format in ItemFormatRepository::find_encrypted_by_user_id(&mut *conn, &from_user_id).await? {
+                    let plaintext = Self::decrypt_with_key(&from_key.key_data, &format.content_type, &format.content)?;
+                    format.content = Self::encrypt_with_key(cipher, &to_key.key_data, &format.content_type, &plaintext)?;
+                    ItemFormatRepository::set_content(&mut *conn, &format).await?;
+                }
+            }
+            
+            ClipboardRepository::reassign_items(&mut *conn, &from_user_id, &to_user_id).await
+        })).await?;
+        
+        // 目标用户开启了加密搜索时为转入的项目建立索引
+        if moved > 0 && SearchIndexService::is_enabled(pool, &target_user_id).await? {
+            SearchIndexService::rebuild_for_user(pool, &target_user_id).await?;
+        }
+        
+        Ok(moved)
+    }
+    
+    // 将单个项目转移给同一设备上登录的另一个账号，加密项目在同一事务中用目标用户的密钥重新加密
+    pub async fn move_item_to_account(
+        pool: &SqlitePool,
+        from_user_id: &str,
+        to_user_id: &str,
+        id: &str
+    ) -> Result<(), AppError> {
+        if from_user_id == to_user_id {
+            return Err(AppError::InvalidData("不能将项目转移给同一用户".to_string()));
+        }
+    
+        let mut item = ClipboardRepository::find_by_id(pool, id, from_user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        let plaintext = Self::decrypt_item(pool, from_user_id, &item).await?;
+    
+        // 事务开始前准备密钥和重新加密后的内容，目标用户没有密钥时创建
+        let mut formats = Vec::new();
+        if item.encrypted {
+            let cipher = SettingsService::get_cipher(pool).await?;
+            let to_key = match EncryptionRepository::find_by_user_id(pool, to_user_id).await? {
+                Some(key) => key,
+                None => EncryptionRepository::create_for_user(pool, to_user_id).await?,
+            };
+    
+            item.content = Self::encrypt_with_key(cipher, &to_key.key_data, &item.content_type, &plaintext)?;
+            for mut format in ItemFormatRepository::find_by_item_id(pool, id).await? {
+                let format_plaintext = Self::decrypt_content(pool, from_user_id, &format.content_type, &format.content).await?;
+                format.content = Self::encrypt_with_key(cipher, &to_key.key_data, &format.content_type, &format_plaintext)?;
+                formats.push(format);
+            }
+        }
+    
+        let from_user_id = from_user_id.to_string();
+        let to_user_id = to_user_id.to_string();
+        let target_user_id = to_user_id.clone();
+        let moved_item = item.clone();
+    
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            if !ClipboardRepository::reassign_item_in(&mut *conn, &item.id, &from_user_id, &to_user_id).await? {
+                return Err(AppError::NotFound("剪贴板项目不存在".to_string()));
+            }
+    
+            if item.encrypted {
+                ClipboardRepository::set_content(&mut *conn, &item).await?;
+                for format in &formats {
+                    ItemFormatRepository::set_content(&mut *conn, format).await?;
+                }
+            }
+    
+            Ok(())
+        })).await?;
+    
+        // 目标用户开启了加密搜索时为转入的项目建立索引
+        SearchIndexService::index_item(pool, &target_user_id, &moved_item, &plaintext).await
+    }
+    
+    // 为明文图片项目生成并保存缩略图；缩略图以明文保存，加密项目不生成
+    // 无法解码的图片和不需要缩小的小图片跳过，列表中直接使用原图
+    async fn store_thumbnail(pool: &SqlitePool, item: &mut ClipboardItem) -> Result<(), AppError> {
+        if let Some(data) = Self::generate_thumbnail(item) {
+            ThumbnailRepository::save(pool, &item.id, &data).await?;
+            item.thumbnail = Some(encoding::encode(data));
+        }
+        Ok(())
+    }
+    
+    // 为明文图片生成缩略图，加密项目、非图片或无法解码的内容返回 None
+    fn generate_thumbnail(item: &ClipboardItem) -> Option<Vec<u8>> {
+        if item.encrypted || !ContentType::from_mime(&item.content_type).is_binary() {
+            return None;
+        }
+        let bytes = encoding::decode(&item.content).ok()?;
+        
+        thumbnail::generate(&bytes, THUMBNAIL_MAX_SIZE)
+    }
+    
+    // 同步写入的项目与 add_item 一样刷新缩略图、搜索索引和明文哈希
+    // 无法解密（密钥不可用或密文损坏）时移除旧索引，哈希保持为空，之后由合并重复项目补算
+    pub(crate) async fn refresh_synced_item(pool: &SqlitePool, item: &mut ClipboardItem) -> Result<(), AppError> {
+        let mut conn = pool.acquire()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        ThumbnailRepository::delete_by_item_id(&mut conn, &item.id).await?;
+        drop(conn);
+        Self::store_thumbnail(pool, item).await?;
+        
+        let plaintext = match Self::decrypt_item(pool, &item.user_id, item).await {
+            Ok(plaintext) => plaintext,
+            Err(AppError::DecryptionFailed(_)) | Err(AppError::KeyUnavailable(_)) => {
+                let mut conn = pool.acquire()
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                return SearchIndexRepository::remove(&mut conn, &item.id).await;
+            }
+            Err(e) => return Err(e),
+        };
+        SearchIndexService::index_item(pool, &item.user_id, item, &plaintext).await?;
+        let content_hash = Self::content_hash(pool, &item.user_id, &plaintext).await?;
+        ClipboardRepository::set_content_hash(pool, &item.id, content_hash.as_deref()).await
+    }
+    
+    // 附上已保存的缩略图
+    async fn with_thumbnails(pool: &SqlitePool, items: Vec<ClipboardItem>) -> Result<Vec<ClipboardItem>, AppError> {
+        let ids: Vec<String> = items
+            .iter()
+            .filter(|item| ContentType::from_mime(&item.content_type).is_binary())
+            .map(|item| item.id.clone())
+            .collect();
+        if ids.is_empty() {
+            return Ok(items);
+        }
+        
+        let mut thumbnails = ThumbnailRepository::find_by_item_ids(pool, &ids).await?;
+        Ok(items
+            .into_iter()
+            .map(|mut item| {
+                item.thumbnail = thumbnails.remove(&item.id).map(encoding::encode);
+                item
+            })
+            .collect())
+    }
+    
+    // 为明文的文本项目生成预览，加密项目和图片等二进制内容不生成
+    fn with_previews(items: Vec<ClipboardItem>, lengths: &PreviewLengths) -> Vec<ClipboardItem> {
+        items
+            .into_iter()
+            .map(|mut item| {
+                if !item.encrypted && !ContentType::from_mime(&item.content_type).is_binary() {
+                    let max_chars = lengths.for_content_type(&item.content_type);
+                    item.preview = Some(text::truncate_chars(&item.content, max_chars));
+                }
+                item
+            })
+            .collect()
+    }
+    
+    // 按脱敏规则替换明文文本项目的内容；加密和二进制项目的 content 不是明文，不处理
+    // 已保存的规则无法编译时跳过该条
+    fn redact(items: Vec<ClipboardItem>, patterns: &[RedactionPattern]) -> Vec<ClipboardItem> {
+        let rules: Vec<_> = patterns
+            .iter()
+            .filter_map(|pattern| pattern.compile().ok().map(|regex| (regex, pattern.replacement.as_str())))
+            .collect();
+        if rules.is_empty() {
+            return items;
+        }
+        
+        items
+            .into_iter()
+            .map(|mut item| {
+                if !item.encrypted && !ContentType::from_mime(&item.content_type).is_binary() {
+                    for (regex, replacement) in &rules {
+                        item.content = regex.replace_all(&item.content, *replacement).into_owned();
+                    }
+                    // 清理前的原始内容同样包含完整值，列表中不返回
+                    item.raw_content = None;
+                }
+                item
+            })
+            .collect()
+    }
+    
+    // 逐个尝试解密加密项目：用户没有密钥或密文无法用当前密钥解密时，
+    // 内容替换为占位符并标记 decrypt_error，由前端提示修复加密
+    async fn mark_undecryptable(
+        pool: &SqlitePool,
+        user_id: &str,
+        items: Vec<ClipboardItem>
+    ) -> Result<Vec<ClipboardItem>, AppError> {
+        if !items.iter().any(|item| item.encrypted) {
+            return Ok(items);
+        }
+        let key = KeyCache::get_key(pool, user_id).await?;
+        
+        Ok(items
+            .into_iter()
+            .map(|mut item| {
+                let readable = key
+                    .as_ref()
+                    .is_some_and(|key| Self::decrypt_with_key(&key.key_data, &item.content_type, &item.content).is_ok());
+                if item.encrypted && !readable {
+                    item.content = UNDECRYPTABLE_PLACEHOLDER.to_string();
+                    item.raw_content = None;
+                    item.decrypt_error = true;
+                }
+                item
+            })
+            .collect())
+    }
+    
+    // 将敏感项目的内容替换为占位符
+    fn mask_sensitive(items: Vec<ClipboardItem>) -> Vec<ClipboardItem> {
+        items
+            .into_iter()
+            .map(|mut item| {
+                if item.is_sensitive {
+                    item.content = SENSITIVE_PLACEHOLDER.to_string();
+                    item.raw_content = None;
+                    item.thumbnail = None;
+                }
+                item
+            })
+            .collect()
+    }
+    
+    // 计算明文内容的 HMAC 哈希（十六进制），密钥由用户的加密密钥派生，数据库中只保存哈希
+    // 用户还没有加密密钥时返回 None，不在这里创建密钥：密钥只在注册和轮换时生成
+    pub async fn content_hash(pool: &SqlitePool, user_id: &str, plaintext: &str) -> Result<Option<String>, AppError> {
+        let hash_key = Self::content_hash_key(pool, user_id).await?;
+        Ok(hash_key.map(|hash_key| Self::hash_with_key(&hash_key, plaintext)))
+    }
+    
+    // 由用户的加密密钥派生哈希密钥，没有加密密钥时返回 None
+    async fn content_hash_key(pool: &SqlitePool, user_id: &str) -> Result<Option<Vec<u8>>, AppError> {
+        let encryption_key = KeyCache::get_key(pool, user_id).await?;
+        Ok(encryption_key.map(|key| crypto::hmac_sha256(&key.key_data, CONTENT_HASH_CONTEXT)))
+    }
+    
+    fn hash_with_key(hash_key: &[u8], plaintext: &str) -> String {
+        crypto::hmac_sha256(hash_key, plaintext.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+    
+    // 使用用户密钥加密内容，返回 base64(nonce + 密文)
+    pub(crate) async fn encrypt_content(
+        pool: &SqlitePool,
+        user_id: &str,
+        content_type: &str,
+        content: &str
+    ) -> Result<String, AppError> {
+        // 获取用户的加密密钥
+        let encryption_key = KeyCache::get_key(pool, user_id).await?
+            .ok_or_else(|| AppError::KeyUnavailable("加密密钥不存在".to_string()))?;
+        
+        let cipher = SettingsService::get_cipher(pool).await?;
+        
+        Self::encrypt_with_key(cipher, &encryption_key.key_data, content_type, content)
+    }
+    
+    // 图片等二进制内容先解码 base64 再加密，密文中不再多保存一层编码
+    fn encrypt_with_key(
+        cipher: AeadCipher,
+        key_data: &[u8],
+        content_type: &str,
+        content: &str
+    ) -> Result<String, AppError> {
+        let plaintext = match ContentType::from_mime(content_type).is_binary() {
+            true => encoding::decode(content).unwrap_or_else(|_| content.as_bytes().to_vec()),
+            false => content.as_bytes().to_vec(),
+        };
+        
+        // 加密内容
+        let nonce = crypto::generate_nonce();
+        let encrypted_data = crypto::encrypt_data_with(
+            cipher,
+            &plaintext,
+            key_data,
+            &nonce
+        ).map_err(AppError::CryptoError)?;
+        
+        // 将加密后的数据和nonce一起存储
+        let combined = [&nonce[..], &encrypted_data[..]].concat();
+        Ok(Base64Content::from_bytes(combined).encode())
     }
     
     // 解密剪贴板项目
@@ -122,17 +1191,26 @@ impl ClipboardService {
             return Ok(item.content.clone());
         }
         
+        Self::decrypt_content(pool, user_id, &item.content_type, &item.content).await
+    }
+    
+    // 使用用户密钥解密 base64(nonce + 密文)
+    pub(crate) async fn decrypt_content(
+        pool: &SqlitePool,
+        user_id: &str,
+        content_type: &str,
+        content: &str
+    ) -> Result<String, AppError> {
         // 获取用户的加密密钥
-        let encryption_key = EncryptionRepository::find_by_user_id(pool, user_id).await?
-            .ok_or_else(|| AppError::NotFound("加密密钥不存在".to_string()))?;
-        
-        // 解码base64
-        let combined = base64::decode(&item.content)
-            .map_err(|e| AppError::CryptoError(e.to_string()))?;
+        let encryption_key = KeyCache::get_key(pool, user_id).await?
+            .ok_or_else(|| AppError::KeyUnavailable("加密密钥不存在".to_string()))?;
         
-        if combined.len() < 12 {
-            return Err(AppError::InvalidData("无效的加密数据".to_string()));
-        }
+        Self::decrypt_with_key(&encryption_key.key_data, content_type, content)
+    }
+    
+    fn decrypt_with_key(key_data: &[u8], content_type: &str, content: &str) -> Result<String, AppError> {
+        // 加密项目的内容必须是 base64(nonce + 密文)，格式错误时给出具体原因
+        let combined = Base64Content::parse_ciphertext(content, 12)?.into_bytes();
         
         // 分离nonce和加密数据
         let nonce = &combined[0..12];
@@ -141,13 +1219,27 @@ impl ClipboardService {
         let mut nonce_array = [0u8; 12];
         nonce_array.copy_from_slice(nonce);
         
-        // 解密数据
-        let decrypted = crypto::decrypt_data(
+        // 二进制内容解密为原始字节，不要求是有效的 UTF-8
+        if ContentType::from_mime(content_type).is_binary() {
+            let decrypted = crypto::decrypt_data_bytes(
+                encrypted_data,
+                key_data,
+                &nonce_array
+            ).map_err(AppError::DecryptionFailed)?;
+            
+            // 旧版本加密的图片解密后是 base64 文本，原样返回；否则为原始字节，编码为 base64
+            return Ok(match String::from_utf8(decrypted) {
+                Ok(text) if encoding::decode(&text).is_ok() => text,
+                Ok(text) => encoding::encode(text.into_bytes()),
+                Err(e) => encoding::encode(e.into_bytes()),
+            });
+        }
+        
+        // 文本内容解密结果必须是有效的 UTF-8
+        crypto::decrypt_data(
             encrypted_data,
-            &encryption_key.key_data,
+            key_data,
             &nonce_array
-        ).map_err(|e| AppError::CryptoError(e))?;
-        
-        Ok(decrypted)
+        ).map_err(AppError::DecryptionFailed)
     }
 }
\ No newline at end of file
@@ -0,0 +1,164 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_tungstenite::connect_async;
+use crate::entity::connection_test::{ConnectionTestResult, SmtpConfig, SmtpSecurity};
+use crate::service::sync_service::SyncService;
+use crate::util::encoding;
+
+// 单次连接测试的超时时间（秒）
+pub const CONNECTION_TEST_TIMEOUT_SECS: u64 = 10;
+
+// SMTP 会话：按行读取应答，多行应答以 "250-" 形式续行
+struct SmtpSession<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SmtpSession<S> {
+    fn new(stream: S) -> Self {
+        Self { stream: BufReader::new(stream) }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    // 读取一条完整应答，返回应答码和最后一行的文本
+    async fn read_reply(&mut self) -> Result<(u16, String), String> {
+        loop {
+            let mut line = String::new();
+            let read = self.stream.read_line(&mut line)
+                .await
+                .map_err(|e| format!("读取 SMTP 应答失败: {}", e))?;
+            if read == 0 {
+                return Err("SMTP 服务器关闭了连接".to_string());
+            }
+
+            let line = line.trim_end();
+            let code = line.get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| format!("无法识别的 SMTP 应答: {}", line))?;
+
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok((code, line.get(4..).unwrap_or("").to_string()));
+            }
+        }
+    }
+
+    async fn expect(&mut self, expected: u16) -> Result<(), String> {
+        let (code, text) = self.read_reply().await?;
+        if code != expected {
+            return Err(format!("SMTP 服务器返回 {}: {}", code, text));
+        }
+        Ok(())
+    }
+
+    async fn command(&mut self, line: &str, expected: u16) -> Result<(), String> {
+        self.stream.get_mut()
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .await
+            .map_err(|e| format!("发送 SMTP 命令失败: {}", e))?;
+        self.expect(expected).await
+    }
+
+    // EHLO 之后按需认证并退出，不发送任何邮件
+    async fn finish(&mut self, config: &SmtpConfig) -> Result<(), String> {
+        self.command("EHLO sharing-copyboard", 250).await?;
+
+        if let Some(username) = &config.username {
+            let password = config.password.as_deref().unwrap_or("");
+            let credentials = encoding::encode(format!("\0{}\0{}", username, password));
+            self.command(&format!("AUTH PLAIN {}", credentials), 235)
+                .await
+                .map_err(|e| format!("SMTP 认证失败: {}", e))?;
+        }
+
+        self.command("QUIT", 221).await
+    }
+}
+
+pub struct ConnectivityService;
+
+impl ConnectivityService {
+    // 测试 SMTP 配置：建立连接并认证，不发送邮件也不保存配置
+    pub async fn test_smtp(config: &SmtpConfig, timeout: Duration) -> ConnectionTestResult {
+        Self::measure(timeout, Self::smtp_handshake(config)).await
+    }
+
+    // 测试同步服务器：建立 WebSocket 连接后立即关闭
+    pub async fn test_sync_server(server_url: &str, timeout: Duration) -> ConnectionTestResult {
+        Self::measure(timeout, Self::websocket_handshake(server_url)).await
+    }
+
+    async fn measure(
+        timeout: Duration,
+        probe: impl Future<Output = Result<(), String>>
+    ) -> ConnectionTestResult {
+        let started = Instant::now();
+        let result = match tokio::time::timeout(timeout, probe).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("连接超时（{} 毫秒）", timeout.as_millis())),
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        ConnectionTestResult {
+            success: result.is_ok(),
+            latency_ms,
+            error: result.err(),
+        }
+    }
+
+    async fn smtp_handshake(config: &SmtpConfig) -> Result<(), String> {
+        let address = format!("{}:{}", config.host, config.port);
+        let tcp = TcpStream::connect(&address)
+            .await
+            .map_err(|e| format!("无法连接到 {}: {}", address, e))?;
+
+        match config.security {
+            SmtpSecurity::None => {
+                let mut session = SmtpSession::new(tcp);
+                session.expect(220).await?;
+                session.finish(config).await
+            }
+            SmtpSecurity::Tls => {
+                let tls = Self::tls_connect(&config.host, tcp).await?;
+                let mut session = SmtpSession::new(tls);
+                session.expect(220).await?;
+                session.finish(config).await
+            }
+            SmtpSecurity::StartTls => {
+                let mut session = SmtpSession::new(tcp);
+                session.expect(220).await?;
+                session.command("EHLO sharing-copyboard", 250).await?;
+                session.command("STARTTLS", 220).await?;
+
+                let tls = Self::tls_connect(&config.host, session.into_inner()).await?;
+                SmtpSession::new(tls).finish(config).await
+            }
+        }
+    }
+
+    async fn tls_connect(
+        host: &str,
+        tcp: TcpStream
+    ) -> Result<tokio_native_tls::TlsStream<TcpStream>, String> {
+        let connector = native_tls::TlsConnector::new()
+            .map_err(|e| format!("Failed to build TLS connector: {}", e))?;
+        tokio_native_tls::TlsConnector::from(connector)
+            .connect(host, tcp)
+            .await
+            .map_err(|e| format!("TLS 握手失败: {}", e))
+    }
+
+    async fn websocket_handshake(server_url: &str) -> Result<(), String> {
+        let url = SyncService::parse_server_url(server_url).map_err(|e| e.to_string())?;
+
+        let (mut ws_stream, _) = connect_async(url)
+            .await
+            .map_err(|e| format!("无法连接到同步服务器: {}", e))?;
+        let _ = ws_stream.close(None).await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
+use crate::error::AppError;
+use crate::repository::encryption_repository::{EncryptionKey, EncryptionRepository};
+
+// 用户加密密钥的进程内缓存，加解密时不必每次查询数据库
+// 条目按连接池区分：保存连接配置的弱引用，连接池释放后条目不再匹配，下次写入时清理
+struct CachedKey {
+    pool: Weak<SqliteConnectOptions>,
+    key: EncryptionKey,
+}
+
+struct CacheInner {
+    entries: Vec<CachedKey>,
+    // 每次失效时递增，读取数据库期间发生失效的结果不写入缓存
+    generation: u64,
+}
+
+fn cache() -> &'static Mutex<CacheInner> {
+    static CACHE: OnceLock<Mutex<CacheInner>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(CacheInner { entries: Vec::new(), generation: 0 }))
+}
+
+fn same_pool(entry: &CachedKey, options: &Arc<SqliteConnectOptions>) -> bool {
+    Weak::ptr_eq(&entry.pool, &Arc::downgrade(options))
+}
+
+pub struct KeyCache;
+
+impl KeyCache {
+    // 获取用户的加密密钥，未命中时从数据库读取并缓存；没有密钥时不缓存
+    pub async fn get_key(pool: &SqlitePool, user_id: &str) -> Result<Option<EncryptionKey>, AppError> {
+        let options = pool.connect_options();
+
+        let generation = {
+            let inner = cache().lock().unwrap();
+            let cached = inner
+                .entries
+                .iter()
+                .find(|entry| entry.key.user_id == user_id && same_pool(entry, &options));
+            if let Some(entry) = cached {
+                return Ok(Some(entry.key.clone()));
+            }
+            inner.generation
+        };
+
+        let key = EncryptionRepository::find_by_user_id(pool, user_id).await?;
+
+        if let Some(key) = &key {
+            let mut inner = cache().lock().unwrap();
+            if inner.generation == generation {
+                inner.entries.retain(|entry| entry.pool.strong_count() > 0);
+                inner.entries.push(CachedKey { pool: Arc::downgrade(&options), key: key.clone() });
+            }
+        }
+
+        Ok(key)
+    }
+
+    // 密钥更换、修改或重置密码后调用，之后的读取重新查询数据库
+    pub fn invalidate_user(pool: &SqlitePool, user_id: &str) {
+        let options = pool.connect_options();
+        let mut inner = cache().lock().unwrap();
+        inner.generation += 1;
+        inner.entries.retain(|entry| !(entry.key.user_id == user_id && same_pool(entry, &options)));
+    }
+}
@@ -0,0 +1,90 @@
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::AppError;
+use crate::repository::encryption_repository::{EncryptionRepository, WrappedKey, KEY_LENGTH, NONCE_LENGTH};
+use crate::util::crypto;
+use crate::util::db;
+use crate::util::encoding::{self, Base64Content};
+
+pub struct KeyProvisionService;
+
+impl KeyProvisionService {
+    // 为新绑定的设备包装用户密钥，包装密钥由双方共享的配对口令派生
+    // 相同的幂等键重复调用时返回第一次保存的结果，连接中断后新设备可以直接重试
+    pub async fn provision_key_to_device(
+        pool: &SqlitePool,
+        user_id: &str,
+        device_id: &str,
+        idempotency_key: &str,
+        pairing_secret: &str
+    ) -> Result<WrappedKey, AppError> {
+        if device_id.is_empty() || idempotency_key.is_empty() {
+            return Err(AppError::InvalidData("设备 ID 和幂等键不能为空".to_string()));
+        }
+        if pairing_secret.is_empty() {
+            return Err(AppError::InvalidData("配对口令不能为空".to_string()));
+        }
+
+        // 用户还没有密钥时创建，保证新设备总能拿到密钥
+        let key = match EncryptionRepository::find_by_user_id(pool, user_id).await? {
+            Some(key) => key,
+            None => EncryptionRepository::create_for_user(pool, user_id).await?,
+        };
+
+        // Argon2 派生较慢，在事务外完成
+        let salt = crypto::generate_salt();
+        let wrapping_key = crypto::derive_key_from_passphrase(pairing_secret, &salt)
+            .map_err(AppError::CryptoError)?;
+        let nonce = crypto::generate_nonce();
+        let encrypted = crypto::encrypt_data(&key.key_data, &wrapping_key, &nonce)
+            .map_err(AppError::CryptoError)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let candidate = WrappedKey {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            idempotency_key: idempotency_key.to_string(),
+            wrapped_key: encoding::encode([&nonce[..], &encrypted[..]].concat()),
+            salt: encoding::encode(salt),
+            created_at: now,
+        };
+
+        // 查询与保存在同一事务中完成，并发重试也只会保存一份
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            if let Some(existing) = EncryptionRepository::find_wrapped_by_idempotency_key(&mut *conn, &candidate.idempotency_key).await? {
+                if existing.user_id != candidate.user_id || existing.device_id != candidate.device_id {
+                    return Err(AppError::InvalidData("幂等键已用于其他设备".to_string()));
+                }
+                return Ok(existing);
+            }
+
+            EncryptionRepository::save_wrapped(&mut *conn, &candidate).await?;
+            Ok(candidate)
+        })).await
+    }
+
+    // 新设备使用配对口令解开包装的密钥
+    pub fn unwrap_key(wrapped: &WrappedKey, pairing_secret: &str) -> Result<Vec<u8>, AppError> {
+        let salt = encoding::decode(&wrapped.salt)
+            .map_err(|e| AppError::DecryptionFailed(e.to_string()))?;
+        let combined = Base64Content::parse_ciphertext(&wrapped.wrapped_key, NONCE_LENGTH)?.into_bytes();
+
+        let wrapping_key = crypto::derive_key_from_passphrase(pairing_secret, &salt)
+            .map_err(AppError::CryptoError)?;
+        let mut nonce = [0u8; NONCE_LENGTH];
+        nonce.copy_from_slice(&combined[..NONCE_LENGTH]);
+
+        let key_data = crypto::decrypt_data_bytes(&combined[NONCE_LENGTH..], &wrapping_key, &nonce)
+            .map_err(AppError::DecryptionFailed)?;
+
+        if key_data.len() != KEY_LENGTH {
+            return Err(AppError::DecryptionFailed("解开的密钥长度错误".to_string()));
+        }
+
+        Ok(key_data)
+    }
+}
@@ -0,0 +1,110 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::entity::mail::{MailQueueStatus, QueuedMail, MAIL_STATUS_PENDING};
+use crate::error::AppError;
+use crate::repository::mail_repository::MailRepository;
+
+// 最大发送次数，超过后转为死信
+pub const MAX_MAIL_ATTEMPTS: i64 = 5;
+// 重试退避基数与上限（秒）
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 60 * 60;
+// 后台发送轮询间隔（秒）与单次批量大小
+const WORKER_POLL_SECS: u64 = 5;
+const WORKER_BATCH_SIZE: i64 = 20;
+
+// 邮件发送器，实际的 SMTP 实现接入时只需实现该 trait
+// 只在本 crate 内使用，不需要约束 Future 为 Send
+#[allow(async_fn_in_trait)]
+pub trait MailSender {
+    async fn send(&self, mail: &QueuedMail) -> Result<(), String>;
+}
+
+// 开发阶段使用的发送器：只打印邮件内容
+pub struct LogMailSender;
+
+impl MailSender for LogMailSender {
+    async fn send(&self, mail: &QueuedMail) -> Result<(), String> {
+        println!("发送邮件至 {}: {}\n{}", mail.recipient, mail.subject, mail.body);
+        Ok(())
+    }
+}
+
+pub struct MailService;
+
+impl MailService {
+    // 将邮件加入发送队列，立即返回
+    pub async fn enqueue(pool: &SqlitePool, recipient: &str, subject: &str, body: &str) -> Result<QueuedMail, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let mail = QueuedMail {
+            id: Uuid::new_v4().to_string(),
+            recipient: recipient.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            status: MAIL_STATUS_PENDING.to_string(),
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        
+        MailRepository::save(pool, &mail).await?;
+        
+        Ok(mail)
+    }
+    
+    // 发送所有到期的邮件，返回成功发送的数量
+    pub async fn process_due<S: MailSender>(pool: &SqlitePool, sender: &S, now: i64) -> Result<usize, AppError> {
+        let mails = MailRepository::find_due(pool, now, WORKER_BATCH_SIZE).await?;
+        let mut sent = 0;
+        
+        for mail in mails {
+            match sender.send(&mail).await {
+                Ok(()) => {
+                    MailRepository::mark_sent(pool, &mail.id, now).await?;
+                    sent += 1;
+                }
+                Err(e) => {
+                    let attempts = mail.attempts + 1;
+                    // 指数退避，超过最大次数后不再重试
+                    let next_attempt_at = if attempts >= MAX_MAIL_ATTEMPTS {
+                        None
+                    } else {
+                        let delay = (RETRY_BASE_SECS << (attempts - 1)).min(RETRY_MAX_SECS);
+                        Some(now + delay)
+                    };
+                    eprintln!("发送邮件失败 ({}/{}): {}", attempts, MAX_MAIL_ATTEMPTS, e);
+                    MailRepository::mark_failed(pool, &mail.id, &e, next_attempt_at, now).await?;
+                }
+            }
+        }
+        
+        Ok(sent)
+    }
+    
+    // 后台发送循环
+    pub async fn run_worker<S: MailSender>(pool: SqlitePool, sender: S) {
+        loop {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            
+            if let Err(e) = Self::process_due(&pool, &sender, now).await {
+                eprintln!("处理邮件队列失败: {:?}", e);
+            }
+            
+            tokio::time::sleep(tokio::time::Duration::from_secs(WORKER_POLL_SECS)).await;
+        }
+    }
+    
+    pub async fn get_queue_status(pool: &SqlitePool) -> Result<MailQueueStatus, AppError> {
+        MailRepository::count_by_status(pool).await
+    }
+}
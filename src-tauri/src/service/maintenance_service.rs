@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::entity::content_type::{ContentType, FALLBACK_CONTENT_TYPE};
+use crate::error::AppError;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::init::{CONTENT_TYPE_MIGRATION_VERSION, SCHEMA_VERSION_KEY};
+use crate::repository::settings_repository::SettingsRepository;
+use crate::repository::share_repository::ShareRepository;
+use crate::repository::snapshot_repository::SnapshotRepository;
+
+// 设置项：上次压缩数据库的时间
+pub const LAST_COMPACTION_KEY: &str = "last_compaction_at";
+// 设置项：启动时是否已检查过外键孤儿记录
+pub const ORPHANS_CHECKED_KEY: &str = "orphans_checked";
+
+// 删除孤儿记录后可能产生新的孤儿（外键未启用时不会级联删除），最多重复检查的轮数
+const MAX_ORPHAN_PASSES: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompactionResult {
+    pub bytes_before: i64,
+    pub bytes_after: i64,
+    pub bytes_reclaimed: i64,
+    pub compacted_at: i64,
+}
+
+// 孤儿记录（引用的用户或项目已不存在）的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanPolicy {
+    // 删除孤儿记录
+    #[default]
+    Delete,
+    // 只统计，不修改
+    Report,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OrphanCount {
+    pub table: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanReport {
+    pub policy: OrphanPolicy,
+    pub orphans: Vec<OrphanCount>, // 按表名排序
+    pub deleted: u64,
+}
+
+// 一种 content_type 的规范化结果；defaulted 为 true 表示无法识别，改为默认类型
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContentTypeChange {
+    pub from: String,
+    pub to: String,
+    pub count: u64,
+    pub defaulted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ContentTypeReport {
+    pub changed: u64, // 修改的项目总数
+    pub defaulted: u64, // 其中改为默认类型的项目数
+    pub changes: Vec<ContentTypeChange>, // 按原类型排序
+}
+
+pub struct MaintenanceService;
+
+impl MaintenanceService {
+    // 执行 WAL 检查点和 VACUUM；VACUUM 不能在事务中执行，调用方需暂停其他写入
+    pub async fn compact(pool: &SqlitePool) -> Result<CompactionResult, AppError> {
+        // 先清理过期分享和快照，释放的空间由 VACUUM 回收
+        Self::prune_expired_shares(pool).await?;
+        Self::prune_expired_snapshots(pool).await?;
+        
+        let bytes_before = Self::database_size(pool).await?;
+        
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        sqlx::query("VACUUM")
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let bytes_after = Self::database_size(pool).await?;
+        
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        SettingsRepository::set(pool, LAST_COMPACTION_KEY, &now.to_string()).await?;
+        
+        Ok(CompactionResult {
+            bytes_before,
+            bytes_after,
+            bytes_reclaimed: (bytes_before - bytes_after).max(0),
+            compacted_at: now,
+        })
+    }
+    
+    pub async fn last_compaction_at(pool: &SqlitePool) -> Result<Option<i64>, AppError> {
+        let value = SettingsRepository::get(pool, LAST_COMPACTION_KEY).await?;
+        Ok(value.and_then(|v| v.parse::<i64>().ok()))
+    }
+    
+    // 清理已过期的分享记录，返回清理数量
+    pub async fn prune_expired_shares(pool: &SqlitePool) -> Result<u64, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        ShareRepository::prune_expired(pool, now).await
+    }
+    
+    // 清理已过期的快照，返回清理数量
+    pub async fn prune_expired_snapshots(pool: &SqlitePool) -> Result<u64, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        SnapshotRepository::prune_expired(pool, now).await
+    }
+    
+    // 按 PRAGMA foreign_key_check 查找孤儿记录，并按策略删除或只统计
+    pub async fn repair_orphans(pool: &SqlitePool, policy: OrphanPolicy) -> Result<OrphanReport, AppError> {
+        let mut counts: BTreeMap<String, i64> = BTreeMap::new();
+        let mut deleted = 0;
+        
+        for _ in 0..MAX_ORPHAN_PASSES {
+            let orphans = Self::foreign_key_check(pool).await?;
+            if orphans.is_empty() {
+                break;
+            }
+            
+            for (table, _) in &orphans {
+                *counts.entry(table.clone()).or_insert(0) += 1;
+            }
+            if policy == OrphanPolicy::Report {
+                break;
+            }
+            
+            let mut tx = pool.begin()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            for (table, rowid) in &orphans {
+                let result = sqlx::query(&format!("DELETE FROM \"{}\" WHERE rowid = ?", table.replace('"', "\"\"")))
+                    .bind(rowid)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                deleted += result.rows_affected();
+            }
+            tx.commit()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+        
+        Ok(OrphanReport {
+            policy,
+            orphans: counts.into_iter().map(|(table, count)| OrphanCount { table, count }).collect(),
+            deleted,
+        })
+    }
+    
+    // 启动时执行一次孤儿检查（外键约束启用前遗留的数据），之后不再重复
+    pub async fn repair_orphans_once(pool: &SqlitePool) -> Result<Option<OrphanReport>, AppError> {
+        if SettingsRepository::get_bool(pool, ORPHANS_CHECKED_KEY, false).await? {
+            return Ok(None);
+        }
+        
+        let report = Self::repair_orphans(pool, OrphanPolicy::Delete).await?;
+        SettingsRepository::set(pool, ORPHANS_CHECKED_KEY, "true").await?;
+        
+        Ok(Some(report))
+    }
+    
+    // 将项目中自由填写的 content_type 规范化为 MIME 类型，无法识别的改为默认类型
+    pub async fn normalize_content_types(pool: &SqlitePool) -> Result<ContentTypeReport, AppError> {
+        let mut tx = pool.begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let mut report = ContentTypeReport::default();
+        for (content_type, _) in ClipboardRepository::count_all_content_types_in(&mut tx).await? {
+            let (normalized, defaulted) = match ContentType::normalize_mime(&content_type) {
+                Some(normalized) => (normalized, false),
+                None => (FALLBACK_CONTENT_TYPE.to_string(), true),
+            };
+            if normalized == content_type {
+                continue;
+            }
+            
+            let count = ClipboardRepository::rename_content_type_in(&mut tx, &content_type, &normalized).await?;
+            report.changed += count;
+            if defaulted {
+                report.defaulted += count;
+            }
+            report.changes.push(ContentTypeChange {
+                from: content_type,
+                to: normalized,
+                count,
+                defaulted,
+            });
+        }
+        
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(report)
+    }
+    
+    // 启动时规范化一次旧数据，完成后更新结构版本，之后不再重复
+    pub async fn normalize_content_types_once(pool: &SqlitePool) -> Result<Option<ContentTypeReport>, AppError> {
+        if SettingsRepository::get_i64(pool, SCHEMA_VERSION_KEY, 0).await? >= CONTENT_TYPE_MIGRATION_VERSION {
+            return Ok(None);
+        }
+        
+        let report = Self::normalize_content_types(pool).await?;
+        SettingsRepository::set(pool, SCHEMA_VERSION_KEY, &CONTENT_TYPE_MIGRATION_VERSION.to_string()).await?;
+        
+        Ok(Some(report))
+    }
+    
+    // 违反外键约束的记录：(表名, rowid)
+    async fn foreign_key_check(pool: &SqlitePool) -> Result<Vec<(String, i64)>, AppError> {
+        let rows = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let rowid: Option<i64> = row.try_get(1).ok()?;
+                Some((row.try_get::<String, _>(0).ok()?, rowid?))
+            })
+            .collect())
+    }
+    
+    // 数据库文件大小（页数 × 页大小）
+    async fn database_size(pool: &SqlitePool) -> Result<i64, AppError> {
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(page_count * page_size)
+    }
+}
@@ -1,3 +1,22 @@
 pub mod user_service;
 pub mod auth_service;
-pub mod clipboard_service;
\ No newline at end of file
+pub mod clipboard_service;
+pub mod task_registry;
+pub mod sync_service;
+pub mod settings_service;
+pub mod mail_service;
+pub mod session_cache;
+pub mod key_cache;
+pub mod rate_limiter;
+pub mod app_lock_service;
+pub mod stats_service;
+pub mod backup_service;
+pub mod change_service;
+pub mod maintenance_service;
+pub mod search_index_service;
+pub mod connectivity_service;
+pub mod key_provision_service;
+pub mod share_service;
+pub mod snapshot_service;
+pub mod support_service;
+pub mod profile_service;
\ No newline at end of file
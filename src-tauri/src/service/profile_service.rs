@@ -0,0 +1,161 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::path::{Path, PathBuf};
+use crate::error::AppError;
+use crate::repository;
+
+// 数据库文件名，每个配置使用独立的文件
+pub const DB_FILE_NAME: &str = "sharing-copyboard.db";
+
+// 默认配置，数据库保存在数据目录下（与旧版本位置相同）
+pub const DEFAULT_PROFILE: &str = "default";
+
+// 其他配置保存在数据目录的 profiles/<名称>/ 下
+pub const PROFILES_DIR: &str = "profiles";
+
+// 记录上次切换到的配置，启动时没有指定配置时使用
+pub const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+// 启动参数与环境变量：--profile <名称> / --data-dir <目录>
+pub const PROFILE_ARG: &str = "--profile";
+pub const DATA_DIR_ARG: &str = "--data-dir";
+pub const PROFILE_ENV: &str = "SHARING_COPYBOARD_PROFILE";
+pub const DATA_DIR_ENV: &str = "SHARING_COPYBOARD_DATA_DIR";
+
+// 配置名称的最大长度
+pub const MAX_PROFILE_NAME_CHARS: usize = 64;
+
+// 当前使用的数据目录和配置；pinned 为 true 表示配置由启动参数或环境变量指定，不能在运行时切换
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileConfig {
+    pub data_dir: PathBuf,
+    pub name: String,
+    pub pinned: bool,
+}
+
+pub struct ProfileService;
+
+impl ProfileService {
+    // 解析启动参数和环境变量：参数优先于环境变量，都没有时使用上次切换到的配置
+    pub fn resolve<I>(args: I, env: impl Fn(&str) -> Option<String>) -> Result<ProfileConfig, AppError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let args: Vec<String> = args.into_iter().collect();
+        
+        let data_dir = Self::arg_value(&args, DATA_DIR_ARG)
+            .or_else(|| env(DATA_DIR_ENV))
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        
+        let pinned_name = Self::arg_value(&args, PROFILE_ARG)
+            .or_else(|| env(PROFILE_ENV))
+            .filter(|name| !name.is_empty());
+        
+        let (name, pinned) = match pinned_name {
+            Some(name) => (name, true),
+            None => (Self::read_active(&data_dir).unwrap_or_else(|| DEFAULT_PROFILE.to_string()), false),
+        };
+        Self::validate_name(&name)?;
+        
+        Ok(ProfileConfig { data_dir, name, pinned })
+    }
+    
+    // 配置名称只允许字母、数字、下划线和连字符，避免路径穿越
+    pub fn validate_name(name: &str) -> Result<(), AppError> {
+        if name.is_empty() || name.chars().count() > MAX_PROFILE_NAME_CHARS {
+            return Err(AppError::InvalidData(format!("配置名称长度必须在 1 到 {} 之间", MAX_PROFILE_NAME_CHARS)));
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(AppError::InvalidData("配置名称只能包含字母、数字、下划线和连字符".to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    // 配置的数据库文件路径
+    pub fn db_path(data_dir: &Path, name: &str) -> PathBuf {
+        match name == DEFAULT_PROFILE {
+            true => data_dir.join(DB_FILE_NAME),
+            false => data_dir.join(PROFILES_DIR).join(name).join(DB_FILE_NAME),
+        }
+    }
+    
+    // 打开配置的数据库（不存在时创建）并初始化表
+    pub async fn open_pool(data_dir: &Path, name: &str) -> Result<SqlitePool, AppError> {
+        Self::validate_name(name)?;
+        
+        let path = Self::db_path(data_dir, name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::IoError(e.to_string()))?;
+        }
+        
+        let options = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        // 初始化表，旧版数据库在此迁移到新结构
+        repository::init_tables(&pool).await?;
+        
+        Ok(pool)
+    }
+    
+    // 列出已有的配置，默认配置总是排在第一位
+    pub fn list_profiles(data_dir: &Path) -> Result<Vec<String>, AppError> {
+        let mut profiles = Vec::new();
+        
+        match std::fs::read_dir(data_dir.join(PROFILES_DIR)) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry.map_err(|e| AppError::IoError(e.to_string()))?;
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    if name != DEFAULT_PROFILE
+                        && Self::validate_name(&name).is_ok()
+                        && entry.path().join(DB_FILE_NAME).is_file()
+                    {
+                        profiles.push(name);
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(AppError::IoError(e.to_string())),
+        }
+        
+        profiles.sort();
+        profiles.insert(0, DEFAULT_PROFILE.to_string());
+        Ok(profiles)
+    }
+    
+    // 记录下次启动使用的配置；新配置的数据库在下次启动时创建
+    pub fn set_active(data_dir: &Path, name: &str) -> Result<(), AppError> {
+        Self::validate_name(name)?;
+        
+        std::fs::create_dir_all(data_dir)
+            .map_err(|e| AppError::IoError(e.to_string()))?;
+        std::fs::write(data_dir.join(ACTIVE_PROFILE_FILE), name)
+            .map_err(|e| AppError::IoError(e.to_string()))
+    }
+    
+    fn read_active(data_dir: &Path) -> Option<String> {
+        std::fs::read_to_string(data_dir.join(ACTIVE_PROFILE_FILE))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| Self::validate_name(name).is_ok())
+    }
+    
+    // 支持 "--name value" 和 "--name=value" 两种写法
+    fn arg_value(args: &[String], name: &str) -> Option<String> {
+        let prefix = format!("{}=", name);
+        args.iter().enumerate().find_map(|(i, arg)| {
+            if arg == name {
+                args.get(i + 1).cloned()
+            } else {
+                arg.strip_prefix(&prefix).map(|value| value.to_string())
+            }
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+// 滑动窗口限流：window_secs 秒内最多允许 max_requests 次请求
+pub struct RateLimiter {
+    requests: Mutex<VecDeque<i64>>,
+    max_requests: usize,
+    window_secs: i64,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: usize, window_secs: i64) -> Self {
+        Self {
+            requests: Mutex::new(VecDeque::new()),
+            max_requests: max_requests.max(1),
+            window_secs,
+        }
+    }
+
+    // 允许时记录本次请求；超出限制时返回距下次允许请求的秒数
+    pub fn check(&self, now: i64) -> Result<(), i64> {
+        let mut requests = self.requests.lock().unwrap();
+        retry_after(&mut requests, self.max_requests, self.window_secs, now)?;
+
+        requests.push_back(now);
+        Ok(())
+    }
+
+    // 撤销 check 在 at 时刻记录的请求；只统计失败次数时，尝试成功后调用
+    pub fn release(&self, at: i64) {
+        let mut requests = self.requests.lock().unwrap();
+        if let Some(index) = requests.iter().rposition(|&time| time == at) {
+            requests.remove(index);
+        }
+    }
+}
+
+// 按键（如用户 ID）分别计数的滑动窗口限流
+pub struct KeyedRateLimiter {
+    requests: Mutex<HashMap<String, VecDeque<i64>>>,
+    max_requests: usize,
+    window_secs: i64,
+}
+
+impl KeyedRateLimiter {
+    pub fn new(max_requests: usize, window_secs: i64) -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+            max_requests: max_requests.max(1),
+            window_secs,
+        }
+    }
+
+    // 在锁内检查并预占一次尝试，超出限制时返回距下次允许请求的秒数
+    // 只统计失败次数时先预占，尝试成功后调用 release 归还，并发请求不会越过上限
+    pub fn reserve(&self, key: &str, now: i64) -> Result<(), i64> {
+        let mut requests = self.requests.lock().unwrap();
+        let entries = requests.entry(key.to_string()).or_default();
+        retry_after(entries, self.max_requests, self.window_secs, now)?;
+
+        entries.push_back(now);
+        Ok(())
+    }
+
+    // 归还 reserve 在 at 时刻预占的尝试
+    pub fn release(&self, key: &str, at: i64) {
+        let mut requests = self.requests.lock().unwrap();
+        if let Some(entries) = requests.get_mut(key) {
+            if let Some(index) = entries.iter().rposition(|&time| time == at) {
+                entries.remove(index);
+            }
+            if entries.is_empty() {
+                requests.remove(key);
+            }
+        }
+    }
+}
+
+// 移除窗口外的记录，超出限制时返回距下次允许请求的秒数
+fn retry_after(requests: &mut VecDeque<i64>, max_requests: usize, window_secs: i64, now: i64) -> Result<(), i64> {
+    while requests.front().is_some_and(|&at| at <= now - window_secs) {
+        requests.pop_front();
+    }
+
+    if requests.len() >= max_requests {
+        let oldest = requests.front().copied().unwrap_or(now);
+        return Err((oldest + window_secs - now).max(1));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+// 加密项目的明文搜索索引（默认关闭，需用户主动开启）
+//
+// 威胁模型：
+// - 索引只保存每个词的 HMAC-SHA256 哈希，不保存明文，也不保存词的位置和顺序。
+// - HMAC 密钥由用户的加密密钥派生，没有加密密钥的一方无法通过字典预计算还原索引词。
+// - 哈希是确定性的：能读取数据库的一方仍可以看出哪些加密项目包含相同的词、
+//   某个词出现的频率，以及每个项目大约包含多少个不同的词。
+// - 如果攻击者同时拿到数据库和加密密钥，本来就可以直接解密内容，索引不会带来额外泄露。
+// 因此只有接受上述元数据泄露的用户才应开启；关闭时会删除该用户的全部索引。
+
+use sqlx::SqlitePool;
+use crate::entity::clipboard_item::ClipboardItem;
+use crate::error::AppError;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::search_index_repository::SearchIndexRepository;
+use crate::repository::settings_repository::SettingsRepository;
+use crate::service::clipboard_service::ClipboardService;
+use crate::service::key_cache::KeyCache;
+use crate::util::crypto;
+use crate::util::text;
+
+// 设置项：加密项目搜索索引，按用户保存为 encrypted_search:<user_id>
+pub const ENCRYPTED_SEARCH_KEY: &str = "encrypted_search";
+
+// 派生索引密钥时使用的域分隔标签，避免与其他用途的 HMAC 混用
+const SEARCH_INDEX_CONTEXT: &[u8] = b"sharing-copyboard/search-index/v1";
+
+pub struct SearchIndexService;
+
+impl SearchIndexService {
+    pub async fn is_enabled(pool: &SqlitePool, user_id: &str) -> Result<bool, AppError> {
+        let key = format!("{}:{}", ENCRYPTED_SEARCH_KEY, user_id);
+        SettingsRepository::get_bool(pool, &key, false).await
+    }
+
+    // 开启时为已有的加密项目重建索引，关闭时删除全部索引
+    pub async fn set_enabled(pool: &SqlitePool, user_id: &str, enabled: bool) -> Result<(), AppError> {
+        let key = format!("{}:{}", ENCRYPTED_SEARCH_KEY, user_id);
+        SettingsRepository::set(pool, &key, &enabled.to_string()).await?;
+
+        if enabled {
+            Self::rebuild_for_user(pool, user_id).await?;
+        } else {
+            SearchIndexRepository::clear_for_user(pool, user_id).await?;
+        }
+
+        Ok(())
+    }
+
+    // 更新单个项目的索引：只为加密项目建立索引，明文项目直接按内容搜索
+    pub async fn index_item(
+        pool: &SqlitePool,
+        user_id: &str,
+        item: &ClipboardItem,
+        plaintext: &str
+    ) -> Result<(), AppError> {
+        if !item.encrypted || !text::is_text_content_type(&item.content_type) {
+            let mut conn = pool.acquire()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            return SearchIndexRepository::remove(&mut conn, &item.id).await;
+        }
+
+        if !Self::is_enabled(pool, user_id).await? {
+            return Ok(());
+        }
+
+        let hashes = Self::hash_tokens(pool, user_id, &text::search_tokens(plaintext)).await?;
+        SearchIndexRepository::replace(pool, &item.id, user_id, &hashes).await
+    }
+
+    // 计算新项目的索引词，由调用方在保存项目的事务中写入；不需要建立索引时返回 None
+    pub(crate) async fn item_hashes(
+        pool: &SqlitePool,
+        user_id: &str,
+        item: &ClipboardItem,
+        plaintext: &str
+    ) -> Result<Option<Vec<String>>, AppError> {
+        if !item.encrypted || !text::is_text_content_type(&item.content_type) || !Self::is_enabled(pool, user_id).await? {
+            return Ok(None);
+        }
+
+        Self::hash_tokens(pool, user_id, &text::search_tokens(plaintext)).await.map(Some)
+    }
+
+    // 重建用户全部加密项目的索引，返回建立索引的项目数
+    pub async fn rebuild_for_user(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+        SearchIndexRepository::clear_for_user(pool, user_id).await?;
+
+        let items = ClipboardRepository::find_all_by_user_id_oldest_first(pool, user_id).await?;
+        let mut indexed = 0;
+
+        for item in items.iter().filter(|item| item.encrypted) {
+            // 密文损坏的项目跳过，不影响其他项目
+            let plaintext = match ClipboardService::decrypt_item(pool, user_id, item).await {
+                Ok(plaintext) => plaintext,
+                Err(AppError::DecryptionFailed(e)) => {
+                    eprintln!("跳过无法解密的项目 {}: {}", item.id, e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            Self::index_item(pool, user_id, item, &plaintext).await?;
+            indexed += 1;
+        }
+
+        Ok(indexed)
+    }
+
+    // 计算查询词的哈希；未开启时返回空列表
+    pub async fn query_hashes(pool: &SqlitePool, user_id: &str, query: &str) -> Result<Vec<String>, AppError> {
+        if !Self::is_enabled(pool, user_id).await? {
+            return Ok(Vec::new());
+        }
+
+        Self::hash_tokens(pool, user_id, &text::search_tokens(query)).await
+    }
+
+    async fn hash_tokens(pool: &SqlitePool, user_id: &str, tokens: &[String]) -> Result<Vec<String>, AppError> {
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let encryption_key = KeyCache::get_key(pool, user_id).await?
+            .ok_or_else(|| AppError::KeyUnavailable("加密密钥不存在".to_string()))?;
+        let index_key = crypto::hmac_sha256(&encryption_key.key_data, SEARCH_INDEX_CONTEXT);
+
+        Ok(tokens
+            .iter()
+            .map(|token| {
+                crypto::hmac_sha256(&index_key, token.as_bytes())
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect()
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::entity::user::User;
+
+// 默认缓存容量
+pub const DEFAULT_SESSION_CACHE_CAPACITY: usize = 256;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SessionCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+}
+
+struct CachedSession {
+    user: User,
+    expires_at: i64,
+    last_used: u64,
+}
+
+struct CacheInner {
+    entries: HashMap<String, CachedSession>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+// 会话缓存（LRU），避免每次命令都查询数据库
+pub struct SessionCache {
+    inner: Mutex<CacheInner>,
+    capacity: usize,
+}
+
+impl SessionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                entries: HashMap::new(),
+                tick: 0,
+                hits: 0,
+                misses: 0,
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    // 查询缓存，过期的条目会被移除并视为未命中
+    pub fn get(&self, token: &str, now: i64) -> Option<User> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        let user = match inner.entries.get_mut(token) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = tick;
+                Some(entry.user.clone())
+            }
+            Some(_) => {
+                inner.entries.remove(token);
+                None
+            }
+            None => None,
+        };
+
+        if user.is_some() {
+            inner.hits += 1;
+        } else {
+            inner.misses += 1;
+        }
+        user
+    }
+
+    // 写入缓存，容量已满时淘汰最久未使用的条目
+    pub fn insert(&self, token: &str, user: User, expires_at: i64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if !inner.entries.contains_key(token) && inner.entries.len() >= self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+
+        inner.entries.insert(token.to_string(), CachedSession { user, expires_at, last_used: tick });
+    }
+
+    pub fn invalidate_token(&self, token: &str) {
+        self.inner.lock().unwrap().entries.remove(token);
+    }
+
+    pub fn invalidate_user(&self, user_id: &str) {
+        self.inner.lock().unwrap().entries.retain(|_, entry| entry.user.id != user_id);
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+
+    pub fn stats(&self) -> SessionCacheStats {
+        let inner = self.inner.lock().unwrap();
+        SessionCacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            size: inner.entries.len(),
+        }
+    }
+}
+
+impl Default for SessionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_CACHE_CAPACITY)
+    }
+}
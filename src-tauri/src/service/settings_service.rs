@@ -0,0 +1,425 @@
+use chrono::{Datelike, Local, Timelike};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use crate::entity::content_type::ContentType;
+use crate::error::AppError;
+use crate::repository::encryption_repository::EncryptionRepository;
+use crate::repository::settings_repository::SettingsRepository;
+use crate::util::crypto::AeadCipher;
+use crate::util::text;
+
+// 设置项：剪贴板内容清理
+pub const SANITIZE_CONTENT_KEY: &str = "sanitize_content";
+pub const KEEP_RAW_CONTENT_KEY: &str = "keep_raw_content";
+// 设置项：免打扰时段（JSON）
+pub const QUIET_HOURS_KEY: &str = "quiet_hours";
+// 设置项：默认加密策略，按用户保存为 default_encrypt:<user_id>
+pub const DEFAULT_ENCRYPT_KEY: &str = "default_encrypt";
+// 设置项：分页查询单页的最大条数
+pub const MAX_PAGE_SIZE_KEY: &str = "max_page_size";
+pub const DEFAULT_MAX_PAGE_SIZE: i64 = 500;
+// 设置项：各类内容的预览长度（JSON）
+pub const PREVIEW_LENGTHS_KEY: &str = "preview_lengths";
+// 设置项：列表中显示内容时应用的脱敏规则（JSON），数据库中的原始内容不变
+pub const REDACTION_PATTERNS_KEY: &str = "redaction_patterns";
+pub const MAX_REDACTION_PATTERNS: usize = 32;
+// 单条正则编译后的大小上限，避免规则过于复杂拖慢列表
+const REDACTION_REGEX_SIZE_LIMIT: usize = 1 << 20;
+// 设置项：新加密内容使用的算法，已有密文按各自保存的算法解密
+pub const CIPHER_KEY: &str = "cipher";
+// 设置项：存储配额（JSON），按用户保存为 storage_quota:<user_id>
+pub const STORAGE_QUOTA_KEY: &str = "storage_quota";
+// 设置项：剪贴板监控两次保存之间的最短间隔（毫秒），0 表示不限制
+pub const CAPTURE_COOLDOWN_MS_KEY: &str = "capture_cooldown_ms";
+pub const MAX_CAPTURE_COOLDOWN_MS: i64 = 60_000;
+// 设置项：是否允许注册前检查邮箱是否已被使用，注重隐私的部署可以关闭
+pub const EMAIL_CHECK_ENABLED_KEY: &str = "email_check_enabled";
+// 设置项：登录后是否自动启动剪贴板监控
+pub const AUTO_MONITOR_KEY: &str = "auto_monitor";
+// 设置项：剪贴板监控保存的内容类别（JSON），未设置时保存全部类别
+pub const MONITOR_CAPTURE_TYPES_KEY: &str = "monitor_capture_types";
+// 设置项：应用 PIN 的 Argon2 哈希，未设置时不锁定界面
+pub const APP_PIN_HASH_KEY: &str = "app_pin_hash";
+// 设置项：解锁后空闲多少秒重新锁定
+pub const APP_LOCK_IDLE_SECS_KEY: &str = "app_lock_idle_secs";
+pub const DEFAULT_APP_LOCK_IDLE_SECS: i64 = 5 * 60;
+pub const MIN_APP_LOCK_IDLE_SECS: i64 = 30;
+pub const MAX_APP_LOCK_IDLE_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SanitizeSettings {
+    pub enabled: bool,
+    pub keep_raw: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct EncryptionPolicy {
+    pub default_encrypt: bool,
+}
+
+// 列表中预览文本的最大字符数，按内容类别分别设置
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PreviewLengths {
+    pub text: usize,
+    pub code: usize,
+    pub url: usize,
+}
+
+impl Default for PreviewLengths {
+    fn default() -> Self {
+        Self { text: 80, code: 120, url: 200 }
+    }
+}
+
+impl PreviewLengths {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.text == 0 || self.code == 0 || self.url == 0 {
+            return Err(AppError::InvalidData("预览长度必须大于 0".to_string()));
+        }
+        Ok(())
+    }
+    
+    // 内容类型对应的预览长度
+    pub fn for_content_type(&self, content_type: &str) -> usize {
+        if ContentType::from_mime(content_type) == ContentType::Url {
+            self.url
+        } else if text::is_code_content_type(content_type) {
+            self.code
+        } else {
+            self.text
+        }
+    }
+}
+
+// 脱敏规则：匹配 pattern（正则）的部分替换为 replacement，可用 $1 等引用捕获组
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RedactionPattern {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+impl RedactionPattern {
+    pub fn compile(&self) -> Result<Regex, AppError> {
+        regex::RegexBuilder::new(&self.pattern)
+            .size_limit(REDACTION_REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|e| AppError::InvalidData(format!("无效的脱敏规则 {}: {}", self.pattern, e)))
+    }
+}
+
+// 存储配额（字节）：超出软配额只提醒，超出硬配额拒绝新内容；未设置表示不限制
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct StorageQuota {
+    pub soft_limit_bytes: Option<i64>,
+    pub hard_limit_bytes: Option<i64>,
+}
+
+impl StorageQuota {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.soft_limit_bytes.is_some_and(|limit| limit <= 0)
+            || self.hard_limit_bytes.is_some_and(|limit| limit <= 0) {
+            return Err(AppError::InvalidData("存储配额必须大于 0".to_string()));
+        }
+        if let (Some(soft), Some(hard)) = (self.soft_limit_bytes, self.hard_limit_bytes) {
+            if soft > hard {
+                return Err(AppError::InvalidData("软配额不能大于硬配额".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 免打扰时段：期间不采集剪贴板，也不进行同步
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub ranges: Vec<QuietRange>,
+}
+
+// start 晚于 end 时表示跨越午夜，归属于开始的那一天；start 等于 end 表示全天
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuietRange {
+    pub weekdays: Vec<u32>, // 0 = 周一, 6 = 周日
+    pub start: String, // HH:MM（本地时间）
+    pub end: String,
+}
+
+impl QuietHours {
+    pub fn validate(&self) -> Result<(), AppError> {
+        for range in &self.ranges {
+            if range.weekdays.iter().any(|day| *day > 6) {
+                return Err(AppError::InvalidData("星期必须在 0（周一）到 6（周日）之间".to_string()));
+            }
+            parse_time(&range.start)?;
+            parse_time(&range.end)?;
+        }
+        Ok(())
+    }
+    
+    // weekday: 0 = 周一；minute: 当天的第几分钟
+    pub fn is_quiet_at(&self, weekday: u32, minute: u32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        
+        let previous_day = (weekday + 6) % 7;
+        self.ranges.iter().any(|range| {
+            let (start, end) = match (parse_time(&range.start), parse_time(&range.end)) {
+                (Ok(start), Ok(end)) => (start, end),
+                _ => return false,
+            };
+            let today = range.weekdays.contains(&weekday);
+            
+            if start == end {
+                today
+            } else if start < end {
+                today && minute >= start && minute < end
+            } else {
+                (today && minute >= start) || (range.weekdays.contains(&previous_day) && minute < end)
+            }
+        })
+    }
+}
+
+// 解析 HH:MM，返回当天的第几分钟
+fn parse_time(value: &str) -> Result<u32, AppError> {
+    let invalid = || AppError::InvalidData(format!("无效的时间: {}，格式应为 HH:MM", value));
+    
+    let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+    
+    if hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+    
+    Ok(hour * 60 + minute)
+}
+
+pub struct SettingsService;
+
+impl SettingsService {
+    pub async fn get_sanitize_settings(pool: &SqlitePool) -> Result<SanitizeSettings, AppError> {
+        Ok(SanitizeSettings {
+            enabled: SettingsRepository::get_bool(pool, SANITIZE_CONTENT_KEY, false).await?,
+            keep_raw: SettingsRepository::get_bool(pool, KEEP_RAW_CONTENT_KEY, false).await?,
+        })
+    }
+    
+    pub async fn set_sanitize_settings(pool: &SqlitePool, settings: &SanitizeSettings) -> Result<(), AppError> {
+        SettingsRepository::set(pool, SANITIZE_CONTENT_KEY, &settings.enabled.to_string()).await?;
+        SettingsRepository::set(pool, KEEP_RAW_CONTENT_KEY, &settings.keep_raw.to_string()).await?;
+        Ok(())
+    }
+    
+    pub async fn get_quiet_hours(pool: &SqlitePool) -> Result<QuietHours, AppError> {
+        let value = SettingsRepository::get(pool, QUIET_HOURS_KEY).await?;
+        
+        // 未设置或无法解析时视为未启用
+        Ok(value
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default())
+    }
+    
+    pub async fn set_quiet_hours(pool: &SqlitePool, schedule: &QuietHours) -> Result<(), AppError> {
+        schedule.validate()?;
+        
+        let value = serde_json::to_string(schedule)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, QUIET_HOURS_KEY, &value).await
+    }
+    
+    pub async fn get_storage_quota(pool: &SqlitePool, user_id: &str) -> Result<StorageQuota, AppError> {
+        let key = format!("{}:{}", STORAGE_QUOTA_KEY, user_id);
+        let value = SettingsRepository::get(pool, &key).await?;
+        
+        // 未设置或无法解析时视为不限制
+        Ok(value
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default())
+    }
+    
+    pub async fn set_storage_quota(pool: &SqlitePool, user_id: &str, quota: &StorageQuota) -> Result<(), AppError> {
+        quota.validate()?;
+        
+        let key = format!("{}:{}", STORAGE_QUOTA_KEY, user_id);
+        let value = serde_json::to_string(quota)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, &key, &value).await
+    }
+    
+    // 当前本地时间是否处于免打扰时段
+    pub async fn is_quiet_now(pool: &SqlitePool) -> Result<bool, AppError> {
+        let schedule = Self::get_quiet_hours(pool).await?;
+        let now = Local::now();
+        
+        Ok(schedule.is_quiet_at(
+            now.weekday().num_days_from_monday(),
+            now.hour() * 60 + now.minute()
+        ))
+    }
+    
+    pub async fn get_encryption_policy(pool: &SqlitePool, user_id: &str) -> Result<EncryptionPolicy, AppError> {
+        let key = format!("{}:{}", DEFAULT_ENCRYPT_KEY, user_id);
+        Ok(EncryptionPolicy {
+            default_encrypt: SettingsRepository::get_bool(pool, &key, false).await?,
+        })
+    }
+    
+    pub async fn set_encryption_policy(
+        pool: &SqlitePool,
+        user_id: &str,
+        policy: &EncryptionPolicy
+    ) -> Result<(), AppError> {
+        // 开启默认加密前确保用户已有加密密钥
+        if policy.default_encrypt && EncryptionRepository::find_by_user_id(pool, user_id).await?.is_none() {
+            EncryptionRepository::create_for_user(pool, user_id).await?;
+        }
+        
+        let key = format!("{}:{}", DEFAULT_ENCRYPT_KEY, user_id);
+        SettingsRepository::set(pool, &key, &policy.default_encrypt.to_string()).await
+    }
+    
+    pub async fn get_cipher(pool: &SqlitePool) -> Result<AeadCipher, AppError> {
+        let value = SettingsRepository::get(pool, CIPHER_KEY).await?;
+        // 未设置或格式错误时使用默认算法
+        Ok(value
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default())
+    }
+    
+    pub async fn set_cipher(pool: &SqlitePool, cipher: AeadCipher) -> Result<(), AppError> {
+        let value = serde_json::to_string(&cipher)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, CIPHER_KEY, &value).await
+    }
+    
+    pub async fn get_max_page_size(pool: &SqlitePool) -> Result<i64, AppError> {
+        let size = SettingsRepository::get_i64(pool, MAX_PAGE_SIZE_KEY, DEFAULT_MAX_PAGE_SIZE).await?;
+        // 设置值无效时回退到默认值
+        Ok(if size > 0 { size } else { DEFAULT_MAX_PAGE_SIZE })
+    }
+    
+    pub async fn set_max_page_size(pool: &SqlitePool, size: i64) -> Result<(), AppError> {
+        if size <= 0 {
+            return Err(AppError::InvalidData("分页大小上限必须大于 0".to_string()));
+        }
+        
+        SettingsRepository::set(pool, MAX_PAGE_SIZE_KEY, &size.to_string()).await
+    }
+    
+    pub async fn get_capture_cooldown_ms(pool: &SqlitePool) -> Result<i64, AppError> {
+        let cooldown = SettingsRepository::get_i64(pool, CAPTURE_COOLDOWN_MS_KEY, 0).await?;
+        // 设置值无效时视为不限制
+        Ok(if (0..=MAX_CAPTURE_COOLDOWN_MS).contains(&cooldown) { cooldown } else { 0 })
+    }
+    
+    pub async fn set_capture_cooldown_ms(pool: &SqlitePool, cooldown_ms: i64) -> Result<(), AppError> {
+        if !(0..=MAX_CAPTURE_COOLDOWN_MS).contains(&cooldown_ms) {
+            return Err(AppError::InvalidData(format!("捕获间隔必须在 0 到 {} 毫秒之间", MAX_CAPTURE_COOLDOWN_MS)));
+        }
+        
+        SettingsRepository::set(pool, CAPTURE_COOLDOWN_MS_KEY, &cooldown_ms.to_string()).await
+    }
+    
+    pub async fn get_email_check_enabled(pool: &SqlitePool) -> Result<bool, AppError> {
+        SettingsRepository::get_bool(pool, EMAIL_CHECK_ENABLED_KEY, true).await
+    }
+    
+    pub async fn set_email_check_enabled(pool: &SqlitePool, enabled: bool) -> Result<(), AppError> {
+        SettingsRepository::set(pool, EMAIL_CHECK_ENABLED_KEY, &enabled.to_string()).await
+    }
+    
+    pub async fn get_auto_monitor(pool: &SqlitePool) -> Result<bool, AppError> {
+        SettingsRepository::get_bool(pool, AUTO_MONITOR_KEY, false).await
+    }
+    
+    pub async fn set_auto_monitor(pool: &SqlitePool, enabled: bool) -> Result<(), AppError> {
+        SettingsRepository::set(pool, AUTO_MONITOR_KEY, &enabled.to_string()).await
+    }
+    
+    pub async fn get_capture_types(pool: &SqlitePool) -> Result<Vec<ContentType>, AppError> {
+        let value = SettingsRepository::get(pool, MONITOR_CAPTURE_TYPES_KEY).await?;
+        
+        // 未设置或无法解析时保存全部类别
+        Ok(value
+            .and_then(|v| serde_json::from_str::<Vec<ContentType>>(&v).ok())
+            .filter(|types| !types.is_empty())
+            .unwrap_or_else(|| ContentType::ALL.to_vec()))
+    }
+    
+    pub async fn set_capture_types(pool: &SqlitePool, types: &[ContentType]) -> Result<(), AppError> {
+        if types.is_empty() {
+            return Err(AppError::InvalidData("至少需要保存一种内容类别，停止采集请关闭监控".to_string()));
+        }
+        
+        let mut unique: Vec<ContentType> = Vec::new();
+        for content_type in types {
+            if !unique.contains(content_type) {
+                unique.push(*content_type);
+            }
+        }
+        
+        let value = serde_json::to_string(&unique)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, MONITOR_CAPTURE_TYPES_KEY, &value).await
+    }
+    
+    pub async fn get_app_lock_idle_secs(pool: &SqlitePool) -> Result<i64, AppError> {
+        let secs = SettingsRepository::get_i64(pool, APP_LOCK_IDLE_SECS_KEY, DEFAULT_APP_LOCK_IDLE_SECS).await?;
+        Ok(secs.clamp(MIN_APP_LOCK_IDLE_SECS, MAX_APP_LOCK_IDLE_SECS))
+    }
+    
+    pub async fn set_app_lock_idle_secs(pool: &SqlitePool, secs: i64) -> Result<(), AppError> {
+        if !(MIN_APP_LOCK_IDLE_SECS..=MAX_APP_LOCK_IDLE_SECS).contains(&secs) {
+            return Err(AppError::InvalidData(format!(
+                "空闲锁定时间必须在 {} 到 {} 秒之间",
+                MIN_APP_LOCK_IDLE_SECS, MAX_APP_LOCK_IDLE_SECS
+            )));
+        }
+        SettingsRepository::set(pool, APP_LOCK_IDLE_SECS_KEY, &secs.to_string()).await
+    }
+    
+    pub async fn get_preview_lengths(pool: &SqlitePool) -> Result<PreviewLengths, AppError> {
+        let value = SettingsRepository::get(pool, PREVIEW_LENGTHS_KEY).await?;
+        
+        // 未设置或无法解析时使用默认值
+        Ok(value
+            .and_then(|v| serde_json::from_str::<PreviewLengths>(&v).ok())
+            .filter(|lengths| lengths.validate().is_ok())
+            .unwrap_or_default())
+    }
+    
+    pub async fn set_preview_lengths(pool: &SqlitePool, lengths: &PreviewLengths) -> Result<(), AppError> {
+        lengths.validate()?;
+        
+        let value = serde_json::to_string(lengths)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, PREVIEW_LENGTHS_KEY, &value).await
+    }
+    
+    pub async fn get_redaction_patterns(pool: &SqlitePool) -> Result<Vec<RedactionPattern>, AppError> {
+        let value = SettingsRepository::get(pool, REDACTION_PATTERNS_KEY).await?;
+        
+        // 未设置或无法解析时不脱敏
+        Ok(value
+            .and_then(|v| serde_json::from_str::<Vec<RedactionPattern>>(&v).ok())
+            .unwrap_or_default())
+    }
+    
+    pub async fn set_redaction_patterns(pool: &SqlitePool, patterns: &[RedactionPattern]) -> Result<(), AppError> {
+        if patterns.len() > MAX_REDACTION_PATTERNS {
+            return Err(AppError::InvalidData(format!("脱敏规则最多 {} 条", MAX_REDACTION_PATTERNS)));
+        }
+        for pattern in patterns {
+            pattern.compile()?;
+        }
+        
+        let value = serde_json::to_string(patterns)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, REDACTION_PATTERNS_KEY, &value).await
+    }
+}
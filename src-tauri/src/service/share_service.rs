@@ -0,0 +1,98 @@
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::entity::share::{Share, ShareLink, SharedContent};
+use crate::error::AppError;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::share_repository::ShareRepository;
+use crate::service::clipboard_service::ClipboardService;
+use crate::util::crypto;
+
+// 分享有效期范围（秒）
+pub const MIN_SHARE_TTL_SECS: i64 = 60;
+pub const MAX_SHARE_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+pub struct ShareService;
+
+impl ShareService {
+    // 使用分享口令派生的密钥加密项目内容，返回分享码；项目本身的加密密钥不会离开本机
+    pub async fn create_share_link(
+        pool: &SqlitePool,
+        user_id: &str,
+        id: &str,
+        passphrase: &str,
+        ttl_secs: i64
+    ) -> Result<ShareLink, AppError> {
+        if passphrase.is_empty() {
+            return Err(AppError::InvalidData("分享口令不能为空".to_string()));
+        }
+        if !(MIN_SHARE_TTL_SECS..=MAX_SHARE_TTL_SECS).contains(&ttl_secs) {
+            return Err(AppError::InvalidData(format!(
+                "分享有效期必须在 {} 到 {} 秒之间",
+                MIN_SHARE_TTL_SECS, MAX_SHARE_TTL_SECS
+            )));
+        }
+        
+        let item = ClipboardRepository::find_by_id(pool, id, user_id).await?
+            .ok_or_else(|| AppError::NotFound(format!("Clipboard item with ID {} not found", id)))?;
+        let content = ClipboardService::decrypt_item(pool, user_id, &item).await?;
+        
+        let salt = crypto::generate_salt();
+        let nonce = crypto::generate_nonce();
+        let key = crypto::derive_key_from_passphrase(passphrase, &salt)
+            .map_err(AppError::CryptoError)?;
+        let payload = crypto::encrypt_data(content.as_bytes(), &key, &nonce)
+            .map_err(AppError::CryptoError)?;
+        
+        let now = Self::now();
+        let share = Share {
+            code: crypto::generate_share_code(),
+            user_id: user_id.to_string(),
+            content_type: item.content_type,
+            payload,
+            salt: salt.to_vec(),
+            nonce: nonce.to_vec(),
+            created_at: now,
+            expires_at: now + ttl_secs,
+        };
+        ShareRepository::save(pool, &share).await?;
+        
+        Ok(ShareLink {
+            code: share.code,
+            expires_at: share.expires_at,
+        })
+    }
+    
+    // 兑换分享码；不存在与已过期不作区分，口令错误返回 InvalidCredentials
+    pub async fn redeem_share(
+        pool: &SqlitePool,
+        code: &str,
+        passphrase: &str
+    ) -> Result<SharedContent, AppError> {
+        let share = ShareRepository::find_active(pool, code.trim(), Self::now()).await?
+            .ok_or_else(|| AppError::NotFound("分享不存在或已过期".to_string()))?;
+        
+        if share.nonce.len() != 12 {
+            return Err(AppError::DecryptionFailed("无效的分享数据".to_string()));
+        }
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&share.nonce);
+        
+        let key = crypto::derive_key_from_passphrase(passphrase, &share.salt)
+            .map_err(AppError::CryptoError)?;
+        let content = crypto::decrypt_data(&share.payload, &key, &nonce)
+            .map_err(|_| AppError::InvalidCredentials)?;
+        
+        Ok(SharedContent {
+            content,
+            content_type: share.content_type,
+            expires_at: share.expires_at,
+        })
+    }
+    
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+}
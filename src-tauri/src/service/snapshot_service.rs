@@ -0,0 +1,55 @@
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+use crate::error::AppError;
+use crate::repository::snapshot_repository::SnapshotRepository;
+use crate::service::search_index_service::SearchIndexService;
+use crate::util::db;
+
+// 快照保留时长（秒），只作为批量操作前的临时备份
+pub const SNAPSHOT_TTL_SECS: i64 = 24 * 60 * 60;
+
+pub struct SnapshotService;
+
+impl SnapshotService {
+    // 复制用户当前的全部项目和标签，返回快照 ID
+    pub async fn create_snapshot(pool: &SqlitePool, user_id: &str) -> Result<String, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let id = Uuid::new_v4().to_string();
+        let snapshot_id = id.clone();
+        let owner = user_id.to_string();
+        db::with_transaction(pool, move |conn| Box::pin(async move {
+            SnapshotRepository::create_in(&mut *conn, &snapshot_id, &owner, now, now + SNAPSHOT_TTL_SECS).await
+        })).await?;
+        
+        Ok(id)
+    }
+    
+    // 在一个事务中用快照替换用户当前的项目和标签，返回恢复的项目数
+    pub async fn restore_snapshot(pool: &SqlitePool, user_id: &str, id: &str) -> Result<u64, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let snapshot_id = id.to_string();
+        let owner = user_id.to_string();
+        let restored = db::with_transaction(pool, move |conn| Box::pin(async move {
+            if !SnapshotRepository::is_active_in(&mut *conn, &snapshot_id, &owner, now).await? {
+                return Err(AppError::NotFound("快照不存在或已过期".to_string()));
+            }
+            SnapshotRepository::restore_in(&mut *conn, &snapshot_id, &owner).await
+        })).await?;
+        
+        // 恢复的加密项目需要重新建立搜索索引
+        if SearchIndexService::is_enabled(pool, user_id).await? {
+            SearchIndexService::rebuild_for_user(pool, user_id).await?;
+        }
+        
+        Ok(restored)
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::AppError;
+use crate::service::settings_service::SettingsService;
+
+// 每日统计的天数
+pub const STATS_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContentTypeCount {
+    pub content_type: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyCount {
+    pub date: String, // YYYY-MM-DD（UTC）
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardStatistics {
+    pub total_items: i64,
+    pub total_size: i64, // 字节数，加密项目按密文长度计算
+    pub encrypted_items: i64,
+    pub by_content_type: Vec<ContentTypeCount>,
+    pub items_per_day: Vec<DailyCount>,
+}
+
+// 用户的存储占用与配额
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageUsage {
+    pub used_bytes: i64,
+    pub soft_limit_bytes: Option<i64>,
+    pub hard_limit_bytes: Option<i64>,
+    pub over_soft_limit: bool,
+}
+
+pub struct StatsService;
+
+impl StatsService {
+    pub async fn get_statistics(pool: &SqlitePool, user_id: &str) -> Result<ClipboardStatistics, AppError> {
+        // 总数、总大小和加密数量
+        let totals = sqlx::query(
+            "SELECT COUNT(*) as total_items,
+                    COALESCE(SUM(LENGTH(CAST(content AS BLOB)) + COALESCE(LENGTH(content_blob), 0)), 0) as total_size,
+                    COALESCE(SUM(CASE WHEN encrypted != 0 THEN 1 ELSE 0 END), 0) as encrypted_items
+             FROM clipboard_items WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        // 按类型统计
+        let by_content_type = sqlx::query(
+            "SELECT content_type, COUNT(*) as count
+             FROM clipboard_items WHERE user_id = ?
+             GROUP BY content_type ORDER BY count DESC, content_type ASC"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .map(|row| ContentTypeCount {
+            content_type: row.get("content_type"),
+            count: row.get("count"),
+        })
+        .collect();
+        
+        // 最近 30 天每日新增数量
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let since = now - STATS_DAYS * 24 * 60 * 60;
+        
+        let items_per_day = sqlx::query(
+            "SELECT date(created_at, 'unixepoch') as day, COUNT(*) as count
+             FROM clipboard_items WHERE user_id = ? AND created_at >= ?
+             GROUP BY day ORDER BY day ASC"
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .map(|row| DailyCount {
+            date: row.get("day"),
+            count: row.get("count"),
+        })
+        .collect();
+        
+        Ok(ClipboardStatistics {
+            total_items: totals.get("total_items"),
+            total_size: totals.get("total_size"),
+            encrypted_items: totals.get("encrypted_items"),
+            by_content_type,
+            items_per_day,
+        })
+    }
+    
+    // 估算用户占用的存储：内容、外部二进制内容、保留的原始内容以及其他格式
+    pub async fn storage_used(pool: &SqlitePool, user_id: &str) -> Result<i64, AppError> {
+        let items: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(LENGTH(CAST(content AS BLOB))
+                                 + COALESCE(LENGTH(content_blob), 0)
+                                 + COALESCE(LENGTH(CAST(raw_content AS BLOB)), 0)), 0)
+             FROM clipboard_items WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let formats: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(LENGTH(CAST(f.content AS BLOB))), 0)
+             FROM item_formats f JOIN clipboard_items c ON c.id = f.item_id
+             WHERE c.user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(items + formats)
+    }
+    
+    pub async fn get_storage_usage(pool: &SqlitePool, user_id: &str) -> Result<StorageUsage, AppError> {
+        let used_bytes = Self::storage_used(pool, user_id).await?;
+        let quota = SettingsService::get_storage_quota(pool, user_id).await?;
+        
+        Ok(StorageUsage {
+            used_bytes,
+            soft_limit_bytes: quota.soft_limit_bytes,
+            hard_limit_bytes: quota.hard_limit_bytes,
+            over_soft_limit: quota.soft_limit_bytes.is_some_and(|limit| used_bytes > limit),
+        })
+    }
+}
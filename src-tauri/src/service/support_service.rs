@@ -0,0 +1,133 @@
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::entity::support_bundle::{AppInfo, AuditSummary, BundleCounts, ErrorSummary, SupportBundle, SyncSummary};
+use crate::error::AppError;
+use crate::repository::audit_repository::AuditRepository;
+use crate::repository::init::SCHEMA_VERSION_KEY;
+use crate::repository::mail_repository::MailRepository;
+use crate::repository::settings_repository::SettingsRepository;
+use crate::service::sync_service::SyncService;
+use crate::sync;
+use crate::util::text;
+
+// 诊断包中保留的最近审计记录和错误数量
+pub const SUPPORT_BUNDLE_RECENT_LIMIT: i64 = 50;
+// 诊断包保存在应用数据目录下的子目录
+pub const SUPPORT_BUNDLE_DIR: &str = "support";
+
+pub struct SupportService;
+
+impl SupportService {
+    // 生成诊断包；sync_connected 为当前同步连接状态，由调用方从连接管理器读取
+    pub async fn build_bundle(pool: &SqlitePool, user_id: &str, sync_connected: bool) -> Result<SupportBundle, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let schema_version = SettingsRepository::get_i64(pool, SCHEMA_VERSION_KEY, 0).await?;
+        let counts = Self::count_for_user(pool, user_id).await?;
+        
+        // 服务器地址只保留主机和端口
+        let server_host = SyncService::get_server_url(pool).await?
+            .and_then(|url| url::Url::parse(&url).ok())
+            .and_then(|url| url.host_str().map(|host| match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            }));
+        let sync = SyncSummary {
+            server_host,
+            connected: sync_connected,
+            last_sync_at: sync::get_last_sync_timestamp(pool).await?,
+            bound_devices: sync::get_bound_devices(pool).await?.len(),
+        };
+        
+        let recent_audit = AuditRepository::find_by_user(pool, user_id, SUPPORT_BUNDLE_RECENT_LIMIT, 0).await?
+            .into_iter()
+            .map(|entry| AuditSummary {
+                action: entry.action,
+                created_at: entry.created_at,
+            })
+            .collect();
+        
+        let recent_errors = MailRepository::find_recent_errors(pool, SUPPORT_BUNDLE_RECENT_LIMIT).await?
+            .into_iter()
+            .map(|(message, occurred_at)| ErrorSummary {
+                source: "mail".to_string(),
+                message: text::redact_secrets(&message),
+                occurred_at,
+            })
+            .collect();
+        
+        Ok(SupportBundle {
+            app_info: AppInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+            },
+            generated_at: now,
+            schema_version,
+            counts,
+            sync,
+            recent_audit,
+            recent_errors,
+        })
+    }
+    
+    // 将诊断包写入 dir/support，返回文件路径
+    pub fn write_bundle(dir: &Path, bundle: &SupportBundle) -> Result<PathBuf, AppError> {
+        let dir = dir.join(SUPPORT_BUNDLE_DIR);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| AppError::IoError(e.to_string()))?;
+        
+        let json = serde_json::to_vec_pretty(bundle)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        let path = dir.join(format!("support-bundle-{}.json", bundle.generated_at));
+        std::fs::write(&path, json)
+            .map_err(|e| AppError::IoError(e.to_string()))?;
+        
+        Ok(path)
+    }
+    
+    async fn count_for_user(pool: &SqlitePool, user_id: &str) -> Result<BundleCounts, AppError> {
+        let (items, encrypted_items): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(SUM(CASE WHEN encrypted != 0 THEN 1 ELSE 0 END), 0)
+             FROM clipboard_items WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let tags: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM item_tags WHERE item_id IN (SELECT id FROM clipboard_items WHERE user_id = ?)"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let tombstones: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tombstones WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        Ok(BundleCounts {
+            items,
+            encrypted_items,
+            tags,
+            tombstones,
+            unsynced_items: sync::count_unsynced(pool, Some(user_id)).await?,
+            sessions,
+        })
+    }
+}
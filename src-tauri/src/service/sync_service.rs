@@ -0,0 +1,230 @@
+use sqlx::SqlitePool;
+use crate::entity::tombstone::Tombstone;
+use crate::error::AppError;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::session_repository::SessionRepository;
+use crate::repository::settings_repository::SettingsRepository;
+use crate::repository::tombstone_repository::TombstoneRepository;
+use crate::sync::{self, DeviceSyncFilter, MergePolicy, ReconnectPolicy, SyncMessage};
+use crate::util::validation;
+use uuid::Uuid;
+
+// 设置项：当前同步服务器地址
+pub const SYNC_SERVER_URL_KEY: &str = "sync_server_url";
+// 设置项：固定的服务器证书 SHA-256 指纹
+pub const SYNC_PINNED_CERT_KEY: &str = "sync_pinned_cert";
+// 设置项：重连上限（JSON）
+pub const SYNC_RECONNECT_POLICY_KEY: &str = "sync_reconnect_policy";
+// 设置项：按设备的同步过滤（JSON），实际键为 "device_sync_filter:<设备 ID>"
+pub const DEVICE_SYNC_FILTER_KEY: &str = "device_sync_filter";
+// 设置项：远程修改与本地冲突时的合并策略（JSON）
+pub const MERGE_POLICY_KEY: &str = "merge_policy";
+// 设置项：本机的设备 ID，每次安装首次运行时生成
+pub const DEVICE_ID_KEY: &str = "device_id";
+
+pub struct SyncService;
+
+impl SyncService {
+    pub async fn get_server_url(pool: &SqlitePool) -> Result<Option<String>, AppError> {
+        SettingsRepository::get(pool, SYNC_SERVER_URL_KEY).await
+    }
+    
+    // 校验同步服务器地址，只接受 ws:// 和 wss://
+    pub fn parse_server_url(server_url: &str) -> Result<url::Url, AppError> {
+        let url = url::Url::parse(server_url)
+            .map_err(|e| AppError::InvalidData(format!("无效的同步服务器地址: {}", e)))?;
+        
+        if url.scheme() != "ws" && url.scheme() != "wss" {
+            return Err(AppError::InvalidData("同步服务器地址必须以 ws:// 或 wss:// 开头".to_string()));
+        }
+        
+        Ok(url)
+    }
+    
+    // 切换服务器：保存新地址，重置同步时间戳以触发全量同步，并清空旧服务器的设备列表
+    pub async fn reset_for_server(pool: &SqlitePool, new_url: &str) -> Result<(), AppError> {
+        Self::parse_server_url(new_url)?;
+        
+        SettingsRepository::set(pool, SYNC_SERVER_URL_KEY, new_url).await?;
+        sync::update_last_sync_timestamp(pool, 0).await?;
+        sync::clear_bound_devices(pool).await?;
+        
+        Ok(())
+    }
+    
+    pub async fn get_pinned_cert(pool: &SqlitePool) -> Result<Option<String>, AppError> {
+        SettingsRepository::get(pool, SYNC_PINNED_CERT_KEY).await
+    }
+    
+    // 设置或清除（None）固定的证书指纹，返回规范化后的指纹
+    pub async fn set_pinned_cert(pool: &SqlitePool, fingerprint: Option<&str>) -> Result<Option<String>, AppError> {
+        match fingerprint {
+            Some(fingerprint) => {
+                let normalized = validation::normalize_fingerprint(fingerprint)?;
+                SettingsRepository::set(pool, SYNC_PINNED_CERT_KEY, &normalized).await?;
+                Ok(Some(normalized))
+            }
+            None => {
+                SettingsRepository::delete(pool, SYNC_PINNED_CERT_KEY).await?;
+                Ok(None)
+            }
+        }
+    }
+    
+    // 未设置时使用默认上限
+    pub async fn get_reconnect_policy(pool: &SqlitePool) -> Result<ReconnectPolicy, AppError> {
+        let value = SettingsRepository::get(pool, SYNC_RECONNECT_POLICY_KEY).await?;
+        
+        match value {
+            Some(value) => serde_json::from_str(&value)
+                .map_err(|e| AppError::InvalidData(format!("重连设置格式错误: {}", e))),
+            None => Ok(ReconnectPolicy::default()),
+        }
+    }
+    
+    pub async fn set_reconnect_policy(pool: &SqlitePool, policy: &ReconnectPolicy) -> Result<(), AppError> {
+        if policy.max_attempts == Some(0) {
+            return Err(AppError::InvalidData("最大重连次数必须大于 0".to_string()));
+        }
+        
+        let value = serde_json::to_string(policy)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, SYNC_RECONNECT_POLICY_KEY, &value).await
+    }
+    
+    // 未设置时按更新时间合并
+    pub async fn get_merge_policy(pool: &SqlitePool) -> Result<MergePolicy, AppError> {
+        let value = SettingsRepository::get(pool, MERGE_POLICY_KEY).await?;
+        
+        match value {
+            Some(value) => serde_json::from_str(&value)
+                .map_err(|e| AppError::InvalidData(format!("合并策略格式错误: {}", e))),
+            None => Ok(MergePolicy::default()),
+        }
+    }
+    
+    pub async fn set_merge_policy(pool: &SqlitePool, policy: MergePolicy) -> Result<(), AppError> {
+        let value = serde_json::to_string(&policy)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, MERGE_POLICY_KEY, &value).await
+    }
+    
+    pub async fn get_device_sync_filter(pool: &SqlitePool, device_id: &str) -> Result<DeviceSyncFilter, AppError> {
+        let key = format!("{}:{}", DEVICE_SYNC_FILTER_KEY, device_id);
+        let value = SettingsRepository::get(pool, &key).await?;
+        
+        match value {
+            Some(value) => serde_json::from_str(&value)
+                .map_err(|e| AppError::InvalidData(format!("设备同步过滤格式错误: {}", e))),
+            None => Ok(DeviceSyncFilter::default()),
+        }
+    }
+    
+    pub async fn set_device_sync_filter(
+        pool: &SqlitePool,
+        device_id: &str,
+        filter: &DeviceSyncFilter
+    ) -> Result<(), AppError> {
+        if device_id.is_empty() {
+            return Err(AppError::InvalidData("设备 ID 不能为空".to_string()));
+        }
+        if filter.excluded_content_types.iter().any(|t| t.trim().is_empty()) {
+            return Err(AppError::InvalidData("内容类型不能为空".to_string()));
+        }
+        
+        let key = format!("{}:{}", DEVICE_SYNC_FILTER_KEY, device_id);
+        let value = serde_json::to_string(filter)
+            .map_err(|e| AppError::InvalidData(e.to_string()))?;
+        SettingsRepository::set(pool, &key, &value).await
+    }
+    
+    // 获取本机的设备 ID，尚未生成时（新安装或旧版本升级）生成并保存
+    pub async fn get_device_id(pool: &SqlitePool) -> Result<String, AppError> {
+        if let Some(device_id) = SettingsRepository::get(pool, DEVICE_ID_KEY).await? {
+            return Ok(device_id);
+        }
+        
+        // 并发首次调用时只有一个 ID 会被保存，统一读取保存的值
+        SettingsRepository::set_if_absent(pool, DEVICE_ID_KEY, &Uuid::new_v4().to_string()).await?;
+        SettingsRepository::get(pool, DEVICE_ID_KEY).await?
+            .ok_or_else(|| AppError::DatabaseError("设备 ID 保存失败".to_string()))
+    }
+    
+    // 重新生成本机的设备 ID，本机已有的会话改到新 ID 下，返回新 ID
+    // 同步连接在下次建立时使用新 ID
+    pub async fn regenerate_device_id(pool: &SqlitePool) -> Result<String, AppError> {
+        let old_device_id = Self::get_device_id(pool).await?;
+        let new_device_id = Uuid::new_v4().to_string();
+        
+        SettingsRepository::set(pool, DEVICE_ID_KEY, &new_device_id).await?;
+        SessionRepository::reassign_device(pool, &old_device_id, &new_device_id).await?;
+        
+        Ok(new_device_id)
+    }
+    
+    // 构造发给单个设备的项目消息：项目必须属于当前用户，目标必须是已绑定的其他设备
+    pub async fn build_direct_send(
+        pool: &SqlitePool,
+        user_id: &str,
+        item_id: &str,
+        device_id: &str
+    ) -> Result<SyncMessage, AppError> {
+        let item = ClipboardRepository::find_by_id(pool, item_id, user_id).await?
+            .ok_or_else(|| AppError::NotFound("剪贴板项目不存在".to_string()))?;
+        
+        if device_id == Self::get_device_id(pool).await? {
+            return Err(AppError::InvalidData("不能发送给本设备".to_string()));
+        }
+        
+        let bound = sync::get_bound_devices(pool).await?;
+        if !bound.iter().any(|device| device.device_id == device_id) {
+            return Err(AppError::NotFound("设备未绑定".to_string()));
+        }
+        
+        Ok(SyncMessage::DirectSend {
+            target_device_id: device_id.to_string(),
+            item,
+        })
+    }
+    
+    // 解绑设备：移出绑定列表，删除当前用户在该设备上的会话及按设备保存的同步状态，返回删除的会话数
+    // 被解绑的设备再次连接时令牌验证失败，由客户端清空本地数据
+    pub async fn unbind_device(pool: &SqlitePool, user_id: &str, device_id: &str) -> Result<u64, AppError> {
+        if device_id.is_empty() {
+            return Err(AppError::InvalidData("设备 ID 不能为空".to_string()));
+        }
+        
+        sync::remove_bound_device(pool, device_id).await?;
+        let revoked = SessionRepository::delete_by_device(pool, user_id, device_id).await?;
+        
+        SettingsRepository::delete(pool, &format!("{}:{}", DEVICE_SYNC_FILTER_KEY, device_id)).await?;
+        sync::delete_device_skew(pool, device_id).await?;
+        
+        Ok(revoked)
+    }
+    
+    // 合并绑定设备列表中重复的设备记录，返回移除的记录数
+    pub async fn dedupe_devices(pool: &SqlitePool) -> Result<usize, AppError> {
+        sync::dedupe_bound_devices(pool).await
+    }
+    
+    // 声明本地状态为准，不再上传现有项目
+    pub async fn mark_all_synced(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+        sync::mark_all_synced(pool, user_id).await
+    }
+    
+    // 强制重新上传用户的所有项目
+    pub async fn mark_all_unsynced(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+        sync::mark_all_unsynced(pool, user_id).await
+    }
+    
+    pub async fn get_tombstones(
+        pool: &SqlitePool,
+        user_id: &str,
+        since: i64,
+        limit: i64,
+        offset: i64
+    ) -> Result<Vec<Tombstone>, AppError> {
+        TombstoneRepository::find_by_user_since(pool, user_id, since, limit, offset).await
+    }
+}
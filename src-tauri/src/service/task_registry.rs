@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::Mutex;
+use crate::error::AppError;
+
+// 后台任务名称
+pub const CLIPBOARD_MONITOR_TASK: &str = "clipboard_monitor";
+pub const SYNC_LOOP_TASK: &str = "sync_loop";
+pub const MAIL_WORKER_TASK: &str = "mail_worker";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackgroundTaskInfo {
+    pub name: String,
+    pub started_at: i64,
+    pub finished: bool,
+}
+
+struct BackgroundTask {
+    handle: JoinHandle<()>,
+    started_at: i64,
+}
+
+// 后台任务注册表，保存所有已启动任务的句柄
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, BackgroundTask>>,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 注册任务，同名的旧任务会被终止，避免重复启动
+    pub async fn register(&self, name: &str, handle: JoinHandle<()>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let mut tasks = self.tasks.lock().await;
+        if let Some(old) = tasks.insert(name.to_string(), BackgroundTask { handle, started_at: now }) {
+            old.handle.abort();
+        }
+    }
+
+    // 同名任务仍在运行时不启动新任务并返回 false
+    // 检查和注册在同一把锁内完成，并发调用时只会启动一个任务
+    pub async fn register_if_absent<F>(&self, name: &str, spawn: F) -> bool
+    where
+        F: FnOnce() -> JoinHandle<()>,
+    {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let mut tasks = self.tasks.lock().await;
+        if tasks.get(name).is_some_and(|task| !task.handle.inner().is_finished()) {
+            return false;
+        }
+
+        tasks.insert(name.to_string(), BackgroundTask { handle: spawn(), started_at: now });
+        true
+    }
+
+    pub async fn list(&self) -> Vec<BackgroundTaskInfo> {
+        let tasks = self.tasks.lock().await;
+        let mut result: Vec<BackgroundTaskInfo> = tasks
+            .iter()
+            .map(|(name, task)| BackgroundTaskInfo {
+                name: name.clone(),
+                started_at: task.started_at,
+                finished: task.handle.inner().is_finished(),
+            })
+            .collect();
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result
+    }
+
+    pub async fn is_running(&self, name: &str) -> bool {
+        let tasks = self.tasks.lock().await;
+        tasks
+            .get(name)
+            .map(|task| !task.handle.inner().is_finished())
+            .unwrap_or(false)
+    }
+
+    // 终止并移除任务
+    pub async fn stop(&self, name: &str) -> Result<(), AppError> {
+        let mut tasks = self.tasks.lock().await;
+        match tasks.remove(name) {
+            Some(task) => {
+                task.handle.abort();
+                Ok(())
+            }
+            None => Err(AppError::NotFound(format!("后台任务不存在: {}", name))),
+        }
+    }
+
+    pub async fn stop_all(&self) {
+        let mut tasks = self.tasks.lock().await;
+        for (_, task) in tasks.drain() {
+            task.handle.abort();
+        }
+    }
+}
+
+impl Default for TaskRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -5,7 +5,21 @@ use crate::entity::user::{User, UserProfile};
 use crate::repository::user_repository::UserRepository;
 use crate::repository::session_repository::SessionRepository;
 use crate::error::AppError;
+use crate::service::rate_limiter::RateLimiter;
+use crate::service::settings_service::SettingsService;
 use crate::util::crypto;
+use crate::util::db::{retry_on_busy, write_error};
+use crate::util::validation;
+
+// 验证码有效期（秒）
+pub const VERIFICATION_CODE_TTL_SECS: i64 = 10 * 60;
+// 同一邮箱两次发送验证码的最短间隔（秒）
+pub const VERIFICATION_RESEND_COOLDOWN_SECS: i64 = 60;
+// 邮箱可用性检查的限流：每分钟最多检查的次数
+pub const EMAIL_CHECK_MAX_PER_MINUTE: usize = 10;
+// 邮箱可用性检查的随机延迟范围（毫秒），使响应时间不反映查询结果
+const EMAIL_CHECK_MIN_DELAY_MS: u64 = 50;
+const EMAIL_CHECK_MAX_DELAY_MS: u64 = 250;
 
 pub struct UserService;
 
@@ -16,6 +30,9 @@ impl UserService {
         password: &str, 
         verification_code: &str
     ) -> Result<User, AppError> {
+        // 校验邮箱格式
+        validation::validate_email(email)?;
+        
         // 验证验证码
         let is_valid = Self::verify_code(pool, email, verification_code).await?;
         
@@ -32,7 +49,7 @@ impl UserService {
         
         // 哈希密码
         let password_hash = crypto::hash_password(password)
-            .map_err(|e| AppError::CryptoError(e))?;
+            .map_err(AppError::CryptoError)?;
         
         let id = Uuid::new_v4().to_string();
         let now = SystemTime::now()
@@ -68,7 +85,7 @@ impl UserService {
             None => return Err(AppError::NotFound("用户不存在".to_string())),
         };
         
-        let device_count = SessionRepository::count_by_user_id(pool, user_id).await?;
+        let _device_count = SessionRepository::count_by_user_id(pool, user_id).await?;
         
         Ok(UserProfile {
             id: user.id,
@@ -85,6 +102,9 @@ impl UserService {
         username: &str, 
         email: &str
     ) -> Result<UserProfile, AppError> {
+        // 校验邮箱格式
+        validation::validate_email(email)?;
+        
         let user = match UserRepository::find_by_id(pool, user_id).await? {
             Some(user) => user,
             None => return Err(AppError::NotFound("用户不存在".to_string())),
@@ -118,7 +138,7 @@ impl UserService {
             updated_at: now,
         };
         
-        let device_count = SessionRepository::count_by_user_id(pool, user_id).await?;
+        let _device_count = SessionRepository::count_by_user_id(pool, user_id).await?;
         
         Ok(UserProfile {
             id: updated_user.id,
@@ -129,6 +149,81 @@ impl UserService {
         })
     }
     
+    // 注册前检查邮箱是否可用（未被注册）
+    // 为防止枚举账号：可通过设置整体关闭，有限流，并加入随机延迟
+    pub async fn email_available(pool: &SqlitePool, limiter: &RateLimiter, email: &str) -> Result<bool, AppError> {
+        if !SettingsService::get_email_check_enabled(pool).await? {
+            return Err(AppError::InvalidData("邮箱检查已关闭".to_string()));
+        }
+        
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        limiter.check(now).map_err(AppError::RateLimited)?;
+        
+        let delay = EMAIL_CHECK_MIN_DELAY_MS + rand::random::<u64>() % (EMAIL_CHECK_MAX_DELAY_MS - EMAIL_CHECK_MIN_DELAY_MS + 1);
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+        
+        validation::validate_email(email)?;
+        Ok(UserRepository::find_by_email(pool, email).await?.is_none())
+    }
+    
+    // 生成 6 位数字验证码，替换该邮箱之前的验证码
+    pub async fn generate_verification_code(pool: &SqlitePool, email: &str) -> Result<String, AppError> {
+        validation::validate_email(email)?;
+        
+        let code = format!("{:06}", rand::random::<u32>() % 1000000);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let expires_at = now + VERIFICATION_CODE_TTL_SECS;
+        
+        retry_on_busy(|| async {
+            sqlx::query(
+                "INSERT INTO verification_codes (email, code, created_at, expires_at)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(email) DO UPDATE SET
+                 code = excluded.code,
+                 created_at = excluded.created_at,
+                 expires_at = excluded.expires_at"
+            )
+            .bind(email)
+            .bind(&code)
+            .bind(now)
+            .bind(expires_at)
+            .execute(pool)
+            .await
+            .map_err(write_error)
+        }).await?;
+        
+        Ok(code)
+    }
+    
+    // 重新生成验证码（旧验证码随之失效）；距上次发送不足冷却时间时返回 RateLimited
+    pub async fn resend_verification_code(pool: &SqlitePool, email: &str) -> Result<String, AppError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        
+        let last_sent_at: Option<i64> = sqlx::query_scalar("SELECT created_at FROM verification_codes WHERE email = ?")
+            .bind(email)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        
+        if let Some(last_sent_at) = last_sent_at {
+            let retry_after = last_sent_at + VERIFICATION_RESEND_COOLDOWN_SECS - now;
+            if retry_after > 0 {
+                return Err(AppError::RateLimited(retry_after));
+            }
+        }
+        
+        Self::generate_verification_code(pool, email).await
+    }
+    
     // 验证验证码
     async fn verify_code(pool: &SqlitePool, email: &str, code: &str) -> Result<bool, AppError> {
         let now = SystemTime::now()
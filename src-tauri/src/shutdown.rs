@@ -0,0 +1,30 @@
+use std::time::Duration;
+use crate::AppState;
+use crate::service::task_registry::SYNC_LOOP_TASK;
+
+// 退出时推送未同步项目的最长等待时间（秒）
+pub const SHUTDOWN_FLUSH_TIMEOUT_SECS: u64 = 5;
+
+// 关闭流程：停止同步循环，在限定时间内推送未同步项目，断开连接，停止后台任务并关闭连接池
+// 推送或断开超时时直接继续，保证退出不被阻塞
+pub async fn graceful_shutdown(state: &AppState, flush_timeout: Duration) {
+    // 先停止同步循环，避免与下面的推送争用连接
+    let _ = state.tasks.stop(SYNC_LOOP_TASK).await;
+    
+    if let Some(manager) = state.sync_manager.lock().await.take() {
+        if manager.is_connected().await
+            && tokio::time::timeout(flush_timeout, manager.flush_unsynced_items(&state.db)).await.is_err()
+        {
+            eprintln!("退出前推送未同步项目超时");
+        }
+        
+        match tokio::time::timeout(flush_timeout, manager.disconnect()).await {
+            Ok(Err(e)) => eprintln!("断开同步服务器失败: {}", e),
+            Err(_) => eprintln!("断开同步服务器超时"),
+            Ok(Ok(())) => {}
+        }
+    }
+    
+    state.tasks.stop_all().await;
+    state.db.close().await;
+}
@@ -1,15 +1,252 @@
-use crate::{AppState, ClipboardItem, DbError};
+use crate::AppState;
+use crate::entity::clipboard_item::ClipboardItem;
+use crate::entity::tombstone::Tombstone;
+use crate::error::AppError;
+use crate::entity::change::{CHANGE_OP_ADD, CHANGE_OP_DELETE, CHANGE_OP_UPDATE};
+use crate::repository::change_repository::ChangeRepository;
+use crate::repository::clipboard_repository::ClipboardRepository;
+use crate::repository::tombstone_repository::{TombstoneRepository, TOMBSTONE_RETENTION_SECS};
+use crate::service::clipboard_service::ClipboardService;
+use crate::service::settings_service::SettingsService;
+use crate::service::sync_service::SyncService;
+use crate::util::crypto;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool, Row};  // 添加 Row trait 导入
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::Manager;
+use sqlx::{FromRow, SqlitePool, Row};  // 添加 Row trait 导入
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
 use tokio::net::TcpStream;
+use tokio::sync::broadcast;
 use tokio::sync::Mutex as TokioMutex;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message, WebSocketStream};
+use tokio_tungstenite::{connect_async, connect_async_tls_with_config, tungstenite::{protocol::Message, Error as WsError}, Connector, MaybeTlsStream, WebSocketStream};
 use uuid::Uuid;
 use futures_util::sink::SinkExt;
-use futures_util::stream::StreamExt;
+use futures_util::stream::{SplitSink, SplitStream, StreamExt};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// 单次发送的默认超时时间（秒）
+pub const DEFAULT_SEND_TIMEOUT_SECS: u64 = 10;
+
+// 本地变更推送的防抖窗口与最小间隔（毫秒）
+pub const PUSH_DEBOUNCE_MS: u64 = 500;
+pub const PUSH_MIN_INTERVAL_MS: u64 = 2000;
+
+// 小于该值的时钟偏差视为网络延迟，不做校正（秒）
+pub const CLOCK_SKEW_TOLERANCE_SECS: i64 = 2;
+
+// 压缩帧：JSON 超过阈值时 gzip 后以二进制帧发送，帧开头为标记
+// tokio-tungstenite 0.21 不支持 permessage-deflate，因此在应用层压缩
+pub const COMPRESSION_THRESHOLD_BYTES: usize = 8 * 1024;
+pub const COMPRESSED_FRAME_TAG: &[u8] = b"SCGZ";
+// 解压后的最大长度，防止压缩炸弹
+pub const MAX_INFLATED_BYTES: u64 = 64 * 1024 * 1024;
+
+// 证书固定校验失败时的错误前缀，便于调用方区分
+pub const CERT_PIN_MISMATCH: &str = "CERT_PIN_MISMATCH";
+
+// 默认的重连上限：连续失败次数与持续断开时长（秒），任一达到即放弃
+pub const DEFAULT_MAX_RECONNECT_ATTEMPTS: u32 = 30;
+pub const DEFAULT_MAX_RECONNECT_SECS: u64 = 60 * 60;
+// 重连退避的初始间隔与最长间隔（毫秒）
+pub const RECONNECT_BASE_DELAY_MS: u64 = 1000;
+pub const RECONNECT_MAX_DELAY_MS: u64 = 60_000;
+
+// 重连上限，字段为 None 时表示不限制
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    pub max_attempts: Option<u32>,
+    pub max_duration_secs: Option<u64>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(DEFAULT_MAX_RECONNECT_ATTEMPTS),
+            max_duration_secs: Some(DEFAULT_MAX_RECONNECT_SECS),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    // 连续失败 attempts 次、已断开 elapsed 后是否应放弃重连
+    pub fn is_exhausted(&self, attempts: u32, elapsed: Duration) -> bool {
+        let attempts_exceeded = self.max_attempts.is_some_and(|max| attempts >= max);
+        let duration_exceeded = self.max_duration_secs.is_some_and(|max| elapsed >= Duration::from_secs(max));
+        attempts_exceeded || duration_exceeded
+    }
+}
+
+// 连接状态通知中两次错误之间的默认最短间隔（毫秒）
+pub const STATUS_ERROR_MIN_INTERVAL_MS: u64 = 10_000;
+
+// 通过 sync_status_changed 事件通知前端的连接状态
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "state", content = "detail")]
+pub enum SyncStatus {
+    Connecting,
+    Connected,
+    Disconnected,
+    Error(String),
+    GaveUp(String),
+}
+
+// 连接状态通知的节流：相同状态不重复通知，连续的错误只通知第一次，
+// 错误通知之间至少间隔 min_error_interval，避免网络不稳定时刷屏
+pub struct StatusThrottle {
+    min_error_interval: Duration,
+    last: Option<SyncStatus>,
+    last_error_at: Option<Instant>,
+}
+
+impl StatusThrottle {
+    pub fn new(min_error_interval: Duration) -> Self {
+        Self {
+            min_error_interval,
+            last: None,
+            last_error_at: None,
+        }
+    }
+
+    // 是否应通知该状态，需要通知时记录为最近一次状态
+    pub fn should_emit(&mut self, status: &SyncStatus, now: Instant) -> bool {
+        if let SyncStatus::Error(_) = status {
+            if matches!(self.last, Some(SyncStatus::Error(_))) {
+                return false;
+            }
+            if self.last_error_at.is_some_and(|at| now.duration_since(at) < self.min_error_interval) {
+                return false;
+            }
+            self.last_error_at = Some(now);
+        } else if self.last.as_ref() == Some(status) {
+            return false;
+        }
+
+        self.last = Some(status.clone());
+        true
+    }
+}
+
+// 远程更新的合并窗口（毫秒）：窗口内相同 (id, updated_at) 的重复更新只合并一次
+pub const UPDATE_COALESCE_WINDOW_MS: u64 = 5000;
+
+// 记录正在合并或刚合并过的远程更新，多台设备同时推送时避免重复写入
+pub struct UpdateCoalescer {
+    window: Duration,
+    seen: Mutex<HashMap<(String, i64), Instant>>,
+}
+
+impl UpdateCoalescer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 登记一次更新，窗口内已登记过相同版本时返回 false
+    pub fn try_begin(&self, id: &str, version: i64) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, at| now.duration_since(*at) < self.window);
+
+        let key = (id.to_string(), version);
+        if seen.contains_key(&key) {
+            return false;
+        }
+        seen.insert(key, now);
+        true
+    }
+
+    // 合并失败时撤销登记，允许随后的重复更新重试
+    pub fn forget(&self, id: &str, version: i64) {
+        self.seen.lock().unwrap().remove(&(id.to_string(), version));
+    }
+}
+
+// 设备同步过滤：不向该设备同步的内容类型，支持 "image/*" 形式的通配
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DeviceSyncFilter {
+    #[serde(default)]
+    pub excluded_content_types: Vec<String>,
+}
+
+impl DeviceSyncFilter {
+    // 比较时忽略大小写和 "; charset=..." 等参数
+    pub fn excludes(&self, content_type: &str) -> bool {
+        let content_type = normalize_mime(content_type);
+        self.excluded_content_types.iter().any(|pattern| {
+            let pattern = normalize_mime(pattern);
+            match pattern.strip_suffix("/*") {
+                Some(prefix) => content_type.split('/').next() == Some(prefix),
+                None => pattern == content_type,
+            }
+        })
+    }
+}
+
+fn normalize_mime(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+// 远程修改与本地项目冲突时的合并策略
+// 本地有尚未推送的修改时才视为冲突，否则各策略都采用较新的远程修改
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    // 按更新时间，较新的一方保留
+    #[default]
+    LastWriteWins,
+    // 本地未推送的修改不被覆盖
+    LocalWins,
+    // 远程修改总是覆盖本地
+    RemoteWins,
+    // 不自动处理，为远程版本创建冲突副本
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeAction {
+    Apply,
+    Keep,
+    Conflict,
+}
+
+impl MergePolicy {
+    // local_dirty：本地项目有尚未推送的修改
+    pub fn resolve(&self, local_updated_at: i64, local_dirty: bool, remote_updated_at: i64) -> MergeAction {
+        if remote_updated_at == local_updated_at {
+            return MergeAction::Keep;
+        }
+        
+        let newer = remote_updated_at > local_updated_at;
+        match self {
+            MergePolicy::RemoteWins => MergeAction::Apply,
+            MergePolicy::LocalWins if local_dirty => MergeAction::Keep,
+            MergePolicy::Manual if local_dirty => MergeAction::Conflict,
+            _ if newer => MergeAction::Apply,
+            _ => MergeAction::Keep,
+        }
+    }
+}
+
+// 合并远程项目的结果，Conflict 中为新建的冲突副本
+#[derive(Debug, Clone)]
+pub enum MergeOutcome {
+    Applied,
+    Kept,
+    Conflict(Box<ClipboardItem>),
+}
+
+// sync_conflict 事件的内容
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncConflict {
+    pub item_id: String,
+    pub conflict_item: ClipboardItem,
+}
 
 // 设备信息结构体
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,8 +262,20 @@ pub enum SyncMessage {
     Connect {
         device_id: String,
         device_name: String,
+        // 告知服务器可以接收压缩帧
+        #[serde(default)]
+        accepts_compression: bool,
     },
     ItemUpdate(ClipboardItem),
+    // 带发送设备和发送时间的项目更新，用于估计设备间的时钟偏差
+    DeviceItemUpdate {
+        device_id: String,
+        sent_at: i64,
+        item: ClipboardItem,
+        // 按设备同步过滤不应接收该项目的设备
+        #[serde(default)]
+        excluded_devices: Vec<String>,
+    },
     ItemDelete {
         id: String,
     },
@@ -35,6 +284,17 @@ pub enum SyncMessage {
     },
     SyncResponse {
         items: Vec<ClipboardItem>,
+        // 回应请求的设备，批量插入的项目以它为来源；服务器转发时可能为空
+        #[serde(default)]
+        device_id: Option<String>,
+    },
+    TombstoneList {
+        tombstones: Vec<Tombstone>,
+    },
+    // 只发给指定设备的项目，由服务器（局域网模式下由对端）按 target_device_id 转发
+    DirectSend {
+        target_device_id: String,
+        item: ClipboardItem,
     },
     Error {
         code: String,
@@ -42,26 +302,163 @@ pub enum SyncMessage {
     },
 }
 
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
 // WebSocket连接管理器
+// 连接拆分为发送端和接收端，分别加锁：消息循环等待下一帧时不影响其他任务发送消息
 pub struct WebSocketManager {
-    ws_stream: TokioMutex<Option<WebSocketStream<TcpStream>>>,
+    ws_sink: TokioMutex<Option<SplitSink<WsStream, Message>>>,
+    ws_reader: TokioMutex<Option<SplitStream<WsStream>>>,
     device_id: String,
     device_name: String,
     server_url: String,
+    user_id: Option<String>, // 登录用户，只推送该用户的项目；未设置时不推送
     connected: TokioMutex<bool>,
     reconnect_attempts: TokioMutex<u32>,
+    reconnect_policy: ReconnectPolicy,
+    reconnect_base_delay: Duration,
+    gave_up: TokioMutex<bool>, // 达到重连上限后为 true，连接成功时重置
+    send_timeout: Duration,
+    pinned_cert: Option<String>, // 服务器证书的 SHA-256 指纹
+    sync_results: broadcast::Sender<Result<(), String>>,
+    coalescer: UpdateCoalescer,
+    compress_outgoing: AtomicBool, // 收到过服务器的压缩帧或手动开启后压缩发送的大消息
+    status: broadcast::Sender<SyncStatus>,
+    status_throttle: Mutex<StatusThrottle>,
+    last_received: Mutex<Option<Instant>>, // 最近一次收到服务器帧（包括协议层 ping）的时间
 }
 
 impl WebSocketManager {
     // 创建新的WebSocket管理器
     pub fn new(device_id: String, device_name: String, server_url: String) -> Self {
         Self {
-            ws_stream: TokioMutex::new(None),
+            ws_sink: TokioMutex::new(None),
+            ws_reader: TokioMutex::new(None),
             device_id,
             device_name,
             server_url,
+            user_id: None,
             connected: TokioMutex::new(false),
             reconnect_attempts: TokioMutex::new(0),
+            reconnect_policy: ReconnectPolicy::default(),
+            reconnect_base_delay: Duration::from_millis(RECONNECT_BASE_DELAY_MS),
+            gave_up: TokioMutex::new(false),
+            send_timeout: Duration::from_secs(DEFAULT_SEND_TIMEOUT_SECS),
+            pinned_cert: None,
+            sync_results: broadcast::channel(16).0,
+            coalescer: UpdateCoalescer::new(Duration::from_millis(UPDATE_COALESCE_WINDOW_MS)),
+            compress_outgoing: AtomicBool::new(false),
+            status: broadcast::channel(16).0,
+            status_throttle: Mutex::new(StatusThrottle::new(Duration::from_millis(STATUS_ERROR_MIN_INTERVAL_MS))),
+            last_received: Mutex::new(None),
+        }
+    }
+
+    // 设置两次错误状态通知之间的最短间隔
+    pub fn with_status_error_interval(self, interval: Duration) -> Self {
+        *self.status_throttle.lock().unwrap() = StatusThrottle::new(interval);
+        self
+    }
+
+    // 已知服务器支持压缩帧时直接开启压缩发送
+    pub fn with_compression(self, enabled: bool) -> Self {
+        self.compress_outgoing.store(enabled, Ordering::Relaxed);
+        self
+    }
+
+    // 设置单次发送超时时间
+    pub fn with_send_timeout(mut self, send_timeout: Duration) -> Self {
+        self.send_timeout = send_timeout;
+        self
+    }
+
+    // 设置固定的服务器证书指纹，设置后只接受指纹一致的证书
+    pub fn with_pinned_cert(mut self, pinned_cert: Option<String>) -> Self {
+        self.pinned_cert = pinned_cert;
+        self
+    }
+
+    // 设置重连上限
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    // 设置登录用户，推送和删除记录都限定为该用户
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    // 设置重连退避的初始间隔
+    pub fn with_reconnect_base_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_base_delay = delay;
+        self
+    }
+
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    // 订阅同步结果（每次收到 SyncResponse 或服务器错误时发送）
+    pub fn subscribe_sync_results(&self) -> broadcast::Receiver<Result<(), String>> {
+        self.sync_results.subscribe()
+    }
+
+    // 订阅经过节流的连接状态变化
+    pub fn subscribe_status(&self) -> broadcast::Receiver<SyncStatus> {
+        self.status.subscribe()
+    }
+
+    // 记录连接状态，节流后发送给订阅者
+    pub fn set_status(&self, status: SyncStatus) {
+        let emit = self.status_throttle.lock().unwrap().should_emit(&status, Instant::now());
+        if emit {
+            let _ = self.status.send(status);
+        }
+    }
+
+    // 当前是否处于连接状态
+    pub async fn is_connected(&self) -> bool {
+        *self.connected.lock().await
+    }
+
+    // 最近一次收到服务器帧的时间，只发送协议层 ping 的服务器同样视为存活
+    pub fn last_received(&self) -> Option<Instant> {
+        *self.last_received.lock().unwrap()
+    }
+
+    // 是否因达到重连上限而放弃
+    pub async fn has_given_up(&self) -> bool {
+        *self.gave_up.lock().await
+    }
+
+    // 按指数退避重连，直到连接成功或达到重连上限
+    pub async fn reconnect(&self) -> Result<(), String> {
+        let started = tokio::time::Instant::now();
+        let mut attempts: u32 = 0;
+        self.set_status(SyncStatus::Connecting);
+
+        loop {
+            let error = match self.connect().await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            eprintln!("Connection error: {}", error);
+            self.set_status(SyncStatus::Error(error.clone()));
+            attempts += 1;
+
+            if self.reconnect_policy.is_exhausted(attempts, started.elapsed()) {
+                *self.gave_up.lock().await = true;
+                let message = format!("Gave up reconnecting after {} attempts: {}", attempts, error);
+                self.set_status(SyncStatus::GaveUp(message.clone()));
+                return Err(message);
+            }
+
+            let delay = self.reconnect_base_delay
+                .saturating_mul(2u32.saturating_pow(attempts.min(16)))
+                .min(Duration::from_millis(RECONNECT_MAX_DELAY_MS));
+            tokio::time::sleep(delay).await;
         }
     }
 
@@ -75,17 +472,40 @@ impl WebSocketManager {
         let url = url::Url::parse(&self.server_url)
             .map_err(|e| format!("Invalid WebSocket URL: {}", e))?;
 
-        match connect_async(url).await {
+        let result = match &self.pinned_cert {
+            Some(_) => {
+                // 固定证书时以指纹校验代替 CA 校验，因此允许自签名证书
+                let connector = native_tls::TlsConnector::builder()
+                    .danger_accept_invalid_certs(true)
+                    .build()
+                    .map_err(|e| format!("Failed to build TLS connector: {}", e))?;
+                connect_async_tls_with_config(url, None, false, Some(Connector::NativeTls(connector))).await
+            }
+            None => connect_async(url).await,
+        };
+
+        match result {
             Ok((ws_stream, _)) => {
-                let mut stream_lock = self.ws_stream.lock().await;
-                *stream_lock = Some(ws_stream);
+                // 校验失败时直接丢弃连接，不发送任何数据
+                if let Some(pinned) = &self.pinned_cert {
+                    verify_pinned_cert(&ws_stream, pinned)?;
+                }
+
+                let (sink, reader) = ws_stream.split();
+                *self.ws_sink.lock().await = Some(sink);
+                *self.ws_reader.lock().await = Some(reader);
                 *connected = true;
                 *self.reconnect_attempts.lock().await = 0;
+                *self.gave_up.lock().await = false;
+                // 发送超时时 send_message 需要重新获取连接状态锁
+                drop(connected);
+                self.set_status(SyncStatus::Connected);
                 
                 // 发送连接消息
                 self.send_message(SyncMessage::Connect {
                     device_id: self.device_id.clone(),
                     device_name: self.device_name.clone(),
+                    accepts_compression: true,
                 }).await?
             }
             Err(e) => {
@@ -100,21 +520,54 @@ impl WebSocketManager {
 
     // 发送消息
     pub async fn send_message(&self, message: SyncMessage) -> Result<(), String> {
-        let json = serde_json::to_string(&message)
-            .map_err(|e| format!("Failed to serialize message: {}", e))?;
+        let frame = encode_frame(&message, self.compress_outgoing.load(Ordering::Relaxed))?;
 
-        let mut stream_lock = self.ws_stream.lock().await;
-        if let Some(stream) = &mut *stream_lock {
-            stream
-                .send(Message::Text(json))
-                .await
-                .map_err(|e| format!("Failed to send message: {}", e))?;
-            Ok(())
+        let mut sink_lock = self.ws_sink.lock().await;
+        if let Some(sink) = &mut *sink_lock {
+            match tokio::time::timeout(self.send_timeout, sink.send(frame)).await {
+                Ok(result) => {
+                    result.map_err(|e| format!("Failed to send message: {}", e))?;
+                    Ok(())
+                }
+                Err(_) => {
+                    // 半开连接：丢弃发送端并标记为断开，让消息循环重新连接
+                    *sink_lock = None;
+                    drop(sink_lock);
+                    *self.connected.lock().await = false;
+                    self.set_status(SyncStatus::Disconnected);
+                    Err(format!("Send timed out after {:?}", self.send_timeout))
+                }
+            }
         } else {
             Err("Not connected to WebSocket server".to_string())
         }
     }
 
+    // 读取下一帧：收到任何帧都记为连接存活，协议层 ping 立即回复 pong
+    // 只持有接收端的锁，等待期间其他任务仍可发送
+    pub async fn receive_frame(&self) -> Option<Result<Message, WsError>> {
+        let mut reader_lock = self.ws_reader.lock().await;
+        let frame = reader_lock.as_mut()?.next().await;
+        drop(reader_lock);
+
+        if let Some(Ok(message)) = &frame {
+            *self.last_received.lock().unwrap() = Some(Instant::now());
+
+            if let Message::Ping(payload) = message {
+                let mut sink_lock = self.ws_sink.lock().await;
+                if let Some(sink) = &mut *sink_lock {
+                    match tokio::time::timeout(self.send_timeout, sink.send(Message::Pong(payload.clone()))).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => eprintln!("Failed to send pong: {}", e),
+                        Err(_) => eprintln!("Pong timed out after {:?}", self.send_timeout),
+                    }
+                }
+            }
+        }
+
+        frame
+    }
+
     // 接收消息处理循环
     pub async fn start_message_loop(
         self: Arc<Self>,
@@ -122,61 +575,102 @@ impl WebSocketManager {
         app_handle: tauri::AppHandle,
     ) -> Result<(), String> {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        let mut last_push: Option<tokio::time::Instant> = None;
+
+        // 转发连接状态变化给前端，管理器释放后自动结束
+        let mut status_rx = self.subscribe_status();
+        let status_handle = app_handle.clone();
+        tokio::spawn(async move {
+            loop {
+                match status_rx.recv().await {
+                    Ok(status) => {
+                        let _ = status_handle.emit("sync_status_changed", status);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
 
         loop {
-            // 确保连接
+            // 确保连接，达到重连上限时通知前端并退出循环，由 retry_sync 手动重启
             if !*self.connected.lock().await {
-                if let Err(e) = self.connect().await {
-                    eprintln!("Connection error: {}", e);
-                    // 指数退避重连
-                    let attempts = *self.reconnect_attempts.lock().await;
-                    let delay = std::cmp::min(2u64.pow(attempts), 60) * 1000;
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
-                    continue;
+                if let Err(e) = self.recover(&app_state).await {
+                    let _ = app_handle.emit("sync_gave_up", e.clone());
+                    return Err(e);
                 }
+                last_push = Some(tokio::time::Instant::now());
             }
 
             tokio::select! {
                 _ = interval.tick() => {
+                    // 数据库维护期间暂停
+                    let _maintenance = app_state.maintenance_gate.read().await;
+                    
+                    // 免打扰时段暂停同步，每次都重新读取设置
+                    let quiet = SettingsService::is_quiet_now(&app_state.db).await.unwrap_or(false);
+                    
                     // 发送心跳或同步请求
-                    if *self.connected.lock().await {
-                        let last_sync = get_last_sync_timestamp(&app_state.db).await
-                            .unwrap_or(0);
-                        
-                        if let Err(e) = self.send_message(SyncMessage::SyncRequest {
-                            since_timestamp: last_sync,
-                        }).await {
+                    if !quiet && *self.connected.lock().await {
+                        if let Err(e) = self.sync_round(&app_state.db).await {
                             eprintln!("Failed to send sync request: {}", e);
                             *self.connected.lock().await = false;
+                        } else {
+                            last_push = Some(tokio::time::Instant::now());
                         }
                     }
                 }
                 
-                msg = async {
-                    let mut stream_lock = self.ws_stream.lock().await;
-                    if let Some(stream) = &mut *stream_lock {
-                        stream.next().await
-                    } else {
-                        None
+                _ = app_state.sync_notify.notified() => {
+                    // 防抖：等待一小段时间合并连续的本地变更，并限制推送频率
+                    let mut wait = tokio::time::Duration::from_millis(PUSH_DEBOUNCE_MS);
+                    if let Some(last) = last_push {
+                        let min_interval = tokio::time::Duration::from_millis(PUSH_MIN_INTERVAL_MS);
+                        wait = wait.max(min_interval.saturating_sub(last.elapsed()));
                     }
-                } => {
+                    tokio::time::sleep(wait).await;
+                    let _maintenance = app_state.maintenance_gate.read().await;
+                    
+                    // 连接断开或处于免打扰时段时跳过，由下一次心跳补推；没有待推送项目时也跳过
+                    let quiet = SettingsService::is_quiet_now(&app_state.db).await.unwrap_or(false);
+                    let pending = match &self.user_id {
+                        Some(user_id) => count_unsynced(&app_state.db, Some(user_id)).await.unwrap_or(1),
+                        None => 0,
+                    };
+                    if !quiet && pending > 0 && *self.connected.lock().await {
+                        self.push_unsynced_items(&app_state.db).await;
+                        last_push = Some(tokio::time::Instant::now());
+                    }
+                }
+                
+                msg = self.receive_frame() => {
+                    let _maintenance = app_state.maintenance_gate.read().await;
                     match msg {
-                        Some(Ok(Message::Text(text))) => {
-                            match serde_json::from_str::<SyncMessage>(&text) {
-                                Ok(sync_msg) => {
+                        Some(Ok(frame @ (Message::Text(_) | Message::Binary(_)))) => {
+                            // 服务器发来压缩帧说明支持压缩，之后的大消息也压缩发送
+                            if is_compressed_frame(&frame) {
+                                self.compress_outgoing.store(true, Ordering::Relaxed);
+                            }
+                            match decode_frame(frame) {
+                                Ok(Some(sync_msg)) => {
                                     self.handle_message(sync_msg, app_state.clone(), app_handle.clone()).await;
                                 }
+                                Ok(None) => {}
                                 Err(e) => {
                                     eprintln!("Failed to parse message: {}", e);
                                 }
                             }
                         }
+                        // ping 已在 receive_frame 中回复，ping 和 pong 都只用于确认连接存活
+                        Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
                         Some(Ok(Message::Close(_))) => {
                             *self.connected.lock().await = false;
+                            self.set_status(SyncStatus::Disconnected);
                             eprintln!("WebSocket connection closed");
                         }
                         Some(Err(e)) => {
                             *self.connected.lock().await = false;
+                            self.set_status(SyncStatus::Error(e.to_string()));
                             eprintln!("WebSocket error: {}", e);
                         }
                         _ => {}
@@ -186,6 +680,123 @@ impl WebSocketManager {
         }
     }
 
+    // 断开后重新连接，并立即同步一轮补上断开期间的变更，不必等到下一次心跳
+    // 只在达到重连上限时返回错误；同步失败时重新标记为断开，由消息循环再次恢复
+    pub async fn recover(&self, app_state: &AppState) -> Result<(), String> {
+        self.reconnect().await?;
+        
+        let _maintenance = app_state.maintenance_gate.read().await;
+        if SettingsService::is_quiet_now(&app_state.db).await.unwrap_or(false) {
+            return Ok(());
+        }
+        if let Err(e) = self.sync_round(&app_state.db).await {
+            eprintln!("Failed to sync after reconnect: {}", e);
+            *self.connected.lock().await = false;
+        }
+        Ok(())
+    }
+    
+    // 发送同步请求，并补推连接断开期间积压的本地变更和删除记录
+    async fn sync_round(&self, pool: &SqlitePool) -> Result<(), String> {
+        let last_sync = get_last_sync_timestamp(pool).await
+            .unwrap_or(0);
+        
+        self.send_message(SyncMessage::SyncRequest {
+            since_timestamp: last_sync,
+        }).await?;
+        self.push_unsynced_items(pool).await;
+        self.push_tombstones(pool, last_sync).await;
+        Ok(())
+    }
+
+    // 推送登录用户尚未同步的本地项目
+    async fn push_unsynced_items(&self, pool: &SqlitePool) {
+        let Some(user_id) = self.user_id.as_deref() else {
+            return;
+        };
+        let items = match get_unsynced_items(pool, Some(user_id), None).await {
+            Ok(items) => items,
+            Err(e) => {
+                eprintln!("Failed to load unsynced items: {:?}", e);
+                return;
+            }
+        };
+        
+        let filters = match get_device_sync_filters(pool).await {
+            Ok(filters) => filters,
+            Err(e) => {
+                eprintln!("Failed to load device sync filters: {:?}", e);
+                return;
+            }
+        };
+        
+        for item in items {
+            let id = item.id.clone();
+            let excluded_devices = excluded_devices_for(&filters, &item.content_type);
+            
+            // 所有绑定设备都过滤该类型时不再发送，直接标记为已同步以免反复重试
+            if !filters.is_empty() && excluded_devices.len() == filters.len() {
+                if let Err(e) = mark_item_synced(pool, &id).await {
+                    eprintln!("Failed to mark item {} synced: {:?}", id, e);
+                }
+                continue;
+            }
+            
+            let sent_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            let message = SyncMessage::DeviceItemUpdate {
+                device_id: self.device_id.clone(),
+                sent_at,
+                item,
+                excluded_devices,
+            };
+            if let Err(e) = self.send_message(message).await {
+                eprintln!("Failed to push item {}: {}", id, e);
+                return;
+            }
+            if let Err(e) = mark_item_synced(pool, &id).await {
+                eprintln!("Failed to mark item {} synced: {:?}", id, e);
+            }
+        }
+    }
+
+    // 立即推送所有未同步的项目（用于退出前）
+    pub async fn flush_unsynced_items(&self, pool: &SqlitePool) {
+        self.push_unsynced_items(pool).await;
+    }
+
+    // 推送登录用户上次同步之后的删除记录，并清理过期记录
+    async fn push_tombstones(&self, pool: &SqlitePool, since: i64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        if let Err(e) = TombstoneRepository::prune_before(pool, now - TOMBSTONE_RETENTION_SECS).await {
+            eprintln!("Failed to prune tombstones: {:?}", e);
+        }
+        
+        let Some(user_id) = self.user_id.as_deref() else {
+            return;
+        };
+        let tombstones = match TombstoneRepository::find_all_by_user_since(pool, user_id, since).await {
+            Ok(tombstones) => tombstones,
+            Err(e) => {
+                eprintln!("Failed to load tombstones: {:?}", e);
+                return;
+            }
+        };
+        
+        if tombstones.is_empty() {
+            return;
+        }
+        
+        if let Err(e) = self.send_message(SyncMessage::TombstoneList { tombstones }).await {
+            eprintln!("Failed to push tombstones: {}", e);
+        }
+    }
+
     // 处理接收到的消息
     async fn handle_message(
         &self,
@@ -195,15 +806,28 @@ impl WebSocketManager {
     ) {
         match message {
             SyncMessage::ItemUpdate(item) => {
+                if !self.accepts_remote_item(&app_state.db, &item, &[]).await {
+                    return;
+                }
+                
                 // 处理项目更新
-                match sync_item_from_remote(&app_state.db, item.clone()).await {
-                    Ok(_) => {
-                        // 更新缓存
-                        crate::cache_system::add_to_cache(&app_state.cache_queue, item.clone());
-                        
-                        // 通知前端
-                        let _ = app_handle.emit("remote_item_update", item);
+                match self.merge_remote_item(&app_state.db, None, item.clone()).await {
+                    Ok(Some(outcome)) => self.report_merge(outcome, item, &app_state, &app_handle, true),
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("Failed to sync remote item: {:?}", e);
                     }
+                }
+            }
+            SyncMessage::DeviceItemUpdate { device_id, sent_at, item, excluded_devices } => {
+                if !self.accepts_remote_item(&app_state.db, &item, &excluded_devices).await {
+                    return;
+                }
+                
+                // 记录发送设备的时钟偏差并按校正后的时间合并
+                match self.merge_remote_item(&app_state.db, Some((&device_id, sent_at)), item.clone()).await {
+                    Ok(Some(outcome)) => self.report_merge(outcome, item, &app_state, &app_handle, true),
+                    Ok(None) => {}
                     Err(e) => {
                         eprintln!("Failed to sync remote item: {:?}", e);
                     }
@@ -211,10 +835,16 @@ impl WebSocketManager {
             }
             SyncMessage::ItemDelete { id } => {
                 // 处理项目删除
-                match delete_synced_item(&app_state.db, &id).await {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64;
+                
+                // 删除消息中没有所属用户，本地不存在的项目按登录用户记录删除
+                match apply_remote_delete(&app_state.db, &id, self.user_id.as_deref(), now).await {
                     Ok(_) => {
                         // 从缓存中移除
-                        crate::cache_system::remove_from_cache(&app_state.cache_queue, &id);
+                        app_state.recent_items.remove(&id);
                         
                         // 通知前端
                         let _ = app_handle.emit("remote_item_delete", id);
@@ -224,14 +854,51 @@ impl WebSocketManager {
                     }
                 }
             }
-            SyncMessage::SyncResponse { items } => {
-                // 处理同步响应
+            SyncMessage::TombstoneList { tombstones } => {
+                // 删除离线期间在其他设备上被删除的项目
+                match apply_tombstones(&app_state.db, &tombstones).await {
+                    Ok(deleted_ids) => {
+                        for id in deleted_ids {
+                            app_state.recent_items.remove(&id);
+                            let _ = app_handle.emit("remote_item_delete", id);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to apply tombstones: {:?}", e);
+                    }
+                }
+            }
+            SyncMessage::DirectSend { target_device_id, item } => {
+                // 用户主动发送的项目，不经过本设备的同步过滤
+                match self.receive_direct_item(&app_state.db, &target_device_id, item.clone()).await {
+                    Ok(Some(outcome)) => self.report_merge(outcome, item, &app_state, &app_handle, true),
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("Failed to apply direct item: {:?}", e);
+                    }
+                }
+            }
+            SyncMessage::SyncResponse { items, device_id } => {
+                // 处理同步响应：本地不存在的项目在一个事务中批量插入，其余项目逐个按合并策略处理
+                let mut accepted = Vec::new();
                 for item in items {
-                    if let Err(e) = sync_item_from_remote(&app_state.db, item.clone()).await {
-                        eprintln!("Failed to sync item: {:?}", e);
-                    } else {
-                        // 更新缓存
-                        crate::cache_system::add_to_cache(&app_state.cache_queue, item.clone());
+                    if self.accepts_remote_item(&app_state.db, &item, &[]).await {
+                        accepted.push(item);
+                    }
+                }
+                
+                let (inserted, remaining) = self.insert_new_remote_items(&app_state.db, device_id.as_deref(), accepted).await;
+                for item in inserted {
+                    self.report_merge(MergeOutcome::Applied, item, &app_state, &app_handle, false);
+                }
+                
+                for item in remaining {
+                    match self.merge_remote_item(&app_state.db, None, item.clone()).await {
+                        Ok(Some(outcome)) => self.report_merge(outcome, item, &app_state, &app_handle, false),
+                        Ok(None) => {}
+                        Err(e) => {
+                            eprintln!("Failed to sync item: {:?}", e);
+                        }
                     }
                 }
                 
@@ -245,16 +912,154 @@ impl WebSocketManager {
                 
                 // 通知前端刷新
                 let _ = app_handle.emit("sync_completed", ());
+                let _ = self.sync_results.send(Ok(()));
             }
             SyncMessage::Error { code, message } => {
                 eprintln!("Received error from server: {} - {}", code, message);
                 // 通知前端显示错误
                 let _ = app_handle.emit("sync_error", format!("{}: {}", code, message));
+                let _ = self.sync_results.send(Err(format!("{}: {}", code, message)));
             }
             _ => {}
         }
     }
 
+    // 合并远程项目，sender 为发送设备及发送时间；窗口内重复的更新直接跳过并返回 None
+    pub async fn merge_remote_item(
+        &self,
+        pool: &SqlitePool,
+        sender: Option<(&str, i64)>,
+        item: ClipboardItem,
+    ) -> Result<Option<MergeOutcome>, AppError> {
+        let id = item.id.clone();
+        let version = item.updated_at;
+        if !self.coalescer.try_begin(&id, version) {
+            return Ok(None);
+        }
+
+        let result = match sender {
+            Some((device_id, sent_at)) => apply_remote_update(pool, device_id, sent_at, item).await,
+            None => sync_item_from_remote(pool, item, 0, None).await,
+        };
+        if result.is_err() {
+            self.coalescer.forget(&id, version);
+        }
+        result.map(Some)
+    }
+
+    // 处理定向发送的项目：目标不是本设备时忽略（转发错误），否则按正常流程合并
+    pub async fn receive_direct_item(
+        &self,
+        pool: &SqlitePool,
+        target_device_id: &str,
+        item: ClipboardItem,
+    ) -> Result<Option<MergeOutcome>, AppError> {
+        if target_device_id != self.device_id {
+            return Ok(None);
+        }
+
+        self.merge_remote_item(pool, None, item).await
+    }
+
+    // 批量插入本地不存在的远程项目，返回插入的项目和仍需逐个合并的项目；origin 为发送项目的设备
+    // 同一批中重复的 ID 只批量插入第一条，其余留给合并流程；批量插入失败时全部改为逐个合并
+    pub async fn insert_new_remote_items(
+        &self,
+        pool: &SqlitePool,
+        origin: Option<&str>,
+        items: Vec<ClipboardItem>,
+    ) -> (Vec<ClipboardItem>, Vec<ClipboardItem>) {
+        let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+        let existing = match ClipboardRepository::find_existing_ids(pool, &ids).await {
+            Ok(existing) => existing,
+            Err(e) => {
+                eprintln!("Failed to check existing items: {:?}", e);
+                return (Vec::new(), items);
+            }
+        };
+        // 有删除记录的项目由合并流程比较删除时间，避免已删除的项目被重新插入
+        let deleted = match TombstoneRepository::find_deleted_ids(pool, &ids).await {
+            Ok(deleted) => deleted,
+            Err(e) => {
+                eprintln!("Failed to check tombstones: {:?}", e);
+                return (Vec::new(), items);
+            }
+        };
+        
+        let mut seen = HashSet::new();
+        let mut new_items = Vec::new();
+        let mut remaining = Vec::new();
+        for item in items {
+            if existing.contains(&item.id) || deleted.contains(&item.id) || !seen.insert(item.id.clone()) {
+                remaining.push(item);
+            } else if self.coalescer.try_begin(&item.id, item.updated_at) {
+                new_items.push(item);
+            }
+        }
+        
+        if new_items.is_empty() {
+            return (new_items, remaining);
+        }
+        
+        match insert_remote_items(pool, &new_items, origin).await {
+            Ok(()) => {
+                // 项目已提交，刷新派生数据失败只记录日志
+                for item in &mut new_items {
+                    if let Err(e) = ClipboardService::refresh_synced_item(pool, item).await {
+                        eprintln!("Failed to refresh synced item {}: {:?}", item.id, e);
+                    }
+                }
+                (new_items, remaining)
+            }
+            Err(e) => {
+                eprintln!("Failed to insert synced items in batch: {:?}", e);
+                for item in &new_items {
+                    self.coalescer.forget(&item.id, item.updated_at);
+                }
+                new_items.extend(remaining);
+                (Vec::new(), new_items)
+            }
+        }
+    }
+    
+    // 更新缓存并通知前端；冲突时保留本地版本，只通知冲突副本
+    fn report_merge(
+        &self,
+        outcome: MergeOutcome,
+        item: ClipboardItem,
+        app_state: &AppState,
+        app_handle: &tauri::AppHandle,
+        emit_update: bool,
+    ) {
+        match outcome {
+            MergeOutcome::Applied => {
+                app_state.recent_items.add(item.clone());
+                if emit_update {
+                    let _ = app_handle.emit("remote_item_update", item);
+                }
+            }
+            MergeOutcome::Kept => {}
+            MergeOutcome::Conflict(conflict_item) => {
+                app_state.recent_items.add((*conflict_item).clone());
+                let _ = app_handle.emit("sync_conflict", SyncConflict {
+                    item_id: item.id,
+                    conflict_item: *conflict_item,
+                });
+            }
+        }
+    }
+
+    // 合并前检查本设备的同步过滤，读取失败时按未过滤处理
+    async fn accepts_remote_item(&self, pool: &SqlitePool, item: &ClipboardItem, excluded_devices: &[String]) -> bool {
+        match accepts_remote_item(pool, &self.device_id, item, excluded_devices).await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("Failed to check device sync filter: {:?}", e);
+                true
+            }
+        }
+    }
+
     // 断开连接
     pub async fn disconnect(&self) -> Result<(), String> {
         let mut connected = self.connected.lock().await;
@@ -262,28 +1067,104 @@ impl WebSocketManager {
             return Ok(());
         }
 
-        let mut stream_lock = self.ws_stream.lock().await;
-        if let Some(stream) = &mut *stream_lock {
-            stream
-                .close(None)
+        let mut sink_lock = self.ws_sink.lock().await;
+        if let Some(sink) = &mut *sink_lock {
+            sink
+                .close()
                 .await
                 .map_err(|e| format!("Failed to close connection: {}", e))?;
         }
 
-        *stream_lock = None;
+        *sink_lock = None;
+        // 消息循环可能正在等待接收端，此时由它读到关闭帧后结束，下次连接时替换
+        if let Ok(mut reader_lock) = self.ws_reader.try_lock() {
+            *reader_lock = None;
+        }
         *connected = false;
+        self.set_status(SyncStatus::Disconnected);
         Ok(())
     }
 }
 
+// 校验服务器证书指纹是否与固定值一致
+fn verify_pinned_cert(
+    stream: &WsStream,
+    pinned: &str,
+) -> Result<(), String> {
+    let der = match stream.get_ref() {
+        MaybeTlsStream::NativeTls(tls) => tls
+            .get_ref()
+            .peer_certificate()
+            .and_then(|cert| cert.map(|cert| cert.to_der()).transpose())
+            .map_err(|e| format!("{}: failed to read server certificate: {}", CERT_PIN_MISMATCH, e))?,
+        _ => None,
+    };
+
+    let der = der.ok_or_else(|| format!("{}: server did not present a TLS certificate", CERT_PIN_MISMATCH))?;
+    let fingerprint = crypto::sha256_fingerprint(&der);
+    if fingerprint != pinned {
+        return Err(format!(
+            "{}: expected {}, got {}",
+            CERT_PIN_MISMATCH, pinned, fingerprint
+        ));
+    }
+
+    Ok(())
+}
+
+// 将消息编码为 WebSocket 帧：compress 为 true 且 JSON 超过阈值时发送压缩的二进制帧
+pub fn encode_frame(message: &SyncMessage, compress: bool) -> Result<Message, String> {
+    let json = serde_json::to_string(message)
+        .map_err(|e| format!("Failed to serialize message: {}", e))?;
+
+    if !compress || json.len() < COMPRESSION_THRESHOLD_BYTES {
+        return Ok(Message::Text(json));
+    }
+
+    let mut encoder = GzEncoder::new(COMPRESSED_FRAME_TAG.to_vec(), Compression::default());
+    encoder.write_all(json.as_bytes())
+        .map_err(|e| format!("Failed to compress message: {}", e))?;
+    let frame = encoder.finish()
+        .map_err(|e| format!("Failed to compress message: {}", e))?;
+
+    Ok(Message::Binary(frame))
+}
+
+pub fn is_compressed_frame(frame: &Message) -> bool {
+    matches!(frame, Message::Binary(data) if data.starts_with(COMPRESSED_FRAME_TAG))
+}
+
+// 解析文本帧或压缩的二进制帧，其他帧返回 None
+pub fn decode_frame(frame: Message) -> Result<Option<SyncMessage>, String> {
+    let json = match frame {
+        Message::Text(text) => text,
+        Message::Binary(data) if data.starts_with(COMPRESSED_FRAME_TAG) => {
+            let mut json = String::new();
+            GzDecoder::new(&data[COMPRESSED_FRAME_TAG.len()..])
+                .take(MAX_INFLATED_BYTES + 1)
+                .read_to_string(&mut json)
+                .map_err(|e| format!("Failed to inflate message: {}", e))?;
+            if json.len() as u64 > MAX_INFLATED_BYTES {
+                return Err("Inflated message too large".to_string());
+            }
+            json
+        }
+        _ => return Ok(None),
+    };
+
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse message: {}", e))
+}
+
 // 数据同步相关的数据库操作
 
 // 获取最后同步时间戳
-async fn get_last_sync_timestamp(pool: &SqlitePool) -> Result<i64, DbError> {
+pub async fn get_last_sync_timestamp(pool: &SqlitePool) -> Result<i64, AppError> {
     let result = sqlx::query!("SELECT value FROM user_settings WHERE key = 'last_sync_timestamp'")
         .fetch_optional(pool)
         .await
-        .map_err(|e| DbError::QueryError(e.to_string()))?;
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     match result {
         Some(row) => Ok(row.value.parse::<i64>().unwrap_or(0)),
@@ -292,7 +1173,7 @@ async fn get_last_sync_timestamp(pool: &SqlitePool) -> Result<i64, DbError> {
 }
 
 // 更新最后同步时间戳
-async fn update_last_sync_timestamp(pool: &SqlitePool, timestamp: i64) -> Result<(), DbError> {
+pub async fn update_last_sync_timestamp(pool: &SqlitePool, timestamp: i64) -> Result<(), AppError> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -311,153 +1192,419 @@ async fn update_last_sync_timestamp(pool: &SqlitePool, timestamp: i64) -> Result
     .bind(now)
     .execute(pool)
     .await
-    .map_err(|e| DbError::QueryError(e.to_string()))?;
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     Ok(())
 }
 
-// 从远程同步项目
-async fn sync_item_from_remote(pool: &SqlitePool, item: ClipboardItem) -> Result<(), DbError> {
+// 记录设备时钟偏差（设备时间减去本地时间），返回校正时使用的偏差
+pub async fn record_device_skew(
+    pool: &SqlitePool,
+    device_id: &str,
+    sent_at: i64,
+    received_at: i64,
+) -> Result<i64, AppError> {
+    let observed = sent_at - received_at;
+    let skew = if observed.abs() <= CLOCK_SKEW_TOLERANCE_SECS { 0 } else { observed };
+
+    sqlx::query(
+        "
+        INSERT INTO device_clock_skew (device_id, skew_secs, observed_at)
+        VALUES (?, ?, ?)
+        ON CONFLICT(device_id) DO UPDATE SET
+        skew_secs = excluded.skew_secs,
+        observed_at = excluded.observed_at
+        "
+    )
+    .bind(device_id)
+    .bind(skew)
+    .bind(received_at)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(skew)
+}
+
+// 获取设备最近一次观测到的时钟偏差，未观测过时为 0
+pub async fn get_device_skew(pool: &SqlitePool, device_id: &str) -> Result<i64, AppError> {
+    let skew = sqlx::query_scalar::<_, i64>("SELECT skew_secs FROM device_clock_skew WHERE device_id = ?")
+        .bind(device_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(skew.unwrap_or(0))
+}
+
+// 应用带设备信息的远程更新：先根据发送时间记录时钟偏差，再按校正后的时间合并
+pub async fn apply_remote_update(
+    pool: &SqlitePool,
+    device_id: &str,
+    sent_at: i64,
+    item: ClipboardItem,
+) -> Result<MergeOutcome, AppError> {
+    let received_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let skew = record_device_skew(pool, device_id, sent_at, received_at).await?;
+    sync_item_from_remote(pool, item, skew, Some(device_id)).await
+}
+
+// 从远程同步项目，skew 为发送设备相对本地的时钟偏差，origin 为发送设备（未知时为 None）
+// 比较前将远程时间换算为本地时间，并以换算后的时间保存，保证后续比较使用同一时钟
+// 已存在的项目按合并策略决定覆盖、保留或创建冲突副本
+async fn sync_item_from_remote(
+    pool: &SqlitePool,
+    mut item: ClipboardItem,
+    skew: i64,
+    origin: Option<&str>,
+) -> Result<MergeOutcome, AppError> {
+    item.updated_at -= skew;
+    item.created_at -= skew;
+
     // 检查项目是否已存在
     let existing = sqlx::query!("SELECT id, updated_at FROM clipboard_items WHERE id = ?", item.id)
         .fetch_optional(pool)
         .await
-        .map_err(|e| DbError::QueryError(e.to_string()))?;
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-    match existing {
-        Some(row) => {
-            // 如果远程项目更新时间更新，则更新本地项目
-            if item.updated_at > row.updated_at {
-                sqlx::query(
-                    "
-                    UPDATE clipboard_items SET
-                    content = ?,
-                    title = ?,
-                    updated_at = ?,
-                    is_pinned = ?
-                    WHERE id = ?
-                    "
-                )
-                .bind(&item.content)
-                .bind(&item.title)
-                .bind(item.updated_at)
-                .bind(item.is_pinned as i64)
-                .bind(&item.id)
-                .execute(pool)
-                .await
-                .map_err(|e| DbError::QueryError(e.to_string()))?;
-
-                // 更新同步状态
-                sqlx::query(
-                    "
-                    UPDATE sync_status SET
-                    is_synced = 1,
-                    last_sync_attempt = ?
-                    WHERE item_id = ?
-                    "
-                )
-                .bind(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64)
-                .bind(&item.id)
-                .execute(pool)
-                .await
-                .map_err(|e| DbError::QueryError(e.to_string()))?;
-            }
-        }
+    let row = match existing {
+        Some(row) => row,
         None => {
+            // 删除消息可能先于更新到达：删除时间不早于该版本时不再插入，避免项目复活
+            if let Some(deleted_at) = TombstoneRepository::find_deleted_at(pool, &item.id).await? {
+                if deleted_at >= item.updated_at {
+                    return Ok(MergeOutcome::Kept);
+                }
+            }
+
             // 如果项目不存在，则插入新项目
+            insert_remote_item(pool, &item, origin).await?;
+            ClipboardService::refresh_synced_item(pool, &mut item).await?;
+            return Ok(MergeOutcome::Applied);
+        }
+    };
+
+    let policy = SyncService::get_merge_policy(pool).await?;
+    let local_dirty = has_unsynced_changes(pool, &item.id).await?;
+    match policy.resolve(row.updated_at, local_dirty, item.updated_at) {
+        MergeAction::Keep => Ok(MergeOutcome::Kept),
+        MergeAction::Conflict => {
+            // 冲突副本只保存在本设备，由用户决定保留哪个版本
+            let mut copy = item.clone();
+            copy.id = Uuid::new_v4().to_string();
+            insert_remote_item(pool, &copy, origin).await?;
+            ClipboardService::refresh_synced_item(pool, &mut copy).await?;
+            Ok(MergeOutcome::Conflict(Box::new(copy)))
+        }
+        MergeAction::Apply => {
+            // 二进制内容以原始字节保存到 content_blob
+            // 旧的明文哈希在同一条语句中清空，刷新前合并重复项目也不会按旧哈希误删
+            let payload = item.payload();
+            let (content, content_blob) = payload.columns();
+
             sqlx::query(
                 "
-                INSERT INTO clipboard_items (id, content, title, created_at, updated_at, is_pinned)
-                VALUES (?, ?, ?, ?, ?, ?)
+                UPDATE clipboard_items SET
+                content = ?,
+                content_blob = ?,
+                content_type = ?,
+                encrypted = ?,
+                updated_at = ?,
+                raw_content = ?,
+                is_sensitive = ?,
+                note = ?,
+                origin_device_id = ?,
+                content_hash = NULL
+                WHERE id = ?
                 "
             )
-            .bind(&item.id)
-            .bind(&item.content)
-            .bind(&item.title)
-            .bind(item.created_at)
+            .bind(content)
+            .bind(content_blob)
+            .bind(&item.content_type)
+            .bind(item.encrypted as i32)
             .bind(item.updated_at)
-            .bind(item.is_pinned as i64)
+            .bind(&item.raw_content)
+            .bind(item.is_sensitive as i32)
+            .bind(&item.note)
+            .bind(origin)
+            .bind(&item.id)
             .execute(pool)
             .await
-            .map_err(|e| DbError::QueryError(e.to_string()))?;
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            append_change(pool, &item.user_id, CHANGE_OP_UPDATE, &item.id).await?;
 
-            // 创建同步状态记录
+            // 更新同步状态
             sqlx::query(
                 "
-                INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
-                VALUES (?, 1, ?)
+                UPDATE sync_status SET
+                is_synced = 1,
+                last_sync_attempt = ?
+                WHERE item_id = ?
                 "
             )
-            .bind(&item.id)
             .bind(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64)
+            .bind(&item.id)
             .execute(pool)
             .await
-            .map_err(|e| DbError::QueryError(e.to_string()))?;
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            ClipboardService::refresh_synced_item(pool, &mut item).await?;
+
+            Ok(MergeOutcome::Applied)
         }
     }
+}
+
+// 插入远程项目并标记为已同步
+async fn insert_remote_item(pool: &SqlitePool, item: &ClipboardItem, origin: Option<&str>) -> Result<(), AppError> {
+    let payload = item.payload();
+    let (content, content_blob) = payload.columns();
+
+    sqlx::query(
+        "
+        INSERT INTO clipboard_items (id, user_id, content, content_blob, content_type, encrypted, created_at, updated_at, raw_content, source_app, is_sensitive, note, origin_device_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "
+    )
+    .bind(&item.id)
+    .bind(&item.user_id)
+    .bind(content)
+    .bind(content_blob)
+    .bind(&item.content_type)
+    .bind(item.encrypted as i32)
+    .bind(item.created_at)
+    .bind(item.updated_at)
+    .bind(&item.raw_content)
+    .bind(&item.source_app)
+    .bind(item.is_sensitive as i32)
+    .bind(&item.note)
+    .bind(origin)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    append_change(pool, &item.user_id, CHANGE_OP_ADD, &item.id).await?;
+
+    // 创建同步状态记录
+    sqlx::query(
+        "
+        INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
+        VALUES (?, 1, ?)
+        "
+    )
+    .bind(&item.id)
+    .bind(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
+
+// 在一个事务中批量插入远程项目并标记为已同步，origin 为发送项目的设备
+async fn insert_remote_items(pool: &SqlitePool, items: &[ClipboardItem], origin: Option<&str>) -> Result<(), AppError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let mut tx = pool.begin()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    ClipboardRepository::save_many_in(&mut tx, items, origin).await?;
+
+    for item in items {
+        sqlx::query(
+            "
+            INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
+            VALUES (?, 1, ?)
+            "
+        )
+        .bind(&item.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     Ok(())
 }
 
-// 删除同步项目
-async fn delete_synced_item(pool: &SqlitePool, id: &str) -> Result<(), DbError> {
+// 本地项目是否有尚未推送的修改，没有同步状态记录时视为没有
+async fn has_unsynced_changes(pool: &SqlitePool, item_id: &str) -> Result<bool, AppError> {
+    let is_synced = sqlx::query_scalar::<_, i64>("SELECT is_synced FROM sync_status WHERE item_id = ?")
+        .bind(item_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(is_synced == Some(0))
+}
+
+// 追加远程变更对应的变更记录
+async fn append_change(pool: &SqlitePool, user_id: &str, op: &str, item_id: &str) -> Result<(), AppError> {
+    let mut conn = pool.acquire()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    ChangeRepository::append(&mut conn, user_id, op, item_id).await?;
+
+    Ok(())
+}
+
+// 删除同步项目，并记录删除时间
+// 本地不存在的项目同样以 owner 为所属用户写入删除记录，之后迟到的更新不会让它复活；owner 未知时无法归属，不记录
+async fn delete_synced_item(pool: &SqlitePool, id: &str, owner: Option<&str>, deleted_at: i64) -> Result<bool, AppError> {
     // 检查项目是否存在
-    let exists = sqlx::query!("SELECT id FROM clipboard_items WHERE id = ?", id)
+    let existing = sqlx::query("SELECT user_id FROM clipboard_items WHERE id = ?")
+        .bind(id)
         .fetch_optional(pool)
         .await
-        .map_err(|e| DbError::QueryError(e.to_string()))?;
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-    if exists.is_none() {
-        return Ok(());
-    }
+    let user_id: String = match existing {
+        Some(row) => row.get("user_id"),
+        None => {
+            let mut conn = pool.acquire()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            if let Some(owner) = owner {
+                TombstoneRepository::record(&mut conn, id, owner, deleted_at).await?;
+            }
+            return Ok(false);
+        }
+    };
+
+    let mut tx = pool.begin()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     // 删除项目
     sqlx::query("DELETE FROM clipboard_items WHERE id = ?")
         .bind(id)
-        .execute(pool)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    TombstoneRepository::record(&mut tx, id, &user_id, deleted_at).await?;
+    ChangeRepository::append(&mut tx, &user_id, CHANGE_OP_DELETE, id).await?;
+
+    tx.commit()
         .await
-        .map_err(|e| DbError::QueryError(e.to_string()))?;
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     // 同步状态表会通过外键级联删除
 
-    Ok(())
+    Ok(true)
+}
+
+// 应用单条远程删除消息，返回本地是否删除了项目；消息中没有所属用户，由调用方传入登录用户
+pub async fn apply_remote_delete(pool: &SqlitePool, id: &str, owner: Option<&str>, deleted_at: i64) -> Result<bool, AppError> {
+    delete_synced_item(pool, id, owner, deleted_at).await
+}
+
+// 应用远程删除记录，返回本地实际删除的项目 id
+pub async fn apply_tombstones(pool: &SqlitePool, tombstones: &[Tombstone]) -> Result<Vec<String>, AppError> {
+    let mut deleted_ids = Vec::new();
+    for tombstone in tombstones {
+        if delete_synced_item(pool, &tombstone.item_id, Some(&tombstone.user_id), tombstone.deleted_at).await? {
+            deleted_ids.push(tombstone.item_id.clone());
+        }
+    }
+
+    Ok(deleted_ids)
+}
+
+// 读取所有绑定设备的同步过滤，没有过滤设置的设备使用空过滤
+pub async fn get_device_sync_filters(pool: &SqlitePool) -> Result<Vec<(String, DeviceSyncFilter)>, AppError> {
+    let devices = get_bound_devices(pool).await?;
+    let mut filters = Vec::with_capacity(devices.len());
+    for device in devices {
+        let filter = SyncService::get_device_sync_filter(pool, &device.device_id).await?;
+        filters.push((device.device_id, filter));
+    }
+    Ok(filters)
 }
 
-// 获取未同步的项目
-pub async fn get_unsynced_items(pool: &SqlitePool, limit: Option<i64>) -> Result<Vec<ClipboardItem>, DbError> {
+// 返回过滤了该内容类型的设备
+pub fn excluded_devices_for(filters: &[(String, DeviceSyncFilter)], content_type: &str) -> Vec<String> {
+    filters
+        .iter()
+        .filter(|(_, filter)| filter.excludes(content_type))
+        .map(|(device_id, _)| device_id.clone())
+        .collect()
+}
+
+// 远程项目是否应合并到本设备：发送方标记排除本设备，或本设备的过滤包含该类型时跳过
+pub async fn accepts_remote_item(
+    pool: &SqlitePool,
+    device_id: &str,
+    item: &ClipboardItem,
+    excluded_devices: &[String],
+) -> Result<bool, AppError> {
+    if excluded_devices.iter().any(|excluded| excluded == device_id) {
+        return Ok(false);
+    }
+
+    let filter = SyncService::get_device_sync_filter(pool, device_id).await?;
+    Ok(!filter.excludes(&item.content_type))
+}
+
+// 获取未同步的项目，user_id 为 None 时包含所有用户
+pub async fn get_unsynced_items(pool: &SqlitePool, user_id: Option<&str>, limit: Option<i64>) -> Result<Vec<ClipboardItem>, AppError> {
     let limit = limit.unwrap_or(50);
 
     let items = sqlx::query(
         "
-        SELECT c.id, c.content, c.title, c.created_at, c.updated_at, c.is_pinned
+        SELECT c.id, c.user_id, c.content, c.content_blob, c.content_type, c.encrypted, c.created_at, c.updated_at, c.raw_content, c.source_app, c.is_sensitive, c.note
         FROM clipboard_items c
         JOIN sync_status s ON c.id = s.item_id
-        WHERE s.is_synced = 0
+        WHERE s.is_synced = 0 AND (? IS NULL OR c.user_id = ?)
         LIMIT ?
         "
     )
+    .bind(user_id)
+    .bind(user_id)
     .bind(limit)
     .fetch_all(pool)
     .await
-    .map_err(|e| DbError::QueryError(e.to_string()))?;
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     let mut result = Vec::with_capacity(items.len());
     for item in items {
-        result.push(ClipboardItem {
-            id: item.get("id"),
-            content: item.get("content"),
-            title: item.get("title"),
-            created_at: item.get("created_at"),
-            updated_at: item.get("updated_at"),
-            is_pinned: item.get::<i64, _>("is_pinned") != 0,
-        });
+        result.push(ClipboardItem::from_row(&item).map_err(|e| AppError::DatabaseError(e.to_string()))?);
     }
 
     Ok(result)
 }
 
+// 统计未同步的项目数量，user_id 为 None 时统计所有用户
+pub async fn count_unsynced(pool: &SqlitePool, user_id: Option<&str>) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "
+        SELECT COUNT(*)
+        FROM sync_status s
+        JOIN clipboard_items c ON c.id = s.item_id
+        WHERE s.is_synced = 0 AND (? IS NULL OR c.user_id = ?)
+        "
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(count)
+}
+
 // 标记项目为已同步
-pub async fn mark_item_synced(pool: &SqlitePool, id: &str) -> Result<(), DbError> {
+pub async fn mark_item_synced(pool: &SqlitePool, id: &str) -> Result<(), AppError> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -475,15 +1622,86 @@ pub async fn mark_item_synced(pool: &SqlitePool, id: &str) -> Result<(), DbError
     .bind(id)
     .execute(pool)
     .await
-    .map_err(|e| DbError::QueryError(e.to_string()))?;
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     Ok(())
 }
 
+// 标记项目为待同步（本地新增或修改后调用）
+pub async fn mark_item_unsynced(pool: &SqlitePool, id: &str) -> Result<(), AppError> {
+    let mut conn = pool.acquire()
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    mark_item_unsynced_in(&mut conn, id).await
+}
+
+// 在调用方的事务中将项目标记为待同步
+pub async fn mark_item_unsynced_in(conn: &mut sqlx::SqliteConnection, id: &str) -> Result<(), AppError> {
+    sqlx::query(
+        "
+        INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
+        VALUES (?, 0, NULL)
+        ON CONFLICT(item_id) DO UPDATE SET
+        is_synced = 0
+        "
+    )
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
+
+// 将用户的所有项目标记为已同步，缺少同步状态的项目同时补建，返回受影响的项目数
+pub async fn mark_all_synced(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+    set_all_sync_state(pool, user_id, true).await
+}
+
+// 将用户的所有项目标记为待同步，下次推送时全部重新上传
+pub async fn mark_all_unsynced(pool: &SqlitePool, user_id: &str) -> Result<u64, AppError> {
+    set_all_sync_state(pool, user_id, false).await
+}
+
+async fn set_all_sync_state(pool: &SqlitePool, user_id: &str, synced: bool) -> Result<u64, AppError> {
+    let user_id = user_id.to_string();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    // 只有标记为已同步时才记录同步时间
+    let attempt = if synced { Some(now) } else { None };
+
+    crate::util::db::with_transaction(pool, move |conn| Box::pin(async move {
+        // 带 SELECT 的 UPSERT 需要 WHERE 子句消除语法歧义
+        let result = sqlx::query(
+            "
+            INSERT INTO sync_status (item_id, is_synced, last_sync_attempt)
+            SELECT id, ?, ? FROM clipboard_items WHERE user_id = ?
+            ON CONFLICT(item_id) DO UPDATE SET
+            is_synced = excluded.is_synced,
+            last_sync_attempt = COALESCE(excluded.last_sync_attempt, sync_status.last_sync_attempt)
+            "
+        )
+        .bind(synced)
+        .bind(attempt)
+        .bind(&user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    })).await
+}
+
 // 设备管理功能
 
+// 绑定设备列表整体保存为一个 JSON 值，读改写期间持有该锁，避免并发修改丢失更新
+static BOUND_DEVICES_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
 // 获取已绑定设备列表
-pub async fn get_bound_devices(pool: &SqlitePool) -> Result<Vec<DeviceInfo>, DbError> {
+pub async fn get_bound_devices(pool: &SqlitePool) -> Result<Vec<DeviceInfo>, AppError> {
     let devices = sqlx::query(
         "
         SELECT value FROM user_settings WHERE key = 'bound_devices'
@@ -491,13 +1709,13 @@ pub async fn get_bound_devices(pool: &SqlitePool) -> Result<Vec<DeviceInfo>, DbE
     )
     .fetch_optional(pool)
     .await
-    .map_err(|e| DbError::QueryError(e.to_string()))?;
+    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
     match devices {
         Some(row) => {
             let devices_json: String = row.get("value");
             let devices: Vec<DeviceInfo> = serde_json::from_str(&devices_json)
-                .map_err(|e| DbError::QueryError(format!("Failed to parse devices: {}", e)))?;
+                .map_err(|e| AppError::DatabaseError(format!("Failed to parse devices: {}", e)))?;
             Ok(devices)
         }
         None => Ok(Vec::new()),
@@ -505,13 +1723,15 @@ pub async fn get_bound_devices(pool: &SqlitePool) -> Result<Vec<DeviceInfo>, DbE
 }
 
 // 添加绑定设备
-pub async fn add_bound_device(pool: &SqlitePool, device: DeviceInfo) -> Result<(), DbError> {
+pub async fn add_bound_device(pool: &SqlitePool, device: DeviceInfo) -> Result<(), AppError> {
+    let _guard = BOUND_DEVICES_LOCK.lock().await;
+
     // 获取当前设备列表
     let mut devices = get_bound_devices(pool).await?;
 
     // 检查设备数量限制（最多5个设备）
     if devices.len() >= 5 {
-        return Err(DbError::InvalidData("Maximum number of devices (5) reached".to_string()));
+        return Err(AppError::InvalidData("Maximum number of devices (5) reached".to_string()));
     }
 
     // 检查设备是否已存在
@@ -523,64 +1743,93 @@ pub async fn add_bound_device(pool: &SqlitePool, device: DeviceInfo) -> Result<(
         devices.push(device);
     }
 
-    // 保存设备列表
-    let devices_json = serde_json::to_string(&devices)
-        .map_err(|e| DbError::QueryError(format!("Failed to serialize devices: {}", e)))?;
-
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
-
-    sqlx::query(
-        "
-        INSERT INTO user_settings (key, value, updated_at)
-        VALUES ('bound_devices', ?, ?)
-        ON CONFLICT(key) DO UPDATE SET
-        value = excluded.value,
-        updated_at = excluded.updated_at
-        "
-    )
-    .bind(devices_json)
-    .bind(now)
-    .execute(pool)
-    .await
-    .map_err(|e| DbError::QueryError(e.to_string()))?;
-
-    Ok(())
+    save_bound_devices(pool, &devices).await
 }
 
 // 移除绑定设备
-pub async fn remove_bound_device(pool: &SqlitePool, device_id: &str) -> Result<(), DbError> {
+pub async fn remove_bound_device(pool: &SqlitePool, device_id: &str) -> Result<(), AppError> {
+    let _guard = BOUND_DEVICES_LOCK.lock().await;
+
     // 获取当前设备列表
     let mut devices = get_bound_devices(pool).await?;
 
     // 移除设备
     devices.retain(|d| d.device_id != device_id);
 
-    // 保存设备列表
-    let devices_json = serde_json::to_string(&devices)
-        .map_err(|e| DbError::QueryError(format!("Failed to serialize devices: {}", e)))?;
+    save_bound_devices(pool, &devices).await
+}
+
+// 合并重复的设备记录，保留 last_sync 最新的一条（位置取首次出现处），返回移除的记录数
+pub async fn dedupe_bound_devices(pool: &SqlitePool) -> Result<usize, AppError> {
+    let _guard = BOUND_DEVICES_LOCK.lock().await;
+
+    let devices = get_bound_devices(pool).await?;
+    let mut deduped: Vec<DeviceInfo> = Vec::with_capacity(devices.len());
+    for device in devices.iter() {
+        match deduped.iter_mut().find(|d| d.device_id == device.device_id) {
+            Some(existing) if device.last_sync > existing.last_sync => *existing = device.clone(),
+            Some(_) => {}
+            None => deduped.push(device.clone()),
+        }
+    }
+
+    let removed = devices.len() - deduped.len();
+    if removed > 0 {
+        save_bound_devices(pool, &deduped).await?;
+    }
+
+    Ok(removed)
+}
+
+// 保存设备列表，调用方需持有 BOUND_DEVICES_LOCK
+async fn save_bound_devices(pool: &SqlitePool, devices: &[DeviceInfo]) -> Result<(), AppError> {
+    let devices_json = serde_json::to_string(devices)
+        .map_err(|e| AppError::DatabaseError(format!("Failed to serialize devices: {}", e)))?;
 
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs() as i64;
 
-    sqlx::query(
-        "
-        INSERT INTO user_settings (key, value, updated_at)
-        VALUES ('bound_devices', ?, ?)
-        ON CONFLICT(key) DO UPDATE SET
-        value = excluded.value,
-        updated_at = excluded.updated_at
-        "
-    )
-    .bind(devices_json)
-    .bind(now)
-    .execute(pool)
-    .await
-    .map_err(|e| DbError::QueryError(e.to_string()))?;
+    crate::util::db::retry_on_busy(|| async {
+        sqlx::query(
+            "
+            INSERT INTO user_settings (key, value, updated_at)
+            VALUES ('bound_devices', ?, ?)
+            ON CONFLICT(key) DO UPDATE SET
+            value = excluded.value,
+            updated_at = excluded.updated_at
+            "
+        )
+        .bind(&devices_json)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(crate::util::db::write_error)
+    }).await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// 删除设备的时钟偏差记录（解绑设备时使用）
+pub async fn delete_device_skew(pool: &SqlitePool, device_id: &str) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM device_clock_skew WHERE device_id = ?")
+        .bind(device_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
+
+// 清空绑定设备列表（切换同步服务器时使用）
+pub async fn clear_bound_devices(pool: &SqlitePool) -> Result<(), AppError> {
+    let _guard = BOUND_DEVICES_LOCK.lock().await;
+
+    sqlx::query("DELETE FROM user_settings WHERE key = 'bound_devices'")
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
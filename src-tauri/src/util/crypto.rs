@@ -2,9 +2,15 @@ use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Key, Nonce
 };
+use chacha20poly1305::ChaCha20Poly1305;
+use serde::{Deserialize, Serialize};
 use argon2::{self, password_hash::{PasswordHasher, SaltString, PasswordHash, PasswordVerifier}};
 use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use rand::{Rng, thread_rng};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
 
 // 生成随机密钥
 pub fn generate_encryption_key() -> [u8; 32] {
@@ -20,43 +26,206 @@ pub fn generate_nonce() -> [u8; 12] {
     nonce
 }
 
-// 加密数据
+// 密文开头的算法标记：标记字节后跟算法 ID
+// 没有标记的旧密文均为 AES-256-GCM
+const CIPHER_TAG: u8 = 0xAE;
+const CIPHER_HEADER_LEN: usize = 2;
+
+// 支持的 AEAD 算法，两者的密钥均为 32 字节、nonce 均为 12 字节
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AeadCipher {
+    #[default]
+    Aes256Gcm,        // 桌面 CPU 通常有硬件加速
+    ChaCha20Poly1305, // 部分没有 AES 指令的 ARM 设备上更快
+}
+
+impl AeadCipher {
+    pub fn id(self) -> u8 {
+        match self {
+            AeadCipher::Aes256Gcm => 1,
+            AeadCipher::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(AeadCipher::Aes256Gcm),
+            2 => Some(AeadCipher::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn seal(self, data: &[u8], encryption_key: &[u8], nonce: &[u8; 12]) -> Result<Vec<u8>, String> {
+        if encryption_key.len() != 32 {
+            return Err("Encryption failed: invalid key length".to_string());
+        }
+        let result = match self {
+            AeadCipher::Aes256Gcm => Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(encryption_key))
+                .encrypt(Nonce::from_slice(nonce), data),
+            AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(encryption_key))
+                .encrypt(chacha20poly1305::Nonce::from_slice(nonce), data),
+        };
+        result.map_err(|e| format!("Encryption failed: {}", e))
+    }
+
+    fn open(self, encrypted_data: &[u8], encryption_key: &[u8], nonce: &[u8; 12]) -> Result<Vec<u8>, String> {
+        if encryption_key.len() != 32 {
+            return Err("Decryption failed: invalid key length".to_string());
+        }
+        let result = match self {
+            AeadCipher::Aes256Gcm => Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(encryption_key))
+                .decrypt(Nonce::from_slice(nonce), encrypted_data),
+            AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(encryption_key))
+                .decrypt(chacha20poly1305::Nonce::from_slice(nonce), encrypted_data),
+        };
+        result.map_err(|e| format!("Decryption failed: {}", e))
+    }
+}
+
+// 使用默认算法（AES-256-GCM）加密数据
 pub fn encrypt_data(data: &[u8], encryption_key: &[u8], nonce: &[u8; 12]) -> Result<Vec<u8>, String> {
-    let key = Key::<Aes256Gcm>::from_slice(encryption_key);
-    let cipher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(nonce);
+    encrypt_data_with(AeadCipher::default(), data, encryption_key, nonce)
+}
+
+// 使用指定算法加密，返回带算法标记的密文
+pub fn encrypt_data_with(
+    cipher: AeadCipher,
+    data: &[u8],
+    encryption_key: &[u8],
+    nonce: &[u8; 12]
+) -> Result<Vec<u8>, String> {
+    let sealed = cipher.seal(data, encryption_key, nonce)?;
     
-    cipher.encrypt(nonce, data)
-        .map_err(|e| format!("Encryption failed: {}", e))
+    let mut output = Vec::with_capacity(CIPHER_HEADER_LEN + sealed.len());
+    output.push(CIPHER_TAG);
+    output.push(cipher.id());
+    output.extend_from_slice(&sealed);
+    Ok(output)
 }
 
-// 解密数据
+// 解密文本数据，结果不是有效的 UTF-8 时返回错误；二进制内容使用 decrypt_data_bytes
 pub fn decrypt_data(encrypted_data: &[u8], encryption_key: &[u8], nonce: &[u8; 12]) -> Result<String, String> {
-    let key = Key::<Aes256Gcm>::from_slice(encryption_key);
-    let cipher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(nonce);
-    
-    let decrypted = cipher.decrypt(nonce, encrypted_data)
-        .map_err(|e| format!("Decryption failed: {}", e))?;
+    let decrypted = decrypt_data_bytes(encrypted_data, encryption_key, nonce)?;
     
     String::from_utf8(decrypted)
         .map_err(|e| format!("Invalid UTF-8 sequence: {}", e))
 }
 
+// 解密为原始字节（用于图片等二进制内容），按密文开头的标记选择算法
+pub fn decrypt_data_bytes(encrypted_data: &[u8], encryption_key: &[u8], nonce: &[u8; 12]) -> Result<Vec<u8>, String> {
+    let tagged = match encrypted_data {
+        [CIPHER_TAG, id, rest @ ..] => AeadCipher::from_id(*id).map(|cipher| (cipher, rest)),
+        _ => None,
+    };
+    
+    match tagged {
+        // 旧密文的第一个字节碰巧等于标记时认证会失败，再按无标记的 AES-256-GCM 解密
+        Some((cipher, rest)) => cipher.open(rest, encryption_key, nonce)
+            .or_else(|e| AeadCipher::Aes256Gcm.open(encrypted_data, encryption_key, nonce).map_err(|_| e)),
+        None => AeadCipher::Aes256Gcm.open(encrypted_data, encryption_key, nonce),
+    }
+}
+
+// 密码哈希使用的应用级密钥（pepper）来自该环境变量，不保存在数据库中；
+// 数据库泄露时没有 pepper 无法离线破解。设置后必须在所有部署中保持不变，
+// 丢失或修改 pepper 会导致已使用 pepper 哈希的密码全部无法验证
+pub const PASSWORD_PEPPER_ENV: &str = "SHARING_COPYBOARD_PEPPER";
+
+// 读取当前配置的 pepper，未设置或为空时返回 None
+pub fn password_pepper() -> Option<String> {
+    std::env::var(PASSWORD_PEPPER_ENV).ok().filter(|pepper| !pepper.is_empty())
+}
+
+// 密码与哈希的匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordMatch {
+    // 使用当前 pepper（或未配置 pepper 时不加 pepper）匹配
+    Current,
+    // 配置了 pepper，但哈希是加入 pepper 之前生成的，应重新哈希
+    Legacy,
+    Mismatch,
+}
+
 // 生成密码哈希
 pub fn hash_password(password: &str) -> Result<String, String> {
+    hash_password_with_pepper(password, password_pepper().as_deref())
+}
+
+pub fn hash_password_with_pepper(password: &str, pepper: Option<&str>) -> Result<String, String> {
     let salt = SaltString::generate(&mut thread_rng());
     let argon2 = Argon2::default();
     
-    argon2.hash_password(password.as_bytes(), &salt)
+    argon2.hash_password(&peppered(password, pepper), &salt)
         .map(|hash| hash.to_string())
         .map_err(|e| format!("Password hashing failed: {}", e))
 }
 
 // 验证密码
 pub fn verify_password(hash: &str, password: &str) -> Result<bool, String> {
+    Ok(check_password(hash, password, password_pepper().as_deref())? != PasswordMatch::Mismatch)
+}
+
+// 先按 pepper 验证，失败时再按旧哈希（无 pepper）验证，以便平滑过渡
+pub fn check_password(hash: &str, password: &str, pepper: Option<&str>) -> Result<PasswordMatch, String> {
     let parsed_hash = PasswordHash::new(hash)
         .map_err(|e| format!("Invalid password hash: {}", e))?;
+    let argon2 = Argon2::default();
     
-    Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
-}
\ No newline at end of file
+    if argon2.verify_password(&peppered(password, pepper), &parsed_hash).is_ok() {
+        return Ok(PasswordMatch::Current);
+    }
+    if pepper.is_some() && argon2.verify_password(password.as_bytes(), &parsed_hash).is_ok() {
+        return Ok(PasswordMatch::Legacy);
+    }
+    Ok(PasswordMatch::Mismatch)
+}
+
+fn peppered(password: &str, pepper: Option<&str>) -> Vec<u8> {
+    let mut bytes = password.as_bytes().to_vec();
+    if let Some(pepper) = pepper {
+        bytes.extend_from_slice(pepper.as_bytes());
+    }
+    bytes
+}
+// 生成密钥派生用的随机盐
+pub fn generate_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    thread_rng().fill(&mut salt);
+    salt
+}
+
+// 分享码的随机字节数（URL 安全 base64 编码后为 8 个字符）
+pub const SHARE_CODE_BYTES: usize = 6;
+
+// 生成分享码
+pub fn generate_share_code() -> String {
+    let mut bytes = [0u8; SHARE_CODE_BYTES];
+    thread_rng().fill(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// 使用 Argon2 从口令派生 256 位密钥
+pub fn derive_key_from_passphrase(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+// 计算证书（DER 编码）的 SHA-256 指纹，小写十六进制
+pub fn sha256_fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// 计算 HMAC-SHA256
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,82 @@
+use futures_util::future::BoxFuture;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::future::Future;
+use std::time::Duration;
+use crate::error::AppError;
+
+// 数据库繁忙时写入的最大尝试次数，以及首次重试前的等待时间（之后每次翻倍）
+pub const BUSY_RETRY_ATTEMPTS: u32 = 5;
+pub const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(20);
+
+// 在单个事务中执行闭包：闭包返回 Ok 时提交，返回 Err 时回滚
+//
+// 用法：
+// with_transaction(pool, move |conn| Box::pin(async move { ... })).await
+pub async fn with_transaction<T, F>(pool: &SqlitePool, f: F) -> Result<T, AppError>
+where
+    T: Send,
+    F: for<'c> FnOnce(&'c mut SqliteConnection) -> BoxFuture<'c, Result<T, AppError>>,
+{
+    // 开始和提交事务时的繁忙错误同样区分出来，调用方可以用 retry_on_busy 整体重试
+    let mut tx = pool.begin()
+        .await
+        .map_err(write_error)?;
+    
+    match f(&mut tx).await {
+        Ok(value) => {
+            tx.commit()
+                .await
+                .map_err(write_error)?;
+            Ok(value)
+        }
+        Err(e) => {
+            // 回滚失败时仍返回原始错误，连接归还连接池时也会自动回滚
+            let _ = tx.rollback().await;
+            Err(e)
+        }
+    }
+}
+
+// 是否为其他连接持有锁导致的暂时性错误
+// 扩展错误码的低 8 位为主错误码：5 = SQLITE_BUSY，6 = SQLITE_LOCKED
+pub fn is_busy_error(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(db) => db.code()
+            .and_then(|code| code.parse::<i32>().ok())
+            .is_some_and(|code| matches!(code & 0xff, 5 | 6)),
+        _ => false,
+    }
+}
+
+// 转换写入错误，繁忙错误单独区分以便重试
+pub fn write_error(e: sqlx::Error) -> AppError {
+    if is_busy_error(&e) {
+        AppError::DatabaseBusy(e.to_string())
+    } else {
+        AppError::DatabaseError(e.to_string())
+    }
+}
+
+// 数据库繁忙时按指数退避重试写入，其他错误立即返回
+//
+// 用法：
+// retry_on_busy(|| async move { sqlx::query(...).execute(pool).await.map_err(write_error) }).await
+pub async fn retry_on_busy<T, F, Fut>(mut f: F) -> Result<T, AppError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let mut delay = BUSY_RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    
+    loop {
+        match f().await {
+            Err(AppError::DatabaseBusy(_)) if attempt < BUSY_RETRY_ATTEMPTS => {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
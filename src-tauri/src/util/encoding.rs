@@ -0,0 +1,58 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::{DecodeError, Engine};
+use crate::error::AppError;
+
+// 标准 base64 编码（带填充），替代已弃用的 base64::encode
+pub fn encode(bytes: impl AsRef<[u8]>) -> String {
+    STANDARD.encode(bytes)
+}
+
+// 标准 base64 解码，替代已弃用的 base64::decode
+pub fn decode(content: impl AsRef<[u8]>) -> Result<Vec<u8>, DecodeError> {
+    STANDARD.decode(content)
+}
+
+// 已校验的 base64 内容，保存解码后的字节
+// content 列中明文和 base64 混存，需要按 base64 处理的内容（密文、二进制）先经过 parse 校验
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Base64Content(Vec<u8>);
+
+impl Base64Content {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    // 校验并解码，非 base64 内容返回 InvalidData，错误中包含出错的位置
+    pub fn parse(content: &str) -> Result<Self, AppError> {
+        decode(content)
+            .map(Self)
+            .map_err(|e| AppError::InvalidData(format!("内容不是有效的 base64: {}", e)))
+    }
+
+    // 解析加密内容 base64(nonce + 密文)：非 base64 或长度不足 min_len 字节时返回 DecryptionFailed
+    pub fn parse_ciphertext(content: &str, min_len: usize) -> Result<Self, AppError> {
+        let bytes = decode(content)
+            .map_err(|e| AppError::DecryptionFailed(format!("加密内容不是有效的 base64: {}", e)))?;
+
+        if bytes.len() < min_len {
+            return Err(AppError::DecryptionFailed(format!(
+                "加密内容长度不足: {} 字节，至少需要 {} 字节", bytes.len(), min_len
+            )));
+        }
+
+        Ok(Self(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    // 编码为 content 列中保存的字符串
+    pub fn encode(&self) -> String {
+        encode(&self.0)
+    }
+}
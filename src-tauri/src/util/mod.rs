@@ -1 +1,8 @@
-pub mod crypto;
\ No newline at end of file
+pub mod crypto;
+pub mod validation;
+pub mod text;
+pub mod source_app;
+pub mod db;
+pub mod transform;
+pub mod encoding;
+pub mod thumbnail;
@@ -0,0 +1,58 @@
+// 获取当前前台应用名称，用于记录剪贴板内容的来源
+// 仅在平台提供查询方式时返回结果，其他情况返回 None
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+pub fn foreground_app_name() -> Option<String> {
+    let output = Command::new("osascript")
+        .args([
+            "-e",
+            "tell application \"System Events\" to get name of first application process whose frontmost is true",
+        ])
+        .output()
+        .ok()?;
+    
+    non_empty(&output.stdout)
+}
+
+#[cfg(target_os = "linux")]
+pub fn foreground_app_name() -> Option<String> {
+    // 依赖 xdotool（X11），Wayland 下通常不可用
+    let output = Command::new("xdotool")
+        .args(["getactivewindow", "getwindowpid"])
+        .output()
+        .ok()?;
+    
+    let pid = non_empty(&output.stdout)?;
+    let comm = std::fs::read(format!("/proc/{}/comm", pid)).ok()?;
+    non_empty(&comm)
+}
+
+#[cfg(target_os = "windows")]
+pub fn foreground_app_name() -> Option<String> {
+    let script = "Add-Type '[DllImport(\"user32.dll\")] public static extern System.IntPtr GetForegroundWindow(); \
+        [DllImport(\"user32.dll\")] public static extern int GetWindowThreadProcessId(System.IntPtr h, out int p);' \
+        -Name W -Namespace N; $p = 0; [void][N.W]::GetWindowThreadProcessId([N.W]::GetForegroundWindow(), [ref]$p); \
+        (Get-Process -Id $p).ProcessName";
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", script])
+        .output()
+        .ok()?;
+    
+    non_empty(&output.stdout)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub fn foreground_app_name() -> Option<String> {
+    None
+}
+
+#[allow(dead_code)]
+fn non_empty(bytes: &[u8]) -> Option<String> {
+    let value = String::from_utf8_lossy(bytes).trim().to_string();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
@@ -0,0 +1,144 @@
+use regex::Regex;
+
+// 零宽字符：零宽空格、零宽非连接符、零宽连接符、字词连接符、BOM
+const ZERO_WIDTH_CHARS: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+// 清理文本内容：去除控制字符（保留换行和制表符）与零宽字符，并去掉末尾空白
+pub fn sanitize_text(content: &str) -> String {
+    let cleaned: String = content
+        .chars()
+        .filter(|c| {
+            if *c == '\n' || *c == '\t' {
+                return true;
+            }
+            !c.is_control() && !ZERO_WIDTH_CHARS.contains(c)
+        })
+        .collect();
+    
+    cleaned.trim_end().to_string()
+}
+
+// 是否为文本类型内容
+pub fn is_text_content_type(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+}
+
+// 是否为代码类内容（源码、JSON、XML 等）
+pub fn is_code_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    mime.starts_with("text/x-")
+        || matches!(
+            mime.as_str(),
+            "application/json" | "application/xml" | "application/javascript"
+                | "text/javascript" | "text/css" | "text/xml"
+        )
+}
+
+// 附着在前一个字符上、不单独占位的字符：组合附加符号、变体选择符、肤色修饰符和零宽连接符
+fn is_extending_char(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{200D}'
+    )
+}
+
+// 按可见字符截断，保留 max_chars 个可见字符，截断时末尾追加省略号
+// 只在字符边界切分，不会切开 UTF-8 多字节字符；组合符号和 ZWJ 连接的 emoji 序列视为一个可见字符
+pub fn truncate_chars(content: &str, max_chars: usize) -> String {
+    let mut visible = 0;
+    let mut after_joiner = false;
+    
+    for (index, c) in content.char_indices() {
+        let starts_new = !is_extending_char(c) && !after_joiner;
+        after_joiner = c == '\u{200D}';
+        
+        if starts_new {
+            if visible == max_chars {
+                return format!("{}…", &content[..index]);
+            }
+            visible += 1;
+        }
+    }
+    
+    content.to_string()
+}
+
+// 是否疑似包含敏感信息：银行卡号（13-19 位数字且通过 Luhn 校验）或美国社会安全号（XXX-XX-XXXX）
+pub fn looks_sensitive(content: &str) -> bool {
+    // 按数字、空格和连字符组成的连续片段检查
+    content
+        .split(|c: char| !(c.is_ascii_digit() || c == ' ' || c == '-'))
+        .map(|run| run.trim_matches(|c| c == ' ' || c == '-'))
+        .filter(|run| !run.is_empty())
+        .any(|run| contains_card_number(run) || run.split(' ').any(is_ssn))
+}
+
+fn contains_card_number(run: &str) -> bool {
+    let digits: Vec<u32> = run.chars().filter_map(|c| c.to_digit(10)).collect();
+    (13..=19).contains(&digits.len()) && luhn_valid(&digits)
+}
+
+fn luhn_valid(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                *d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn is_ssn(token: &str) -> bool {
+    let parts: Vec<&str> = token.split('-').collect();
+    parts.len() == 3
+        && parts.iter().zip([3, 2, 4]).all(|(part, len)| {
+            part.len() == len && part.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+// 去除日志文本中可能的隐私和密钥：邮箱、URL 中的账号密码、较长的令牌或密文片段
+pub fn redact_secrets(message: &str) -> String {
+    let rules = [
+        (r"://[^/\s@]+@", "://<redacted>@"),
+        (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "<email>"),
+        (r"[A-Za-z0-9+/_=-]{20,}", "<redacted>"),
+    ];
+    
+    rules.iter().fold(message.to_string(), |text, (pattern, replacement)| {
+        match Regex::new(pattern) {
+            Ok(regex) => regex.replace_all(&text, *replacement).into_owned(),
+            Err(_) => text,
+        }
+    })
+}
+
+// 拆分搜索词：按非字母数字字符分词并转为小写；非 ASCII 字符（如中文）额外按单字拆分，
+// 以便在没有分词器的情况下按字匹配
+pub fn search_tokens(content: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    
+    for word in content.split(|c: char| !c.is_alphanumeric()) {
+        if word.is_empty() {
+            continue;
+        }
+        
+        if word.is_ascii() {
+            tokens.push(word.to_lowercase());
+        } else {
+            tokens.extend(word.chars().map(|c| c.to_lowercase().collect::<String>()));
+        }
+    }
+    
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
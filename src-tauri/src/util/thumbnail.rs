@@ -0,0 +1,21 @@
+use std::io::Cursor;
+use image::ImageFormat;
+
+// 缩略图的最大边长（像素），按原比例缩放
+pub const THUMBNAIL_MAX_SIZE: u32 = 128;
+
+// 生成 PNG 缩略图，宽高都不超过 max_size
+// 无法解码的内容返回 None；原图不超过 max_size 时直接使用原图，同样返回 None
+pub fn generate(bytes: &[u8], max_size: u32) -> Option<Vec<u8>> {
+    let image = image::load_from_memory(bytes).ok()?;
+    if image.width() <= max_size && image.height() <= max_size {
+        return None;
+    }
+
+    let mut output = Cursor::new(Vec::new());
+    image
+        .thumbnail(max_size, max_size)
+        .write_to(&mut output, ImageFormat::Png)
+        .ok()?;
+    Some(output.into_inner())
+}
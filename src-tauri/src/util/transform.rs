@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use crate::entity::content_type::ContentType;
+use crate::error::AppError;
+use crate::util::text;
+
+// 复制项目时对内容做的转换
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    // 去除格式：HTML 去掉标签并解码常见实体，其他文本清理控制字符和零宽字符
+    Plain,
+    Lowercase,
+    Uppercase,
+    // 百分号编码解码（不把 + 视为空格）
+    UrlDecode,
+    // 去掉每行首尾空白并删除空行
+    TrimLines,
+}
+
+// 按内容类型应用转换；图片等二进制内容不支持任何转换
+pub fn apply(transform: Transform, content_type: &str, content: &str) -> Result<String, AppError> {
+    if ContentType::from_mime(content_type).is_binary() {
+        return Err(AppError::InvalidData(format!("{} 类型的内容不支持转换", content_type)));
+    }
+    
+    match transform {
+        Transform::Plain => Ok(if is_html(content_type) {
+            text::sanitize_text(&strip_html(content))
+        } else {
+            text::sanitize_text(content)
+        }),
+        Transform::Lowercase => Ok(content.to_lowercase()),
+        Transform::Uppercase => Ok(content.to_uppercase()),
+        Transform::UrlDecode => url_decode(content),
+        Transform::TrimLines => Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
+
+fn is_html(content_type: &str) -> bool {
+    content_type.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("text/html")
+}
+
+// 去掉 HTML 标签，块级换行标签转为换行，再解码常见实体
+fn strip_html(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut tag: Option<String> = None;
+    
+    for c in html.chars() {
+        match (&mut tag, c) {
+            (None, '<') => tag = Some(String::new()),
+            (None, c) => output.push(c),
+            (Some(name), '>') => {
+                let name = name.trim_start_matches('/').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+                if matches!(name.trim_end_matches('/'), "br" | "p" | "div" | "li" | "tr") && !output.is_empty() && !output.ends_with('\n') {
+                    output.push('\n');
+                }
+                tag = None;
+            }
+            (Some(name), c) => name.push(c),
+        }
+    }
+    
+    output
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn url_decode(content: &str) -> Result<String, AppError> {
+    let bytes = content.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| AppError::InvalidData(format!("无效的百分号编码（位置 {}）", i)))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    
+    String::from_utf8(decoded)
+        .map_err(|_| AppError::InvalidData("解码结果不是有效的 UTF-8 文本".to_string()))
+}
@@ -0,0 +1,126 @@
+use crate::entity::content_type::ContentType;
+use crate::error::AppError;
+use crate::util::encoding;
+
+// URL 内容的最大长度
+pub const MAX_URL_LENGTH: usize = 2048;
+// 单个项目内容的最大长度（字节，图片按 base64 编码后计算）
+pub const MAX_CONTENT_BYTES: usize = 16 * 1024 * 1024;
+
+const PNG_MAGIC: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const JPEG_MAGIC: &[u8] = &[0xFF, 0xD8, 0xFF];
+
+// 邮箱格式校验（宽松版 RFC 规则）：
+// - 恰好一个 @，本地部分不超过 64 字符，总长度不超过 254 字符
+// - 本地部分不能以 . 开头或结尾，不能包含连续的 . 和特殊符号
+// - 域名至少包含两段，每段由字母数字（含 Unicode）和 - 组成，且不以 - 开头或结尾
+pub fn is_valid_email(email: &str) -> bool {
+    if email.chars().count() > 254 {
+        return false;
+    }
+    
+    let (local, domain) = match email.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    
+    if local.is_empty() || local.chars().count() > 64 || domain.contains('@') {
+        return false;
+    }
+    
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return false;
+    }
+    
+    let local_valid = local.chars().all(|c| {
+        !c.is_whitespace() && !c.is_control() && !"()<>[]:;,\\\"".contains(c)
+    });
+    if !local_valid {
+        return false;
+    }
+    
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return false;
+    }
+    
+    labels.iter().all(|label| {
+        !label.is_empty()
+            && label.chars().count() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    })
+}
+
+pub fn validate_email(email: &str) -> Result<(), AppError> {
+    if is_valid_email(email) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidData("邮箱格式不正确".to_string()))
+    }
+}
+
+// 规范化证书指纹：去掉冒号和空白并转为小写，必须是 64 位十六进制（SHA-256）
+pub fn normalize_fingerprint(fingerprint: &str) -> Result<String, AppError> {
+    let normalized: String = fingerprint
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    
+    if normalized.len() != 64 || !normalized.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::InvalidData("证书指纹必须是 SHA-256 十六进制字符串".to_string()));
+    }
+    
+    Ok(normalized)
+}
+
+// 校验内容是否与声明的类型一致：图片内容为 base64 编码且文件头匹配，URL 内容必须可解析且不超长
+pub fn validate_content(content_type: &str, content: &str) -> Result<(), AppError> {
+    if content.len() > MAX_CONTENT_BYTES {
+        return Err(AppError::InvalidData(format!("内容不能超过 {} 字节", MAX_CONTENT_BYTES)));
+    }
+    
+    match ContentType::from_mime(content_type) {
+        ContentType::Png => validate_image(content, &[PNG_MAGIC]),
+        ContentType::Jpeg => validate_image(content, &[JPEG_MAGIC]),
+        ContentType::Image => validate_image(content, &[PNG_MAGIC, JPEG_MAGIC]),
+        ContentType::Url => validate_url_list(content),
+        ContentType::Text | ContentType::Other => Ok(()),
+    }
+}
+
+fn validate_image(content: &str, magics: &[&[u8]]) -> Result<(), AppError> {
+    let bytes = encoding::decode(content.trim())
+        .map_err(|_| AppError::InvalidData("图片内容必须是 base64 编码".to_string()))?;
+    
+    if magics.iter().any(|magic| bytes.starts_with(magic)) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidData("图片内容与声明的类型不符".to_string()))
+    }
+}
+
+// text/uri-list：每行一个 URL，以 # 开头的行为注释
+fn validate_url_list(content: &str) -> Result<(), AppError> {
+    let urls: Vec<&str> = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    
+    if urls.is_empty() {
+        return Err(AppError::InvalidData("URL 内容不能为空".to_string()));
+    }
+    
+    for url in urls {
+        if url.len() > MAX_URL_LENGTH {
+            return Err(AppError::InvalidData(format!("URL 长度不能超过 {} 个字符", MAX_URL_LENGTH)));
+        }
+        url::Url::parse(url)
+            .map_err(|e| AppError::InvalidData(format!("无效的 URL: {}", e)))?;
+    }
+    
+    Ok(())
+}